@@ -58,7 +58,8 @@ export const useNativeRecording = () => {
 		pipShape?: number,
 		pipX?: number,
 		pipY?: number,
-		pipSize?: number
+		pipSize?: number,
+		audioSources?: { device_id: string; gain_db?: number }[]
 	): Promise<string | null> => {
 		if (!isTauri()) {
 			error.value = 'Native recording only available in desktop app'
@@ -81,6 +82,7 @@ export const useNativeRecording = () => {
 				pipX: pipX || 0.8,
 				pipY: pipY || 0.8,
 				pipSize: pipSize || 0.2,
+				audioSources,
 				outputPath: path
 			})
 
@@ -4,18 +4,21 @@ import { ref } from 'vue'
  * Composables for managing window state during recording
  * - Auto-minimize when recording starts
  * - Auto-restore when recording stops
- * - Global keyboard shortcut (Cmd/Ctrl+Shift+S)
+ * - Global keyboard shortcut (held by Rust while recording, Cmd/Ctrl+Shift+S by default)
  * - System tray integration
+ * - Native/ISO captures stopped from Rust (shortcut, tray, limits) arrive as "recording-stopped"
+ * - Pauses from the tray or shortcut arrive as recorder state changes
  */
 
-const STOP_SHORTCUT = 'CommandOrControl+Shift+S' // Works on Mac (Cmd) and Windows/Linux (Ctrl)
-
 export const useRecordingWindow = () => {
 	const isMinimized = ref(false)
 	const globalShortcutRegistered = ref(false)
-	const stopRecordingCallback = ref<(() => void) | null>(null)
+	const shortcutUnlisten = ref<(() => void) | null>(null)
+	const stopRecordingCallback = ref<((stoppedPath?: string) => void) | null>(null)
 	const trayListenerRegistered = ref(false)
 	const trayUnlisten = ref<(() => void) | null>(null)
+	const stoppedUnlisten = ref<(() => void) | null>(null)
+	const pauseUnlisten = ref<(() => void) | null>(null)
 
 	/**
 	 * Minimize the app window
@@ -85,23 +88,25 @@ export const useRecordingWindow = () => {
 	}
 
 	/**
-	 * Register global keyboard shortcut for stopping recording
+	 * Listen for the toggle-recording global shortcut. The binding itself is
+	 * registered in Rust and can be changed with the rebind_shortcut command.
 	 */
-	const registerGlobalShortcut = async (onStop: () => void) => {
+	const registerGlobalShortcut = async (onStop: (stoppedPath?: string) => void) => {
 		if (typeof window === 'undefined' || !('__TAURI__' in window)) {
 			return
 		}
 
 		try {
-			const { register, unregister, unregisterAll } = await import('@tauri-apps/plugin-global-shortcut')
+			const { listen } = await import('@tauri-apps/api/event')
 
-			// Unregister any existing shortcut first
-			if (globalShortcutRegistered.value) {
-				await unregisterAll()
+			if (shortcutUnlisten.value) {
+				shortcutUnlisten.value()
 			}
 
-			// Register the stop shortcut
-			await register(STOP_SHORTCUT, async () => {
+			shortcutUnlisten.value = await listen<{ action: string, accelerator: string }>('global-shortcut', async (event) => {
+				if (event.payload.action !== 'toggle_recording') {
+					return
+				}
 				console.log('🛑 Global shortcut pressed - stopping recording')
 				// Call stop recording (this will also restore via teardownRecordingMode)
 				await onStop()
@@ -111,39 +116,27 @@ export const useRecordingWindow = () => {
 
 			globalShortcutRegistered.value = true
 			stopRecordingCallback.value = onStop
-			console.log('✅ Global shortcut registered:', STOP_SHORTCUT)
+			console.log('✅ Listening for global stop shortcut')
 		} catch (error) {
-			console.error('Failed to register global shortcut:', error)
+			console.error('Failed to listen for global shortcut:', error)
 		}
 	}
 
 	/**
-	 * Unregister global shortcut
+	 * Stop listening for the global shortcut
 	 */
 	const unregisterGlobalShortcut = async () => {
-		if (typeof window === 'undefined' || !('__TAURI__' in window)) {
-			return
-		}
-
 		if (!globalShortcutRegistered.value) {
 			return
 		}
 
-		try {
-			const { unregister, unregisterAll } = await import('@tauri-apps/plugin-global-shortcut')
-			// Try to unregister specific shortcut first, then unregister all as fallback
-			try {
-				await unregister(STOP_SHORTCUT)
-			} catch {
-				// If specific unregister fails, unregister all
-				await unregisterAll()
-			}
-			globalShortcutRegistered.value = false
-			stopRecordingCallback.value = null
-			console.log('✅ Global shortcut unregistered')
-		} catch (error) {
-			console.error('Failed to unregister global shortcut:', error)
+		if (shortcutUnlisten.value) {
+			shortcutUnlisten.value()
+			shortcutUnlisten.value = null
 		}
+		globalShortcutRegistered.value = false
+		stopRecordingCallback.value = null
+		console.log('✅ Global shortcut listener removed')
 	}
 
 	/**
@@ -169,9 +162,10 @@ export const useRecordingWindow = () => {
 	}
 
 	/**
-	 * Setup: minimize window, register shortcut, show notification
+	 * Setup: minimize window, register shortcut, show notification.
+	 * Captures the frontend runs itself pass onPauseChange to follow pause/resume.
 	 */
-	const setupRecordingMode = async (onStop: () => void) => {
+	const setupRecordingMode = async (onStop: (stoppedPath?: string) => void, onPauseChange?: (paused: boolean) => void) => {
 		if (typeof window === 'undefined' || !('__TAURI__' in window)) {
 			return
 		}
@@ -186,6 +180,14 @@ export const useRecordingWindow = () => {
 			
 			// Setup tray listener AFTER callback is registered
 			await setupTrayListener(onStop)
+			await setupStoppedListener()
+			if (onPauseChange) {
+				await setupPauseListener(onPauseChange)
+			}
+
+			// Let the tray, shortcuts and control API see the web capture
+			const { invoke } = await import('@tauri-apps/api/core')
+			await invoke('report_recording', { recording: true })
 			
 			// Minimize window
 			await minimizeWindow()
@@ -209,6 +211,17 @@ export const useRecordingWindow = () => {
 		try {
 			// Cleanup tray listener
 			await cleanupTrayListener()
+			if (stoppedUnlisten.value) {
+				stoppedUnlisten.value()
+				stoppedUnlisten.value = null
+			}
+			if (pauseUnlisten.value) {
+				pauseUnlisten.value()
+				pauseUnlisten.value = null
+			}
+
+			const { invoke } = await import('@tauri-apps/api/core')
+			await invoke('report_recording', { recording: false })
 			
 			// Unregister global shortcut
 			await unregisterGlobalShortcut()
@@ -227,7 +240,7 @@ export const useRecordingWindow = () => {
 	 * Setup tray event listener
 	 * Must be called AFTER stopRecordingCallback is set
 	 */
-	const setupTrayListener = async (onStop: () => void) => {
+	const setupTrayListener = async (onStop: (stoppedPath?: string) => void) => {
 		if (typeof window === 'undefined' || !('__TAURI__' in window)) {
 			return
 		}
@@ -266,6 +279,53 @@ export const useRecordingWindow = () => {
 		}
 	}
 
+	/**
+	 * Listen for a recording Rust has already stopped; the callback gets its path
+	 * and only finishes up instead of stopping again
+	 */
+	const setupStoppedListener = async () => {
+		if (stoppedUnlisten.value) {
+			return
+		}
+
+		try {
+			const { listen } = await import('@tauri-apps/api/event')
+			stoppedUnlisten.value = await listen<{ output_path: string }>('recording-stopped', async (event) => {
+				console.log('🛑 Recording stopped from Rust:', event.payload.output_path)
+				if (stopRecordingCallback.value) {
+					await stopRecordingCallback.value(event.payload.output_path)
+					await restoreWindow()
+				}
+			})
+		} catch (err) {
+			console.error('Failed to listen for recording-stopped:', err)
+		}
+	}
+
+	/**
+	 * Follow the recorder into and out of "paused"
+	 */
+	const setupPauseListener = async (onPauseChange: (paused: boolean) => void) => {
+		if (pauseUnlisten.value) {
+			return
+		}
+
+		try {
+			const { listen } = await import('@tauri-apps/api/event')
+			let paused = false
+			pauseUnlisten.value = await listen<{ state: string }>('recorder-state-changed', (event) => {
+				const nowPaused = event.payload.state === 'paused'
+				if (nowPaused !== paused && (nowPaused || event.payload.state === 'recording')) {
+					paused = nowPaused
+					console.log(paused ? '⏸️ Recording paused' : '▶️ Recording resumed')
+					onPauseChange(paused)
+				}
+			})
+		} catch (err) {
+			console.error('Failed to listen for recorder state changes:', err)
+		}
+	}
+
 	/**
 	 * Cleanup tray listener
 	 */
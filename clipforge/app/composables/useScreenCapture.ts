@@ -113,9 +113,10 @@ export const useScreenCapture = () => {
 				// Setup recording mode: minimize window, register global shortcut, show notification
 				// Only do this AFTER permissions are confirmed (so permission dialog can appear if needed)
 				try {
-					await setupRecordingMode(() => {
-						// Callback to stop recording when shortcut is pressed
-						stopRecording()
+					await setupRecordingMode((stoppedPath) => {
+						// Callback to stop recording when shortcut is pressed, or to finish
+						// one Rust already stopped
+						stopRecording(stoppedPath)
 					})
 					console.log('✅ Window minimized and shortcuts registered')
 				} catch (err) {
@@ -302,6 +303,15 @@ export const useScreenCapture = () => {
 			await setupRecordingMode(() => {
 				// Callback to stop recording when shortcut is pressed
 				stopRecording()
+			}, (paused) => {
+				// Paused from the tray or shortcut
+				for (const recorder of [screenRecorder, webcamRecorder]) {
+					if (paused && recorder?.state === 'recording') {
+						recorder.pause()
+					} else if (!paused && recorder?.state === 'paused') {
+						recorder.resume()
+					}
+				}
 			})
 		} catch (err) {
 			console.warn('⚠️ Failed to setup recording window mode:', err)
@@ -323,13 +333,22 @@ export const useScreenCapture = () => {
 		}
 	}
 
-	const stopRecording = async () => {
+	// Set while a native stop is being finished, so the "recording-stopped" event
+	// our own stop_screen_recording call triggers doesn't finish it a second time
+	let stoppingNative = false
+
+	const stopRecording = async (stoppedPath?: string) => {
 		// Handle native recording stop (Tauri)
 		if (isTauri()) {
+			if (stoppingNative) {
+				return null
+			}
+			stoppingNative = true
 			try {
 				const { invoke } = await import('@tauri-apps/api/core')
 				const { stopRecording: stopNative } = (await import('./useNativeRecording')).useNativeRecording()
-				const outputPath = await stopNative()
+				// Rust already stopped it (shortcut, tray, limit); just pick up the file
+				const outputPath = stoppedPath ?? await stopNative()
 				
 				if (outputPath) {
 					console.log('✅ Native recording stopped:', outputPath)
@@ -491,6 +510,8 @@ export const useScreenCapture = () => {
 				}
 				
 				return null
+			} finally {
+				stoppingNative = false
 			}
 		}
 		
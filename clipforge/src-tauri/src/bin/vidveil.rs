@@ -0,0 +1,424 @@
+// Headless front end to the export pipeline, for scripts and CI.
+// Events go to stderr as one JSON object per line. The library logs with println!,
+// so stdout is log output only and can be ignored or kept for debugging.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+
+use serde_json::{json, Map, Value};
+
+use nuxtor::commands::audio_devices::{self, AudioSource};
+use nuxtor::commands::export_settings::ExportSettings;
+use nuxtor::commands::ffmpeg::{self, RunControl};
+use nuxtor::commands::ffmpeg_capture::{self, CaptureOptions};
+use nuxtor::commands::ffmpeg_export;
+use nuxtor::commands::output_paths::{self, TemplateValues};
+use nuxtor::commands::preview;
+use nuxtor::commands::project::Project;
+use nuxtor::commands::video_devices;
+use nuxtor::commands::video_processing::VideoClip;
+
+const USAGE: &str = "Usage: vidveil <command> [options]
+
+Options take a value as `--name value` or `--name=value`; -h, --help prints this.
+Events are written to stderr as JSON lines, log output to stdout.
+
+Commands:
+  probe <media>                     Print duration, size and audio presence
+  export <project.json|clips.json>  Render a project or clip list
+      -o, --output <path>           Output path from the working directory; the file name may
+                                    be a template (default: configured template and export dir)
+      --settings <file.json>        ExportSettings to start from
+      --preset <name>               Named export preset (web, youtube, gif, podcast, ...)
+      --format <container>          mp4, mov, webm, mkv, gif, webp, mp3, m4a, wav
+      --resolution <size>           source, 2160p, 1080p, 720p, ... or WIDTHxHEIGHT
+      --aspect <ratio>              16:9, 9:16, 1:1 or 4:5
+      --fit <mode>                  fit, fill or letterbox
+      --background <color>          Letterbox color
+      --quality <level>             high, medium or low
+      --speed <preset>              Encoder preset, ultrafast .. veryslow
+      --codec <codec>               h264, hevc, vp9 or av1
+      --crf <n> | --bitrate <kbps>  Rate control
+      --fps <rate>                  Output frame rate
+      --audio-codec <codec>         aac, opus, mp3 or pcm
+      --audio-bitrate <kbps>        Audio bitrate
+  thumbnail <media>                 Save a poster frame
+      -o, --output <path>  --time <seconds>  --width <px>
+  waveform <media>                  Render the audio waveform as an image
+      -o, --output <path>  --width <px>  --height <px>  --color <color>
+  record                            Record the screen through ffmpeg
+      -o, --output <path>  --duration <seconds>  --fps <rate>  --display <name>
+      --audio <id[@gain_db],...>    Audio devices to mix in, e.g. default@-3,sink.monitor
+      --cursor <show|hide>          Draw the mouse pointer (default show)
+  audio-devices                     List audio capture devices and their ids
+  video-devices                     List cameras with their formats (VIDVEIL_FAKE_WEBCAM adds a file-backed one)";
+
+// Options that take no value
+const BOOLEAN_FLAGS: &[&str] = &["help"];
+
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(raw: &[String]) -> Result<Args, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+
+        let mut iter = raw.iter().peekable();
+
+        while let Some(arg) = iter.next() {
+            let name = match arg.as_str() {
+                "-o" => "output",
+                "-h" => "help",
+                _ => match arg.strip_prefix("--") {
+                    Some(name) => name,
+                    None => {
+                        positional.push(arg.clone());
+                        continue;
+                    }
+                },
+            };
+            if let Some((name, value)) = name.split_once('=') {
+                options.insert(name.to_string(), value.to_string());
+                continue;
+            }
+            if BOOLEAN_FLAGS.contains(&name) {
+                options.insert(name.to_string(), "true".to_string());
+                continue;
+            }
+            // Anything but another option is the value, so "--time -3" still works
+            let value = iter
+                .next_if(|next| !next.starts_with('-') || next.parse::<f64>().is_ok())
+                .ok_or_else(|| format!("Missing value for {}", arg))?;
+            options.insert(name.to_string(), value.clone());
+        }
+
+        Ok(Args { positional, options })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|v| v.as_str())
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.get(name)
+            .map(|v| v.parse::<T>().map_err(|_| format!("--{} expects a number, got '{}'", name, v)))
+            .transpose()
+    }
+
+    fn input(&self) -> Result<&str, String> {
+        let input = self.positional.first().ok_or("Missing input file")?;
+        if !Path::new(input).exists() {
+            return Err(format!("File does not exist: {}", input));
+        }
+        Ok(input)
+    }
+
+    fn reject_unknown(&self, known: &[&str]) -> Result<(), String> {
+        match self.options.keys().find(|k| !known.contains(&k.as_str()) && !BOOLEAN_FLAGS.contains(&k.as_str())) {
+            Some(unknown) => Err(format!("Unknown option --{}", unknown)),
+            None => Ok(()),
+        }
+    }
+}
+
+fn emit(event: Value) {
+    eprintln!("{}", event);
+}
+
+fn progress_reporter(stage: &'static str) -> impl Fn(f64) {
+    move |progress: f64| emit(json!({ "event": "progress", "stage": stage, "progress": (progress * 1000.0).round() / 1000.0 }))
+}
+
+fn main() -> ExitCode {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let Some(command) = raw.first() else {
+        println!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = Args::parse(&raw[1..]).and_then(|args| match command.as_str() {
+        _ if args.flag("help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        "probe" => probe(&args),
+        "export" => export(&args),
+        "thumbnail" => thumbnail(&args),
+        "waveform" => waveform(&args),
+        "record" => record(&args),
+        "audio-devices" => list_audio_devices(&args),
+        "video-devices" => list_video_devices(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(format!("Unknown command '{}'\n\n{}", other, USAGE)),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            emit(json!({ "event": "error", "message": message }));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn probe(args: &Args) -> Result<(), String> {
+    args.reject_unknown(&[])?;
+    let input = args.input()?;
+    let summary = ffmpeg::probe(input)?;
+    let dimensions = ffmpeg::video_dimensions(input).ok();
+
+    emit(json!({
+        "event": "probe",
+        "path": input,
+        "duration": ffmpeg::parse_duration(&summary),
+        "width": dimensions.map(|d| d.0),
+        "height": dimensions.map(|d| d.1),
+        "has_audio": ffmpeg::has_audio_stream(input)?,
+    }));
+    Ok(())
+}
+
+fn export(args: &Args) -> Result<(), String> {
+    args.reject_unknown(&[
+        "output", "settings", "preset", "format", "container", "resolution", "aspect", "fit", "background",
+        "quality", "speed", "codec", "crf", "bitrate", "fps", "audio-codec", "audio-bitrate",
+    ])?;
+    let input = args.input()?;
+    let (project_name, clips) = load_timeline(input)?;
+    let settings = export_settings(args)?;
+    let encode = settings.resolve()?;
+
+    let values = TemplateValues {
+        project: Some(project_name),
+        resolution: Some(encode.frame.label()),
+        preset: settings.export_preset.clone(),
+        ext: encode.container.extension().to_string(),
+    };
+    let output_path = resolve_output(args.get("output"), &values)?;
+    emit(json!({ "event": "start", "stage": "export", "output_path": output_path }));
+
+    let cancelled = AtomicBool::new(false);
+    let on_progress = progress_reporter("export");
+    let control = RunControl {
+        cancelled: &cancelled,
+        on_progress: &on_progress,
+    };
+    let output_path = ffmpeg_export::render_to_file(&clips, &settings, &encode, &output_path, Some(&control))?;
+
+    emit(json!({ "event": "done", "stage": "export", "output_path": output_path }));
+    Ok(())
+}
+
+// A project file, or a bare JSON array of clips
+fn load_timeline(path: &str) -> Result<(String, Vec<VideoClip>), String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let value: Value = serde_json::from_str(&content).map_err(|e| format!("Invalid JSON in {}: {}", path, e))?;
+
+    if value.is_array() {
+        let clips: Vec<VideoClip> =
+            serde_json::from_value(value).map_err(|e| format!("Invalid clip list {}: {}", path, e))?;
+        let name = Path::new(path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        Ok((name, clips))
+    } else {
+        let project = Project::load(path)?;
+        Ok((project.name, project.clips))
+    }
+}
+
+// Start from --settings (if any) and let individual flags override its fields
+fn export_settings(args: &Args) -> Result<ExportSettings, String> {
+    let mut fields = match args.get("settings") {
+        Some(path) => {
+            let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            match serde_json::from_str::<Value>(&content) {
+                Ok(Value::Object(fields)) => fields,
+                _ => return Err(format!("{} must contain an ExportSettings object", path)),
+            }
+        }
+        None => Map::new(),
+    };
+
+    let text_fields = [
+        ("preset", "export_preset"),
+        ("format", "container"),
+        ("container", "container"),
+        ("aspect", "aspect_ratio"),
+        ("fit", "fit"),
+        ("background", "background_color"),
+        ("quality", "quality"),
+        ("speed", "preset"),
+        ("codec", "video_codec"),
+        ("audio-codec", "audio_codec"),
+    ];
+    for (flag, field) in text_fields {
+        if let Some(value) = args.get(flag) {
+            fields.insert(field.to_string(), json!(value));
+        }
+    }
+
+    if let Some(resolution) = args.get("resolution") {
+        let value = match resolution.split_once('x') {
+            Some((width, height)) => {
+                let parse = |v: &str| v.parse::<u32>().map_err(|_| format!("Invalid resolution '{}'", resolution));
+                json!({ "width": parse(width)?, "height": parse(height)? })
+            }
+            None => json!(resolution),
+        };
+        fields.insert("resolution".to_string(), value);
+    }
+    if let Some(crf) = args.number::<u32>("crf")? {
+        fields.insert("rate_control".to_string(), json!({ "mode": "crf", "crf": crf }));
+    }
+    if let Some(kbps) = args.number::<u32>("bitrate")? {
+        if args.get("crf").is_some() {
+            return Err("--crf and --bitrate can't be used together".to_string());
+        }
+        fields.insert("rate_control".to_string(), json!({ "mode": "bitrate", "kbps": kbps }));
+    }
+    if let Some(fps) = args.number::<f64>("fps")? {
+        fields.insert("frame_rate".to_string(), json!(fps));
+    }
+    if let Some(kbps) = args.number::<u32>("audio-bitrate")? {
+        fields.insert("audio_bitrate_kbps".to_string(), json!(kbps));
+    }
+
+    serde_json::from_value(Value::Object(fields)).map_err(|e| format!("Invalid export settings: {}", e))
+}
+
+fn thumbnail(args: &Args) -> Result<(), String> {
+    args.reject_unknown(&["output", "time", "width"])?;
+    let input = args.input()?;
+    let output = output_path_next_to(input, args.get("output"), "_thumb.jpg")?;
+
+    preview::extract_thumbnail(input, &output, args.number("time")?.unwrap_or(0.0), args.number("width")?)?;
+    emit(json!({ "event": "done", "stage": "thumbnail", "output_path": output }));
+    Ok(())
+}
+
+fn waveform(args: &Args) -> Result<(), String> {
+    args.reject_unknown(&["output", "width", "height", "color"])?;
+    let input = args.input()?;
+    let output = output_path_next_to(input, args.get("output"), "_waveform.png")?;
+
+    preview::render_waveform(
+        input,
+        &output,
+        args.number("width")?.unwrap_or(1200),
+        args.number("height")?.unwrap_or(240),
+        args.get("color").unwrap_or("#4f8cff"),
+    )?;
+    emit(json!({ "event": "done", "stage": "waveform", "output_path": output }));
+    Ok(())
+}
+
+fn record(args: &Args) -> Result<(), String> {
+    args.reject_unknown(&["output", "duration", "fps", "display", "audio", "cursor"])?;
+    let defaults = CaptureOptions::default();
+    let show_cursor = match args.get("cursor") {
+        None | Some("show") => true,
+        Some("hide") => false,
+        Some(other) => return Err(format!("--cursor expects show or hide, got '{}'", other)),
+    };
+    let options = CaptureOptions {
+        duration: args.number("duration")?.unwrap_or(defaults.duration),
+        fps: args.number("fps")?.unwrap_or(defaults.fps),
+        display: args.get("display").map(|d| d.to_string()),
+        audio: parse_audio_sources(args.get("audio").unwrap_or(""))?,
+        show_cursor,
+    };
+
+    let values = TemplateValues {
+        project: Some("recording".to_string()),
+        ext: "mp4".to_string(),
+        ..TemplateValues::default()
+    };
+    let output_path = resolve_output(args.get("output"), &values)?;
+    emit(json!({ "event": "start", "stage": "record", "output_path": output_path }));
+
+    let cancelled = AtomicBool::new(false);
+    let on_progress = progress_reporter("record");
+    let control = RunControl {
+        cancelled: &cancelled,
+        on_progress: &on_progress,
+    };
+    ffmpeg_capture::record_screen(&output_path, &options, Some(&control))?;
+
+    emit(json!({ "event": "done", "stage": "record", "output_path": output_path }));
+    Ok(())
+}
+
+fn list_audio_devices(args: &Args) -> Result<(), String> {
+    args.reject_unknown(&[])?;
+    for device in audio_devices::list_audio_devices()? {
+        emit(json!({ "event": "audio_device", "device": device }));
+    }
+    Ok(())
+}
+
+fn list_video_devices(args: &Args) -> Result<(), String> {
+    args.reject_unknown(&[])?;
+    for device in video_devices::list_video_devices()? {
+        emit(json!({ "event": "video_device", "device": device }));
+    }
+    Ok(())
+}
+
+// "default@-3,alsa_output.monitor" -> sources with their gain in dB
+fn parse_audio_sources(value: &str) -> Result<Vec<AudioSource>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|spec| match spec.rsplit_once('@') {
+            Some((device_id, gain)) => Ok(AudioSource {
+                device_id: device_id.to_string(),
+                gain_db: gain.parse().map_err(|_| format!("Invalid gain '{}' for {}", gain, device_id))?,
+            }),
+            None => Ok(AudioSource { device_id: spec.to_string(), gain_db: 0.0 }),
+        })
+        .collect()
+}
+
+// An explicit -o is a path from the working directory, as with any CLI; its file name may
+// use template tokens and gets the extension if it has none. Without -o the configured
+// template and export directory pick the name.
+fn resolve_output(output: Option<&str>, values: &TemplateValues) -> Result<String, String> {
+    let Some(output) = output.map(str::trim).filter(|o| !o.is_empty()) else {
+        return output_paths::resolve_export_path("", values);
+    };
+    let cwd = std::env::current_dir().map_err(|e| format!("Failed to read the working directory: {}", e))?;
+    let mut path = cwd.join(output);
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if file_name.contains('{') {
+        path.set_file_name(output_paths::render_template(&file_name, values)?);
+    }
+    if path.extension().is_none() {
+        path.set_extension(&values.ext);
+    }
+    output_paths::resolve_collision(&path.to_string_lossy())
+}
+
+// Explicit output path, or the input's name with `suffix` in the same directory
+fn output_path_next_to(input: &str, output: Option<&str>, suffix: &str) -> Result<String, String> {
+    let path = match output {
+        Some(output) => output.to_string(),
+        None => {
+            let input = Path::new(input);
+            let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            input.with_file_name(format!("{}{}", stem, suffix)).to_string_lossy().to_string()
+        }
+    };
+    output_paths::resolve_collision(&path)
+}
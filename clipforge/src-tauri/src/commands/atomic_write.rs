@@ -0,0 +1,96 @@
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+// Write-then-rename helpers. Data goes to a hidden sibling of the final path,
+// is flushed to disk and only then renamed over the target, so a crash or a
+// cancelled export never leaves a truncated file that looks finished.
+
+/// Hidden sibling used while a file is being written: "clip.mp4" -> ".clip.partial.mp4".
+/// The extension is kept so ffmpeg still picks the right container.
+pub fn partial_path(path: &str) -> String {
+    let original = Path::new(path);
+    let stem = original.file_stem().and_then(|s| s.to_str()).unwrap_or("export");
+    let partial = match original.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!(".{}.partial.{}", stem, ext),
+        None => format!(".{}.partial", stem),
+    };
+    original.with_file_name(partial).to_string_lossy().to_string()
+}
+
+/// Write `data` to `path` through a partial file
+pub fn write_atomic(path: impl AsRef<Path>, data: &[u8]) -> Result<(), String> {
+    let path = path.as_ref().to_string_lossy().to_string();
+    let partial = partial_path(&path);
+
+    let written = File::create(&partial)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .map_err(|e| format!("Failed to write {}: {}", path, e));
+
+    match written.and_then(|_| rename_into_place(&partial, &path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            discard(&partial);
+            Err(e)
+        }
+    }
+}
+
+/// Flush a finished partial file and move it to its final path
+pub fn commit(partial: &str, path: &str) -> Result<(), String> {
+    flush(partial)?;
+    rename_into_place(partial, path)
+}
+
+/// Like commit, but leaves a file that is already at `path` alone and returns false.
+/// The partial file stays in place so the caller can try another name.
+pub fn commit_new(partial: &str, path: &str) -> Result<bool, String> {
+    flush(partial)?;
+    // A hard link can't replace an existing file, so claiming the name is atomic
+    match fs::hard_link(partial, path) {
+        Ok(()) => {
+            let _ = fs::remove_file(partial);
+            sync_parent(path);
+            Ok(true)
+        }
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+        // Filesystems without hard links (FAT, some network shares): check, then rename
+        Err(_) if Path::new(path).exists() => Ok(false),
+        Err(_) => rename_into_place(partial, path).map(|_| true),
+    }
+}
+
+fn flush(partial: &str) -> Result<(), String> {
+    File::open(partial)
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("Failed to flush {}: {}", partial, e))
+}
+
+/// Remove a partial file left by a failed or cancelled write
+pub fn discard(partial: &str) {
+    if Path::new(partial).exists() {
+        match fs::remove_file(partial) {
+            Ok(()) => println!("🗑️ Removed partial output: {}", partial),
+            Err(e) => println!("⚠️ Failed to remove partial output {}: {}", partial, e),
+        }
+    }
+}
+
+fn rename_into_place(partial: &str, path: &str) -> Result<(), String> {
+    fs::rename(partial, path).map_err(|e| format!("Failed to move {} into place: {}", path, e))?;
+    sync_parent(path);
+    Ok(())
+}
+
+// Persist the rename itself; directories can't be opened for syncing on Windows
+fn sync_parent(path: &str) {
+    #[cfg(unix)]
+    if let Some(parent) = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        let _ = File::open(parent).and_then(|dir| dir.sync_all());
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
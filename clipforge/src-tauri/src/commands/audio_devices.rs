@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tauri::command;
+
+use crate::commands::ffmpeg_capture::{self, DeviceKind};
+
+// Audio capture devices for the ffmpeg-backed recorders (ISO bundles and `vidveil record`).
+// On Linux they come from PulseAudio (or PipeWire's pulse server) through pactl, so
+// a null sink's monitor shows up like any other source. macOS and Windows list
+// what ffmpeg's AVFoundation/DirectShow devices report.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioDeviceKind {
+    Input,    // microphones and line-ins
+    Loopback, // monitor of an output: what the system is playing
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioDevice {
+    pub id: String, // what recordings pass back to select the device
+    pub name: String,
+    pub kind: AudioDeviceKind,
+    pub channels: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub is_default: bool,
+}
+
+/// A device picked for a recording, with its gain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioSource {
+    pub device_id: String,
+    #[serde(default)]
+    pub gain_db: f64,
+}
+
+#[command]
+pub fn list_audio_devices() -> Result<Vec<AudioDevice>, String> {
+    if cfg!(target_os = "linux") {
+        list_pulse_sources()
+    } else if cfg!(target_os = "macos") || cfg!(target_os = "windows") {
+        let devices = ffmpeg_capture::list_ffmpeg_devices(DeviceKind::Audio)?;
+        Ok(devices
+            .into_iter()
+            .enumerate()
+            .map(|(i, (id, name))| ffmpeg_device(id, name, i == 0))
+            .collect())
+    } else {
+        Err("Audio device listing isn't supported on this platform".to_string())
+    }
+}
+
+/// Check the sources against the devices present so a typo fails before the
+/// recording starts. "default" is always accepted; if devices can't be listed the
+/// capture itself reports what's wrong.
+pub fn validate_sources(sources: &[AudioSource]) -> Result<(), String> {
+    for source in sources {
+        if !(-60.0..=30.0).contains(&source.gain_db) {
+            return Err(format!("Gain {} dB for {} is out of range (-60 to +30)", source.gain_db, source.device_id));
+        }
+    }
+
+    let Ok(devices) = list_audio_devices() else {
+        return Ok(());
+    };
+    for source in sources.iter().filter(|s| s.device_id != "default") {
+        if !devices.iter().any(|d| d.id == source.device_id) {
+            return Err(format!("Audio device not found: {}", source.device_id));
+        }
+    }
+    Ok(())
+}
+
+/// Filter that applies the source's gain, if it has one
+pub fn gain_filter(source: &AudioSource) -> Option<String> {
+    (source.gain_db.abs() > f64::EPSILON).then(|| format!("volume={:.2}dB", source.gain_db))
+}
+
+// `pactl list sources` blocks: "Source #1", "Name: ...", "Description: ...",
+// "Sample Specification: s16le 2ch 44100Hz", "Monitor of Sink: n/a"
+fn list_pulse_sources() -> Result<Vec<AudioDevice>, String> {
+    let default_source = pactl(&["info"])?
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("Default Source:").map(|s| s.trim().to_string()));
+    let listing = pactl(&["list", "sources"])?;
+
+    let mut devices = Vec::new();
+    for block in listing.split("Source #").skip(1) {
+        let field = |name: &str| {
+            block
+                .lines()
+                .find_map(|l| l.trim().strip_prefix(name).map(|v| v.trim().to_string()))
+        };
+        let Some(id) = field("Name:") else {
+            continue;
+        };
+        let spec = field("Sample Specification:").unwrap_or_default();
+        let monitor_of = field("Monitor of Sink:").filter(|m| m != "n/a");
+
+        devices.push(AudioDevice {
+            name: field("Description:").unwrap_or_else(|| id.clone()),
+            kind: if monitor_of.is_some() || id.ends_with(".monitor") {
+                AudioDeviceKind::Loopback
+            } else {
+                AudioDeviceKind::Input
+            },
+            channels: spec_value(&spec, "ch"),
+            sample_rate: spec_value(&spec, "Hz"),
+            is_default: default_source.as_deref() == Some(id.as_str()),
+            id,
+        });
+    }
+    Ok(devices)
+}
+
+// "s16le 2ch 44100Hz" -> 2 for "ch", 44100 for "Hz"
+fn spec_value(spec: &str, suffix: &str) -> Option<u32> {
+    spec.split_whitespace().find_map(|part| part.strip_suffix(suffix)?.parse().ok())
+}
+
+fn pactl(args: &[&str]) -> Result<String, String> {
+    // Field names are translated otherwise
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("Failed to run pactl (is PulseAudio or PipeWire running?): {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pactl failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn ffmpeg_device(id: String, name: String, first: bool) -> AudioDevice {
+    let lower = name.to_lowercase();
+    // Loopback drivers (BlackHole, Soundflower, Stereo Mix) carry what the system plays
+    let loopback = ["blackhole", "soundflower", "loopback", "stereo mix"].iter().any(|k| lower.contains(k));
+    AudioDevice {
+        id,
+        name,
+        kind: if loopback { AudioDeviceKind::Loopback } else { AudioDeviceKind::Input },
+        channels: None,
+        sample_rate: None,
+        is_default: first,
+    }
+}
@@ -0,0 +1,218 @@
+use serde::Serialize;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
+
+use crate::commands::audio_devices::{self, AudioSource};
+use crate::commands::ffmpeg;
+use crate::commands::ffmpeg_capture;
+
+// Audio level meters. ffmpeg downmixes every source to mono 16 kHz PCM, merges them
+// into one interleaved stream on a pipe, and we compute peak/RMS per source every
+// METER_INTERVAL. Used by ISO recordings (metering what is being recorded), by a
+// standalone monitor to check the mic before recording, and alongside native
+// recordings, whose AVFoundation capture can't be tapped.
+
+lazy_static::lazy_static! {
+    static ref MONITOR: Arc<Mutex<Option<Child>>> = Arc::new(Mutex::new(None));
+    static ref RECORDING_METER: Arc<Mutex<Option<Child>>> = Arc::new(Mutex::new(None));
+}
+
+pub const METER_SAMPLE_RATE: u32 = 16_000;
+const METER_INTERVAL: Duration = Duration::from_millis(100);
+const SILENCE_FLOOR_DB: f64 = -90.0;
+const CLIPPING_DB: f64 = -0.1;
+// At most one clipping warning per source in this window
+const CLIPPING_COOLDOWN: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MeterMode {
+    Monitor,
+    Recording,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceLevel {
+    pub device_id: String,
+    pub peak_db: f64,
+    pub rms_db: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AudioLevels {
+    mode: MeterMode,
+    sources: Vec<SourceLevel>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AudioClipping {
+    mode: MeterMode,
+    device_id: String,
+    peak_db: f64,
+}
+
+/// Meter sources without recording, e.g. to check levels before starting.
+/// Emits "audio-levels" and "audio-clipping" until stop_audio_monitor.
+#[command]
+pub fn start_audio_monitor(app: AppHandle, sources: Vec<AudioSource>) -> Result<(), String> {
+    if sources.is_empty() {
+        return Err("Select at least one audio source to monitor".to_string());
+    }
+    audio_devices::validate_sources(&sources)?;
+    stop_audio_monitor()?;
+
+    let child = spawn_meter(app, &sources, MeterMode::Monitor)?;
+    *MONITOR.lock().map_err(|e| e.to_string())? = Some(child);
+    println!("🎚️ Monitoring {} audio source(s)", sources.len());
+    Ok(())
+}
+
+#[command]
+pub fn stop_audio_monitor() -> Result<bool, String> {
+    let Some(mut child) = MONITOR.lock().map_err(|e| e.to_string())?.take() else {
+        return Ok(false);
+    };
+    let _ = child.kill();
+    let _ = child.wait();
+    println!("🎚️ Audio monitor stopped");
+    Ok(true)
+}
+
+/// Meter the sources of a native recording in "recording" mode until
+/// stop_recording_meter. The recording itself goes on if the meter can't start.
+pub fn start_recording_meter(app: AppHandle, sources: &[AudioSource]) {
+    stop_recording_meter();
+    match spawn_meter(app, sources, MeterMode::Recording) {
+        Ok(child) => {
+            if let Ok(mut meter) = RECORDING_METER.lock() {
+                *meter = Some(child);
+            }
+        }
+        Err(e) => println!("⚠️ Audio meter unavailable for this recording: {}", e),
+    }
+}
+
+pub fn stop_recording_meter() {
+    let Some(mut child) = RECORDING_METER.lock().ok().and_then(|mut meter| meter.take()) else {
+        return;
+    };
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+// Run ffmpeg metering the sources to a pipe, read on a background thread
+fn spawn_meter(app: AppHandle, sources: &[AudioSource], mode: MeterMode) -> Result<Child, String> {
+    let mut args = Vec::new();
+    for source in sources {
+        args.extend(ffmpeg_capture::audio_input_args(Some(&source.device_id))?);
+    }
+    let inputs: Vec<(String, &AudioSource)> = sources.iter().enumerate().map(|(i, s)| (format!("{}:a", i), s)).collect();
+    args.extend(["-filter_complex".to_string(), meter_filter(&inputs, "meter")]);
+    args.extend(meter_output_args("meter", "pipe:1"));
+
+    let mut child = Command::new(ffmpeg::ffmpeg_binary())
+        .args(["-hide_banner", "-nostats", "-loglevel", "error"])
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    let stdout = child.stdout.take().ok_or("ffmpeg stdout unavailable")?;
+
+    let device_ids = sources.iter().map(|s| s.device_id.clone()).collect();
+    std::thread::spawn(move || run_meter(app, stdout, device_ids, mode));
+    Ok(child)
+}
+
+/// Filter graph turning each (input label, source) into one channel of [label]:
+/// gain applied, mono, 16-bit at the meter rate
+pub fn meter_filter(inputs: &[(String, &AudioSource)], label: &str) -> String {
+    let mut filter = String::new();
+    for (i, (input, source)) in inputs.iter().enumerate() {
+        let gain = audio_devices::gain_filter(source).map(|g| format!("{},", g)).unwrap_or_default();
+        filter.push_str(&format!(
+            "[{}]{}aresample={},aformat=sample_fmts=s16:channel_layouts=mono[meter{}];",
+            input, gain, METER_SAMPLE_RATE, i
+        ));
+    }
+    if inputs.len() == 1 {
+        filter.push_str(&format!("[meter0]anull[{}]", label));
+    } else {
+        for i in 0..inputs.len() {
+            filter.push_str(&format!("[meter{}]", i));
+        }
+        filter.push_str(&format!("amerge=inputs={}[{}]", inputs.len(), label));
+    }
+    filter
+}
+
+/// Output arguments writing the merged meter stream as raw PCM to `target`
+pub fn meter_output_args(label: &str, target: &str) -> Vec<String> {
+    vec![
+        "-map".to_string(),
+        format!("[{}]", label),
+        "-f".to_string(),
+        "s16le".to_string(),
+        target.to_string(),
+    ]
+}
+
+/// Read interleaved s16le (one channel per device, in order) until the pipe closes,
+/// emitting levels every METER_INTERVAL. Keeps draining even if emitting fails so
+/// ffmpeg never blocks on a full pipe.
+pub fn run_meter(app: AppHandle, mut reader: impl Read, device_ids: Vec<String>, mode: MeterMode) {
+    let channels = device_ids.len().max(1);
+    let window_frames = (METER_SAMPLE_RATE as f64 * METER_INTERVAL.as_secs_f64()) as usize;
+    let mut buffer = vec![0u8; window_frames * channels * 2];
+    let mut last_clipping: Vec<Option<Instant>> = vec![None; channels];
+
+    loop {
+        if reader.read_exact(&mut buffer).is_err() {
+            return;
+        }
+
+        let mut peaks = vec![0f64; channels];
+        let mut squares = vec![0f64; channels];
+        for (i, sample) in buffer.chunks_exact(2).enumerate() {
+            let value = i16::from_le_bytes([sample[0], sample[1]]) as f64 / 32768.0;
+            let channel = i % channels;
+            peaks[channel] = peaks[channel].max(value.abs());
+            squares[channel] += value * value;
+        }
+
+        let levels: Vec<SourceLevel> = device_ids
+            .iter()
+            .enumerate()
+            .map(|(c, device_id)| SourceLevel {
+                device_id: device_id.clone(),
+                peak_db: to_db(peaks[c]),
+                rms_db: to_db((squares[c] / window_frames as f64).sqrt()),
+            })
+            .collect();
+
+        for (c, level) in levels.iter().enumerate() {
+            let cooled_down = last_clipping[c].is_none_or(|at| at.elapsed() >= CLIPPING_COOLDOWN);
+            if level.peak_db >= CLIPPING_DB && cooled_down {
+                last_clipping[c] = Some(Instant::now());
+                println!("⚠️ Audio clipping on {}", level.device_id);
+                let _ = app.emit("audio-clipping", AudioClipping {
+                    mode,
+                    device_id: level.device_id.clone(),
+                    peak_db: level.peak_db,
+                });
+            }
+        }
+        let _ = app.emit("audio-levels", AudioLevels { mode, sources: levels });
+    }
+}
+
+fn to_db(amplitude: f64) -> f64 {
+    if amplitude <= 0.0 {
+        return SILENCE_FLOOR_DB;
+    }
+    ((20.0 * amplitude.log10()).max(SILENCE_FLOOR_DB) * 10.0).round() / 10.0
+}
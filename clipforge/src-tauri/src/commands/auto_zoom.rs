@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::commands::ffmpeg;
+use crate::commands::input_events::{self, InputEventKind};
+use crate::commands::video_processing::VideoClip;
+
+// Cursor-follow zoom ("auto-zoom"). A screen clip whose recording logged the pointer
+// (RecordingSettings.track_cursor) zooms in ahead of each burst of clicks, pans to
+// follow later clicks (and the pointer, if asked), and zooms back out once things go
+// quiet. Keyframes are worked out from the input log and rendered with zoompan.
+
+// Shortest pan, so clicks close together still move smoothly
+const MIN_PAN_SECS: f64 = 0.2;
+// How far (as a fraction of the zoomed view) the focus can drift before the view pans
+const DEAD_ZONE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoZoom {
+    pub zoom: f64, // magnification while zoomed in
+    pub easing: Easing,
+    pub transition_secs: f64, // length of each zoom or pan
+    pub hold_secs: f64,       // how long to stay zoomed after the last click
+    pub follow_cursor: bool,  // pan with the pointer between clicks, not just on clicks
+}
+
+impl Default for AutoZoom {
+    fn default() -> Self {
+        AutoZoom {
+            zoom: 2.0,
+            easing: Easing::EaseInOut,
+            transition_secs: 0.8,
+            hold_secs: 2.5,
+            follow_cursor: true,
+        }
+    }
+}
+
+impl AutoZoom {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1.0..=4.0).contains(&self.zoom) {
+            return Err(format!("Zoom {} is out of range (1-4)", self.zoom));
+        }
+        if !(0.1..=5.0).contains(&self.transition_secs) {
+            return Err(format!("Transition {}s is out of range (0.1-5)", self.transition_secs));
+        }
+        if self.hold_secs < 0.0 {
+            return Err("Hold time can't be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The view at `time` (seconds from the clip's in-point): magnification and the
+/// point it centers on, as fractions of the frame. `easing` is how the view moves
+/// here from the previous keyframe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoomKeyframe {
+    pub time: f64,
+    pub zoom: f64,
+    pub x: f64,
+    pub y: f64,
+    pub easing: Easing,
+}
+
+/// Keyframes export would use for the clip, for previewing its zoom. Uses the clip's
+/// auto-zoom settings, or the defaults if it has none.
+#[command]
+pub fn get_zoom_keyframes(clip: VideoClip) -> Result<Vec<ZoomKeyframe>, String> {
+    let settings = clip.auto_zoom.clone().unwrap_or_default();
+    settings.validate()?;
+    zoom_keyframes(&clip, &settings)
+}
+
+/// Work out the zoom keyframes from the clip's input log; empty when the recording
+/// has no clicks to zoom toward. Fails when the log auto-zoom needs wasn't recorded,
+/// e.g. for recordings made where input can't be logged.
+pub fn zoom_keyframes(clip: &VideoClip, settings: &AutoZoom) -> Result<Vec<ZoomKeyframe>, String> {
+    let Some(log) = input_events::load_log(&clip.path)? else {
+        return Err(format!("{} has no input log to auto-zoom from; record it with track_cursor on X11", clip.path));
+    };
+    if settings.follow_cursor && !log.settings.track_cursor {
+        return Err(format!("{} was recorded without track_cursor, so follow_cursor has no pointer to follow", clip.path));
+    }
+    let (width, height) = match (log.width, log.height) {
+        (Some(w), Some(h)) if w > 0 && h > 0 => (w as f64, h as f64),
+        _ => {
+            let (w, h) = ffmpeg::video_dimensions(&clip.path)?;
+            (w as f64, h as f64)
+        }
+    };
+    // (time, x, y, is_click) with positions as fractions of the display
+    let points: Vec<(f64, f64, f64, bool)> = input_events::clip_events(clip, &log)
+        .filter_map(|(t, kind)| match kind {
+            InputEventKind::Click { x, y, .. } => Some((t, x / width, y / height, true)),
+            InputEventKind::Move { x, y } if settings.follow_cursor => Some((t, x / width, y / height, false)),
+            _ => None,
+        })
+        .collect();
+
+    let mut planner = ZoomPlanner::new(settings);
+    let clicks: Vec<&(f64, f64, f64, bool)> = points.iter().filter(|p| p.3).collect();
+    // Clicks close enough together share one zoom-in instead of zooming out between them
+    let max_gap = settings.hold_secs + 2.0 * settings.transition_secs;
+    let mut spans: Vec<(f64, f64)> = Vec::new();
+    for click in clicks {
+        match spans.last_mut() {
+            Some((_, last)) if click.0 - *last <= max_gap => *last = click.0,
+            _ => spans.push((click.0, click.0)),
+        }
+    }
+
+    for (first, last) in spans {
+        let zoom_out_at = last + settings.hold_secs;
+        let mut span_points = points.iter().filter(|p| p.0 >= first && p.0 <= zoom_out_at);
+        if let Some(&(t, x, y, _)) = span_points.next() {
+            planner.move_to(t - settings.transition_secs, t, settings.zoom, x, y);
+        }
+        for &(t, x, y, is_click) in span_points {
+            // The pointer moving mid-pan is followed once the pan ends; clicks always are
+            if !is_click && t < planner.at {
+                continue;
+            }
+            if planner.in_view(x, y) {
+                continue;
+            }
+            let start = if is_click { t - settings.transition_secs } else { t };
+            planner.move_to(start, t, settings.zoom, x, y);
+        }
+        let (x, y) = (planner.x, planner.y);
+        planner.move_to(zoom_out_at, zoom_out_at + settings.transition_secs, 1.0, x, y);
+    }
+    Ok(planner.keyframes)
+}
+
+/// Zoompan from [input] to [output] following the clip's zoom keyframes, or None when
+/// the clip has no auto-zoom or nothing to zoom toward. The clip's input must start at
+/// its in-point (setpts=PTS-STARTPTS).
+pub fn zoom_filter(clip: &VideoClip, input: &str, output: &str) -> Result<Option<String>, String> {
+    let Some(settings) = clip.auto_zoom.as_ref().filter(|_| clip.clip_type == "screen") else {
+        return Ok(None);
+    };
+    settings.validate()?;
+    let keyframes = zoom_keyframes(clip, settings)?;
+    if keyframes.len() < 2 {
+        return Ok(None);
+    }
+
+    let (width, height) = ffmpeg::video_dimensions(&clip.path)?;
+    // zoompan makes `d` frames per input frame, so feed it a steady rate and take one each
+    let fps = ffmpeg::video_frame_rate(&clip.path).unwrap_or(30.0);
+    let zoom = keyframe_expr(&keyframes, |k| k.zoom);
+    let x = keyframe_expr(&keyframes, |k| k.x);
+    let y = keyframe_expr(&keyframes, |k| k.y);
+    Ok(Some(format!(
+        "[{}]fps={:.3},zoompan=z='{}':x='clip(({})*iw-iw/zoom/2,0,iw-iw/zoom)':y='clip(({})*ih-ih/zoom/2,0,ih-ih/zoom)':d=1:s={}x{}:fps={:.3}[{}]",
+        input, fps, zoom, x, y, width, height, fps, output
+    )))
+}
+
+// Builds the keyframe list one move at a time, keeping moves in order and apart
+struct ZoomPlanner {
+    keyframes: Vec<ZoomKeyframe>,
+    easing: Easing,
+    zoom: f64,
+    at: f64, // when the last move ends
+    x: f64,
+    y: f64,
+}
+
+impl ZoomPlanner {
+    fn new(settings: &AutoZoom) -> Self {
+        ZoomPlanner {
+            keyframes: vec![ZoomKeyframe { time: 0.0, zoom: 1.0, x: 0.5, y: 0.5, easing: settings.easing }],
+            easing: settings.easing,
+            zoom: 1.0,
+            at: 0.0,
+            x: 0.5,
+            y: 0.5,
+        }
+    }
+
+    // Whether (x, y) is close enough to the middle of the current view to leave it be
+    fn in_view(&self, x: f64, y: f64) -> bool {
+        let margin = DEAD_ZONE / self.zoom;
+        (x - self.x).abs() <= margin && (y - self.y).abs() <= margin
+    }
+
+    // Move the view between `start` and `end`, pushed back if the last move is still running
+    fn move_to(&mut self, start: f64, end: f64, zoom: f64, x: f64, y: f64) {
+        let start = start.max(self.at);
+        let end = end.max(start + MIN_PAN_SECS);
+        // Keep the focus far enough from the edges that the view doesn't run off the frame
+        let half = 0.5 / zoom;
+        let (x, y) = (x.clamp(half, 1.0 - half), y.clamp(half, 1.0 - half));
+        if start > self.at {
+            self.push(start, self.zoom, self.x, self.y, Easing::Linear);
+        }
+        self.push(end, zoom, x, y, self.easing);
+        self.zoom = zoom;
+        self.x = x;
+        self.y = y;
+        self.at = end;
+    }
+
+    fn push(&mut self, time: f64, zoom: f64, x: f64, y: f64, easing: Easing) {
+        self.keyframes.push(ZoomKeyframe { time, zoom, x, y, easing });
+    }
+}
+
+// zoompan expression for one value over time: the first keyframe's value plus each
+// move's change, scaled by its eased progress (0 before the move, 1 after)
+fn keyframe_expr(keyframes: &[ZoomKeyframe], value: impl Fn(&ZoomKeyframe) -> f64) -> String {
+    let mut expr = format!("{:.4}", value(&keyframes[0]));
+    for pair in keyframes.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        let delta = value(to) - value(from);
+        if delta.abs() < 1e-4 {
+            continue;
+        }
+        let progress = format!("clip((it-{:.3})/{:.3},0,1)", from.time, to.time - from.time);
+        expr.push_str(&format!("{:+.4}*{}", delta, ease(to.easing, &progress)));
+    }
+    expr
+}
+
+fn ease(easing: Easing, p: &str) -> String {
+    match easing {
+        Easing::Linear => p.to_string(),
+        Easing::EaseIn => format!("{p}*{p}"),
+        Easing::EaseOut => format!("{p}*(2-{p})"),
+        Easing::EaseInOut => format!("{p}*{p}*(3-2*{p})"),
+    }
+}
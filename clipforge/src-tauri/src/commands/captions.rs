@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::command;
+
+use crate::commands::atomic_write;
+use crate::commands::export_settings::{EncodeSettings, ExportSettings};
+use crate::commands::ffmpeg;
+use crate::commands::silence::TimeRange;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionCue {
+    pub start: f64, // timeline seconds
+    pub end: f64,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionTrack {
+    pub id: String,
+    pub name: String,
+    pub language: Option<String>, // ISO 639 code, e.g. "en" or "eng"
+    pub cues: Vec<CaptionCue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionFormat {
+    Srt,
+    Vtt,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionMode {
+    Burn, // rendered into the video frames
+    Soft, // muxed as a selectable subtitle stream
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionExportOptions {
+    pub track: CaptionTrack,
+    pub mode: CaptionMode,
+}
+
+impl CaptionFormat {
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "srt" => Ok(CaptionFormat::Srt),
+            "vtt" => Ok(CaptionFormat::Vtt),
+            _ => Err(format!("Unsupported caption file type: {}", path)),
+        }
+    }
+}
+
+/// Import an SRT or WebVTT file as a caption track
+#[command]
+pub fn import_captions(file_path: String, language: Option<String>) -> Result<CaptionTrack, String> {
+    let format = CaptionFormat::from_path(&file_path)?;
+    let content = fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read caption file: {}", e))?;
+
+    let cues = parse_cues(&content, format)?;
+    let name = Path::new(&file_path)
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Captions")
+        .to_string();
+
+    println!("💬 Imported {} caption cue(s) from {}", cues.len(), file_path);
+
+    Ok(CaptionTrack {
+        id: format!("captions_{}", chrono::Utc::now().timestamp_millis()),
+        name,
+        language,
+        cues,
+    })
+}
+
+/// Write a caption track to an SRT or WebVTT file (format taken from the extension)
+#[command]
+pub fn export_captions(track: CaptionTrack, file_path: String) -> Result<String, String> {
+    let format = CaptionFormat::from_path(&file_path)?;
+    atomic_write::write_atomic(&file_path, format_cues(&track.cues, format).as_bytes())
+        .map_err(|e| format!("Failed to write caption file: {}", e))?;
+
+    Ok(file_path)
+}
+
+/// Shift a caption track after timeline ranges were cut out, dropping cues that fell inside them
+#[command]
+pub fn trim_caption_track(track: CaptionTrack, cuts: Vec<TimeRange>) -> Result<CaptionTrack, String> {
+    let mut cuts = cuts;
+    cuts.retain(|c| c.end > c.start);
+    // Apply from the latest cut backwards so earlier cut positions stay valid
+    cuts.sort_by(|a, b| b.start.total_cmp(&a.start));
+
+    let mut cues = track.cues;
+    for cut in &cuts {
+        cues = cues.into_iter().filter_map(|cue| shift_cue(cue, cut)).collect();
+    }
+
+    Ok(CaptionTrack { cues, ..track })
+}
+
+/// Burn captions into a video or mux them as a soft subtitle stream. A burn re-encodes
+/// the video with `settings` when given, ffmpeg's defaults otherwise.
+#[command]
+pub async fn render_captions(
+    input_path: String,
+    output_path: String,
+    options: CaptionExportOptions,
+    settings: Option<ExportSettings>,
+) -> Result<String, String> {
+    let encode = settings.map(|s| s.resolve()).transpose()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        apply_captions(&input_path, &output_path, &options, encode.as_ref())?;
+        Ok(output_path)
+    })
+    .await
+    .map_err(|e| format!("Caption render task failed: {}", e))?
+}
+
+// Shared by render_captions and the export pipeline
+pub fn apply_captions(
+    input_path: &str,
+    output_path: &str,
+    options: &CaptionExportOptions,
+    encode: Option<&EncodeSettings>,
+) -> Result<(), String> {
+    if options.track.cues.is_empty() {
+        return Err("Caption track has no cues".to_string());
+    }
+    if input_path == output_path {
+        return Err("Caption output must differ from the input file".to_string());
+    }
+
+    let subtitle_codec = match options.mode {
+        CaptionMode::Soft => Some(soft_subtitle_codec(output_path)?),
+        CaptionMode::Burn => None,
+    };
+
+    // ffmpeg reads subtitles from a file, so stage the track as SRT next to the output
+    let srt_path = format!("{}.captions.srt", output_path);
+    fs::write(&srt_path, format_cues(&options.track.cues, CaptionFormat::Srt))
+        .map_err(|e| format!("Failed to stage captions: {}", e))?;
+
+    let mut args = vec!["-y".to_string(), "-i".to_string(), input_path.to_string()];
+
+    match subtitle_codec {
+        None => {
+            // Unquoted so paths with apostrophes survive; quoting can't escape them
+            args.extend([
+                "-vf".to_string(),
+                format!("subtitles=filename={}", ffmpeg::escape_graph_text(&srt_path)),
+            ]);
+            if let Some(encode) = encode {
+                if let Some(video) = &encode.video {
+                    args.extend(video.ffmpeg_args(encode.container));
+                }
+            }
+            args.extend(["-c:a".to_string(), "copy".to_string()]);
+        }
+        Some(subtitle_codec) => {
+            args.extend([
+                "-i".to_string(),
+                srt_path.clone(),
+                "-map".to_string(),
+                "0".to_string(),
+                "-map".to_string(),
+                "1:0".to_string(),
+                "-c".to_string(),
+                "copy".to_string(),
+                "-c:s".to_string(),
+                subtitle_codec.to_string(),
+            ]);
+            if let Some(language) = &options.track.language {
+                args.push("-metadata:s:s:0".to_string());
+                args.push(format!("language={}", language));
+            }
+            args.push("-metadata:s:s:0".to_string());
+            args.push(format!("title={}", options.track.name));
+        }
+    }
+
+    args.push(output_path.to_string());
+
+    println!("💬 Applying captions ({:?}) to {}", options.mode, output_path);
+    let result = ffmpeg::run_ffmpeg(&args);
+    let _ = fs::remove_file(&srt_path);
+    result.map(|_| ())
+}
+
+// Subtitle codec the output's container can carry
+fn soft_subtitle_codec(output_path: &str) -> Result<&'static str, String> {
+    let ext = Path::new(output_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "webm" => Ok("webvtt"),
+        "mkv" => Ok("srt"),
+        "mp4" | "mov" | "m4v" => Ok("mov_text"),
+        _ => Err(format!("Soft subtitles aren't supported in .{} files", ext)),
+    }
+}
+
+// Rewrite a finished export with captions applied, keeping the original path. Burned
+// captions are encoded with the export's own settings.
+pub fn apply_captions_in_place(path: &str, options: &CaptionExportOptions, encode: &EncodeSettings) -> Result<(), String> {
+    let original = Path::new(path);
+    let file_name = original
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid export path")?;
+    // Keep the extension so ffmpeg picks the same container
+    let captioned = original.with_file_name(format!("captioned_{}", file_name));
+    let captioned = captioned.to_string_lossy().to_string();
+
+    if let Err(e) = apply_captions(path, &captioned, options, Some(encode)) {
+        let _ = fs::remove_file(&captioned);
+        return Err(e);
+    }
+
+    atomic_write::commit(&captioned, path).map_err(|e| {
+        let _ = fs::remove_file(&captioned);
+        format!("Failed to replace export with captioned copy: {}", e)
+    })
+}
+
+pub fn parse_cues(content: &str, format: CaptionFormat) -> Result<Vec<CaptionCue>, String> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+
+    for block in content.split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|l| !l.trim().is_empty()).collect();
+        // WebVTT header, NOTE/STYLE/REGION blocks and SRT index lines carry no cue timing
+        let Some(timing_index) = lines.iter().position(|l| l.contains("-->")) else {
+            continue;
+        };
+
+        let (start, end) = parse_timing_line(lines[timing_index])?;
+        let text = lines[timing_index + 1..].join("\n");
+        if text.is_empty() {
+            continue;
+        }
+
+        cues.push(CaptionCue { start, end, text });
+    }
+
+    if cues.is_empty() && format == CaptionFormat::Vtt && !content.starts_with("WEBVTT") {
+        return Err("Not a WebVTT file: missing WEBVTT header".to_string());
+    }
+
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(cues)
+}
+
+pub fn format_cues(cues: &[CaptionCue], format: CaptionFormat) -> String {
+    let mut out = String::new();
+    if format == CaptionFormat::Vtt {
+        out.push_str("WEBVTT\n\n");
+    }
+
+    for (i, cue) in cues.iter().enumerate() {
+        if format == CaptionFormat::Srt {
+            out.push_str(&format!("{}\n", i + 1));
+        }
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start, format),
+            format_timestamp(cue.end, format),
+            cue.text
+        ));
+    }
+
+    out
+}
+
+// "00:01:02,500 --> 00:01:05,000" (SRT) or "01:02.500 --> 01:05.000 align:start" (WebVTT)
+fn parse_timing_line(line: &str) -> Result<(f64, f64), String> {
+    let mut parts = line.split("-->");
+    let start = parts.next().unwrap_or("").trim();
+    let end = parts
+        .next()
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or("");
+
+    let start = parse_timestamp(start).ok_or_else(|| format!("Invalid cue timing: {}", line))?;
+    let end = parse_timestamp(end).ok_or_else(|| format!("Invalid cue timing: {}", line))?;
+    if end < start {
+        return Err(format!("Cue ends before it starts: {}", line));
+    }
+
+    Ok((start, end))
+}
+
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let value = value.replace(',', ".");
+    let fields: Vec<&str> = value.split(':').collect();
+    let (hours, minutes, seconds) = match fields.as_slice() {
+        [h, m, s] => (h.parse::<f64>().ok()?, m.parse::<f64>().ok()?, s.parse::<f64>().ok()?),
+        [m, s] => (0.0, m.parse::<f64>().ok()?, s.parse::<f64>().ok()?),
+        _ => return None,
+    };
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+fn format_timestamp(seconds: f64, format: CaptionFormat) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    let separator = if format == CaptionFormat::Srt { ',' } else { '.' };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        total_ms / 3_600_000,
+        (total_ms / 60_000) % 60,
+        (total_ms / 1000) % 60,
+        separator,
+        total_ms % 1000
+    )
+}
+
+// Map a cue across one removed range: cues after it move earlier, cues overlapping it are clamped
+fn shift_cue(cue: CaptionCue, cut: &TimeRange) -> Option<CaptionCue> {
+    let cut_length = cut.end - cut.start;
+    let map_time = |t: f64| {
+        if t <= cut.start {
+            t
+        } else if t >= cut.end {
+            t - cut_length
+        } else {
+            cut.start
+        }
+    };
+
+    let start = map_time(cue.start);
+    let end = map_time(cue.end);
+    if end - start <= 0.0 {
+        return None;
+    }
+
+    Some(CaptionCue { start, end, text: cue.text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start: f64, end: f64, text: &str) -> CaptionCue {
+        CaptionCue { start, end, text: text.to_string() }
+    }
+
+    fn times(cues: &[CaptionCue]) -> Vec<(f64, f64)> {
+        cues.iter().map(|c| (c.start, c.end)).collect()
+    }
+
+    #[test]
+    fn splits_srt_into_cues() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n\r\n2\r\n00:01:02,250 --> 00:01:04,000\r\nTwo\r\nlines\r\n\r\n3\r\n00:00:05,000 --> 00:00:06,000\r\n\r\n";
+        let cues = parse_cues(srt, CaptionFormat::Srt).unwrap();
+        assert_eq!(times(&cues), vec![(1.0, 2.5), (62.25, 64.0)]);
+        assert_eq!(cues[1].text, "Two\nlines");
+    }
+
+    #[test]
+    fn skips_vtt_header_and_note_blocks() {
+        let vtt = "WEBVTT\n\nNOTE written by hand\n\nintro\n01:02.500 --> 01:05.000 align:start\nFirst\n\n00:00.000 --> 00:01.000\nEarlier\n";
+        let cues = parse_cues(vtt, CaptionFormat::Vtt).unwrap();
+        assert_eq!(times(&cues), vec![(0.0, 1.0), (62.5, 65.0)]);
+        assert_eq!(cues[1].text, "First");
+
+        assert!(parse_cues("just some text\n", CaptionFormat::Vtt).is_err());
+        assert!(parse_cues("WEBVTT\n", CaptionFormat::Vtt).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_timing() {
+        assert!(parse_cues("1\n00:00:01,000 --> soon\nText\n", CaptionFormat::Srt).is_err());
+        assert!(parse_cues("1\n00:00:05,000 --> 00:00:04,000\nText\n", CaptionFormat::Srt).is_err());
+    }
+
+    #[test]
+    fn formats_timestamps_per_format() {
+        let cues = [cue(3723.4567, 3725.0, "Hi")];
+        assert_eq!(format_cues(&cues, CaptionFormat::Srt), "1\n01:02:03,457 --> 01:02:05,000\nHi\n\n");
+        assert_eq!(format_cues(&cues, CaptionFormat::Vtt), "WEBVTT\n\n01:02:03.457 --> 01:02:05.000\nHi\n\n");
+
+        let round_trip = parse_cues(&format_cues(&cues, CaptionFormat::Vtt), CaptionFormat::Vtt).unwrap();
+        assert!((round_trip[0].start - 3723.457).abs() < 1e-9);
+        assert_eq!(round_trip[0].end, 3725.0);
+    }
+
+    #[test]
+    fn trimming_shifts_later_cues_and_drops_cut_ones() {
+        let track = CaptionTrack {
+            id: "track".to_string(),
+            name: "Captions".to_string(),
+            language: None,
+            cues: vec![cue(0.0, 2.0, "before"), cue(3.0, 4.0, "inside"), cue(4.5, 6.0, "overlapping"), cue(8.0, 9.0, "after")],
+        };
+        let cuts = vec![TimeRange { start: 2.5, end: 5.0 }, TimeRange { start: 7.0, end: 7.0 }];
+        let trimmed = trim_caption_track(track, cuts).unwrap();
+        assert_eq!(times(&trimmed.cues), vec![(0.0, 2.0), (2.5, 3.5), (5.5, 6.5)]);
+        assert_eq!(trimmed.cues[1].text, "overlapping");
+    }
+}
@@ -0,0 +1,471 @@
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tauri::{command, AppHandle, EventId, Listener};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::commands::audio_devices::AudioSource;
+use crate::commands::export_jobs::{self, BatchExportTarget};
+use crate::commands::video_devices::WebcamSelection;
+use crate::commands::markers;
+use crate::commands::output_paths::{self, TemplateValues};
+use crate::commands::recorder::{self, RecordingLimits, RecordingSettings};
+use crate::commands::scheduled_recording;
+use crate::commands::screen_capture::{self, RecordingRequest};
+use crate::commands::video_processing::VideoClip;
+
+// Opt-in control API for stream decks and scripts: a loopback-only HTTP server
+// with a WebSocket event stream. Every request needs the bearer token, either in
+// an "Authorization: Bearer <token>" header or as a URL-encoded ?token= query parameter.
+
+lazy_static::lazy_static! {
+    static ref CONTROL_API: Mutex<Option<RunningApi>> = Mutex::new(None);
+}
+
+pub const DEFAULT_PORT: u16 = 47800;
+
+// App events relayed to WebSocket clients
+const FORWARDED_EVENTS: [&str; 7] = [
+    "recorder-state-changed",
+    "recording-countdown",
+    "recording-marker-added",
+    "audio-clipping",
+    "export-job-progress",
+    "transcription-progress",
+    "watch-folder-event",
+];
+
+struct RunningApi {
+    port: u16,
+    token: String,
+    shutdown: oneshot::Sender<()>,
+    listeners: Vec<EventId>,
+}
+
+#[derive(Clone)]
+struct ApiState {
+    app: AppHandle,
+    events: broadcast::Sender<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlApiStatus {
+    pub running: bool,
+    pub url: Option<String>,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StartRecordingRequest {
+    screen_id: String,
+    #[serde(default)]
+    include_audio: bool,
+    #[serde(default)]
+    audio_sources: Vec<AudioSource>,
+    #[serde(default)]
+    include_webcam: bool,
+    #[serde(default)]
+    webcam: Option<WebcamSelection>,
+    pip_shape: Option<i32>,
+    pip_x: Option<f32>,
+    pip_y: Option<f32>,
+    pip_size: Option<f32>,
+    output_path: Option<String>, // defaults to the export directory and template
+    countdown: Option<u32>,
+    #[serde(default)]
+    limits: RecordingLimits,
+    #[serde(default)]
+    settings: RecordingSettings,
+    starts_at: Option<String>, // RFC 3339; schedules the recording instead of starting it now
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchExportRequest {
+    clips: Vec<VideoClip>,
+    output_name: String,
+    targets: Vec<BatchExportTarget>,
+}
+
+struct ApiError(StatusCode, String);
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        ApiError(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult = Result<Json<Value>, ApiError>;
+
+/// Start the control API on 127.0.0.1. A random token is generated unless one is given.
+#[command]
+pub async fn start_control_api(app: AppHandle, port: Option<u16>, token: Option<String>) -> Result<ControlApiStatus, String> {
+    if CONTROL_API.lock().map_err(|e| e.to_string())?.is_some() {
+        return Err("Control API is already running".to_string());
+    }
+
+    let token = match token {
+        Some(token) if token.len() < 16 => return Err("Control API token must be at least 16 characters".to_string()),
+        Some(token) => token,
+        None => {
+            let bytes: [u8; 24] = rand::thread_rng().gen();
+            bytes.iter().map(|b| format!("{:02x}", b)).collect()
+        }
+    };
+
+    let port = port.unwrap_or(DEFAULT_PORT);
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to bind control API to 127.0.0.1:{}: {}", port, e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+
+    let (events, _) = broadcast::channel(256);
+    let listeners = FORWARDED_EVENTS
+        .iter()
+        .map(|name| {
+            let events = events.clone();
+            app.listen_any(*name, move |event| {
+                let _ = events.send(format!(r#"{{"event":"{}","payload":{}}}"#, name, event.payload()));
+            })
+        })
+        .collect();
+
+    let state = ApiState { app: app.clone(), events };
+    let routes = Router::new()
+        .route("/api/recording/start", post(start_recording))
+        .route("/api/recording/stop", post(stop_recording))
+        .route("/api/recording/cancel", post(cancel_recording))
+        .route("/api/recording/markers", post(add_marker))
+        .route("/api/exports", post(start_export))
+        .route("/api/events", get(events_socket))
+        .merge(read_routes());
+    let router = with_token(routes, token.clone()).with_state(state);
+
+    let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+    tauri::async_runtime::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(async {
+            let _ = shutdown_signal.await;
+        });
+        if let Err(e) = server.await {
+            println!("❌ Control API stopped: {}", e);
+        }
+    });
+
+    *CONTROL_API.lock().map_err(|e| e.to_string())? = Some(RunningApi {
+        port,
+        token: token.clone(),
+        shutdown,
+        listeners,
+    });
+    println!("🌐 Control API listening on http://127.0.0.1:{}", port);
+
+    Ok(ControlApiStatus {
+        running: true,
+        url: Some(format!("http://127.0.0.1:{}", port)),
+        token: Some(token),
+    })
+}
+
+#[command]
+pub fn stop_control_api(app: AppHandle) -> Result<bool, String> {
+    let Some(running) = CONTROL_API.lock().map_err(|e| e.to_string())?.take() else {
+        return Ok(false);
+    };
+    for listener in running.listeners {
+        app.unlisten(listener);
+    }
+    let _ = running.shutdown.send(());
+    println!("🌐 Control API stopped");
+    Ok(true)
+}
+
+#[command]
+pub fn get_control_api_status() -> Result<ControlApiStatus, String> {
+    let running = CONTROL_API.lock().map_err(|e| e.to_string())?;
+    Ok(match running.as_ref() {
+        Some(api) => ControlApiStatus {
+            running: true,
+            url: Some(format!("http://127.0.0.1:{}", api.port)),
+            token: Some(api.token.clone()),
+        },
+        None => ControlApiStatus {
+            running: false,
+            url: None,
+            token: None,
+        },
+    })
+}
+
+// Routes that only read or cancel and don't need the app handle
+fn read_routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new()
+        .route("/api/status", get(status))
+        .route("/api/recording/markers", get(list_markers))
+        .route("/api/exports", get(list_exports))
+        .route("/api/exports/{job_id}", get(get_export))
+        .route("/api/exports/{job_id}/cancel", post(cancel_export))
+}
+
+fn with_token<S: Clone + Send + Sync + 'static>(router: Router<S>, token: String) -> Router<S> {
+    router.layer(middleware::from_fn_with_state(token, require_token))
+}
+
+async fn require_token(State(expected): State<String>, request: Request, next: Next) -> Response {
+    let from_header = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.to_string());
+    // Browser WebSocket clients can't set headers, so the token may also come in the query
+    let from_query = request
+        .uri()
+        .query()
+        .and_then(|q| form_urlencoded::parse(q.as_bytes()).find(|(key, _)| key == "token"))
+        .map(|(_, value)| value.into_owned());
+
+    match from_header.or(from_query) {
+        Some(token) if tokens_match(&token, &expected) => next.run(request).await,
+        _ => ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid token".to_string()).into_response(),
+    }
+}
+
+// Compare without bailing out at the first differing byte
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+async fn status() -> ApiResult {
+    Ok(Json(json!({ "recorder": recorder::current_state()? })))
+}
+
+async fn start_recording(State(state): State<ApiState>, Json(request): Json<StartRecordingRequest>) -> ApiResult {
+    let output_path = match request.output_path {
+        Some(path) => path,
+        None => output_paths::resolve_export_path(
+            "",
+            &TemplateValues {
+                project: Some("recording".to_string()),
+                ext: "mp4".to_string(),
+                ..TemplateValues::default()
+            },
+        )?,
+    };
+
+    let recording = RecordingRequest {
+        screen_id: request.screen_id,
+        include_audio: request.include_audio,
+        audio_sources: request.audio_sources,
+        include_webcam: request.include_webcam,
+        webcam: request.webcam,
+        pip_shape: request.pip_shape,
+        pip_x: request.pip_x,
+        pip_y: request.pip_y,
+        pip_size: request.pip_size,
+        output_path,
+        settings: request.settings,
+    };
+
+    if let Some(starts_at) = request.starts_at {
+        let state = scheduled_recording::schedule_recording(state.app.clone(), recording, starts_at, request.countdown, Some(request.limits))?;
+        return Ok(Json(json!({ "recorder": state })));
+    }
+
+    let output_path = screen_capture::start_recording(&state.app, recording, request.countdown, request.limits, recorder::RecorderState::Idle)
+        .await
+        .map_err(|e| ApiError(StatusCode::CONFLICT, e))?;
+    Ok(Json(json!({ "output_path": output_path })))
+}
+
+async fn stop_recording(State(state): State<ApiState>) -> ApiResult {
+    let output_path = screen_capture::stop_screen_recording(state.app.clone())
+        .await
+        .map_err(|e| ApiError(StatusCode::CONFLICT, e))?;
+    Ok(Json(json!({ "output_path": output_path })))
+}
+
+async fn cancel_recording(State(state): State<ApiState>) -> ApiResult {
+    let state = recorder::cancel_pending_recording(state.app.clone()).map_err(|e| ApiError(StatusCode::CONFLICT, e))?;
+    Ok(Json(json!({ "recorder": state })))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AddMarkerRequest {
+    label: Option<String>,
+}
+
+async fn list_markers() -> ApiResult {
+    Ok(Json(json!(markers::get_recording_markers(None)?)))
+}
+
+async fn add_marker(State(state): State<ApiState>, request: Option<Json<AddMarkerRequest>>) -> ApiResult {
+    let label = request.and_then(|Json(r)| r.label);
+    let marker = markers::add_recording_marker(state.app.clone(), label).map_err(|e| ApiError(StatusCode::CONFLICT, e))?;
+    Ok(Json(json!(marker)))
+}
+
+async fn list_exports() -> ApiResult {
+    Ok(Json(json!(export_jobs::list_export_jobs().await?)))
+}
+
+async fn start_export(State(state): State<ApiState>, Json(request): Json<BatchExportRequest>) -> ApiResult {
+    let job = export_jobs::start_batch_export(state.app.clone(), request.clips, request.output_name, request.targets).await?;
+    Ok(Json(json!(job)))
+}
+
+async fn get_export(Path(job_id): Path<String>) -> ApiResult {
+    let job = export_jobs::get_export_job(job_id)
+        .await
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e))?;
+    Ok(Json(json!(job)))
+}
+
+async fn cancel_export(Path(job_id): Path<String>) -> ApiResult {
+    Ok(Json(json!({ "cancelled": export_jobs::cancel_export_job(job_id).await? })))
+}
+
+async fn events_socket(State(state): State<ApiState>, upgrade: WebSocketUpgrade) -> Response {
+    let events = state.events.subscribe();
+    upgrade.on_upgrade(move |socket| relay_events(socket, events))
+}
+
+// Send the current recorder state, then every forwarded event until the client goes away
+async fn relay_events(mut socket: WebSocket, mut events: broadcast::Receiver<String>) {
+    if let Ok(state) = recorder::current_state() {
+        let hello = json!({ "event": "recorder-state-changed", "payload": state }).to_string();
+        if socket.send(Message::Text(hello.into())).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if socket.send(Message::Text(event.into())).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{self, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "0123456789abcdef+/=";
+
+    fn app() -> Router {
+        with_token(read_routes(), TOKEN.to_string())
+    }
+
+    async fn send(request: Request<Body>) -> (StatusCode, Value) {
+        let response = app().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    fn get_with_header(uri: &str, authorization: &str) -> Request<Body> {
+        Request::get(uri).header(header::AUTHORIZATION, authorization).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_token() {
+        let (status, body) = send(Request::get("/api/status").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "Missing or invalid token");
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_token() {
+        let (status, _) = send(get_with_header("/api/status", "Bearer 0123456789abcdef+/x")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(get_with_header("/api/status", TOKEN)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(Request::get("/api/status?token=0123456789abcdef").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn accepts_bearer_header() {
+        let (status, body) = send(get_with_header("/api/status", &format!("Bearer {}", TOKEN))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.get("recorder").is_some());
+    }
+
+    #[tokio::test]
+    async fn decodes_query_token() {
+        let uri = "/api/status?other=1&token=0123456789abcdef%2B%2F%3D";
+        let (status, _) = send(Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        // Undecoded, "+" would be read as a space
+        let (status, _) = send(Request::get("/api/status?token=0123456789abcdef+/=").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn unknown_export_job_is_not_found() {
+        let (status, body) = send(get_with_header("/api/exports/missing", &format!("Bearer {}", TOKEN))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "Export job not found: missing");
+    }
+
+    #[tokio::test]
+    async fn cancelling_unknown_export_job_reports_false() {
+        let request = Request::post("/api/exports/missing/cancel")
+            .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = send(request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "cancelled": false }));
+    }
+
+    #[tokio::test]
+    async fn lists_exports_and_markers() {
+        let (status, body) = send(get_with_header("/api/exports", &format!("Bearer {}", TOKEN))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.is_array());
+        let (status, body) = send(get_with_header("/api/recording/markers", &format!("Bearer {}", TOKEN))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.is_array());
+    }
+
+    #[tokio::test]
+    async fn unknown_route_still_needs_token() {
+        let (status, _) = send(Request::get("/api/nothing").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn tokens_compare_by_value_and_length() {
+        assert!(tokens_match(TOKEN, TOKEN));
+        assert!(!tokens_match("0123456789abcdef", TOKEN));
+        assert!(!tokens_match("", TOKEN));
+    }
+}
@@ -0,0 +1,433 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter};
+
+use crate::commands::atomic_write;
+use crate::commands::export_settings::{EncodeSettings, ExportSettings};
+use crate::commands::ffmpeg::RunControl;
+use crate::commands::ffmpeg_export;
+use crate::commands::output_paths::{self, TemplateValues};
+use crate::commands::video_processing::{self, VideoClip};
+
+// Export job queue: jobs run one at a time on a background worker so a long
+// batch doesn't hold up the command it was started from
+
+lazy_static::lazy_static! {
+    static ref EXPORT_JOBS: Arc<Mutex<Vec<ExportJob>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref CANCEL_FLAGS: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>> = Arc::new(Mutex::new(HashMap::new()));
+    static ref JOB_QUEUE: Mutex<Option<Sender<QueuedJob>>> = Mutex::new(None);
+}
+
+// Keeps ids unique when several jobs are queued within the same millisecond
+static NEXT_JOB_NUMBER: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// One output of a batch export. `output_path` overrides the name derived from the batch's output name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchExportTarget {
+    pub settings: ExportSettings,
+    pub output_path: Option<String>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTargetResult {
+    pub label: String,
+    pub output_path: String,
+    pub status: JobStatus,
+    pub progress: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportJob {
+    pub id: String,
+    pub status: JobStatus,
+    pub progress: f64, // across all targets
+    pub created_at: String,
+    pub targets: Vec<ExportTargetResult>,
+}
+
+/// Work done around a queued export: `prepare` builds the clips to render once the
+/// job reaches the worker, and `on_finished` is handed the job's final state
+#[derive(Default)]
+pub struct JobHooks {
+    pub prepare: Option<PrepareClips>,
+    pub on_finished: Option<OnFinished>,
+}
+
+pub type PrepareClips = Box<dyn FnOnce() -> Result<Vec<VideoClip>, String> + Send>;
+pub type OnFinished = Box<dyn FnOnce(&ExportJob) + Send>;
+
+struct QueuedJob {
+    id: String,
+    app: AppHandle,
+    clips: Vec<VideoClip>,
+    targets: Vec<PlannedTarget>,
+    hooks: JobHooks,
+}
+
+struct PlannedTarget {
+    settings: ExportSettings,
+    encode: EncodeSettings,
+    output_path: String,
+    partial_path: String, // every exporter renders here and the file is moved into place when done
+}
+
+impl PlannedTarget {
+    fn is_native(&self) -> bool {
+        self.encode.native_compatible && cfg!(target_os = "macos")
+    }
+}
+
+#[command]
+pub async fn start_batch_export(
+    app: AppHandle,
+    clips: Vec<VideoClip>,
+    output_name: String,
+    targets: Vec<BatchExportTarget>,
+) -> Result<ExportJob, String> {
+    queue_export(app, clips, output_name, targets, JobHooks::default())
+}
+
+/// Plan and queue an export job. With a `prepare` hook, `clips` only name the sources
+/// for planning and the clips actually rendered are built when the job starts.
+pub fn queue_export(
+    app: AppHandle,
+    clips: Vec<VideoClip>,
+    output_name: String,
+    targets: Vec<BatchExportTarget>,
+    hooks: JobHooks,
+) -> Result<ExportJob, String> {
+    if targets.is_empty() {
+        return Err("No export targets given".to_string());
+    }
+
+    // Validate every target up front so a bad one doesn't fail the batch halfway through
+    let mut planned = Vec::new();
+    let mut results = Vec::new();
+    // Prepared clips may be trimmed, which only the ffmpeg pipeline renders
+    let needs_compositor = ffmpeg_export::needs_compositor(&clips) || hooks.prepare.is_some();
+    for (index, target) in targets.into_iter().enumerate() {
+        let mut encode = target
+            .settings
+            .resolve()
+            .map_err(|e| format!("Target {}: {}", index + 1, e))?;
+        encode.native_compatible &= !needs_compositor;
+        let mut label = target.label.clone().unwrap_or_else(|| target_label(&target.settings, &encode));
+        if results.iter().any(|r: &ExportTargetResult| r.label == label) {
+            label = format!("{}_{}", label, index + 1);
+        }
+
+        // Targets without their own path are named from the output template, with
+        // the label appended if the template alone would repeat a file name
+        let values = TemplateValues {
+            project: Some(output_name.clone()),
+            resolution: Some(encode.frame.label()),
+            preset: target.settings.export_preset.clone(),
+            ext: encode.container.extension().to_string(),
+        };
+        let template = output_paths::output_config()?.template;
+        let mut output_path = match &target.output_path {
+            Some(path) => output_paths::resolve_export_path(path, &values)?,
+            None => output_paths::resolve_export_path(&template, &values)?,
+        };
+        if planned.iter().any(|p: &PlannedTarget| p.output_path == output_path) && target.output_path.is_none() {
+            let labelled = template.replace(".{ext}", &format!("_{}.{{ext}}", label));
+            output_path = output_paths::resolve_export_path(&labelled, &values)?;
+        }
+        if planned.iter().any(|p: &PlannedTarget| p.output_path == output_path) {
+            return Err(format!("Two targets would write to {}", output_path));
+        }
+
+        results.push(ExportTargetResult {
+            label,
+            output_path: output_path.clone(),
+            status: JobStatus::Queued,
+            progress: 0.0,
+            error: None,
+        });
+        planned.push(PlannedTarget {
+            settings: target.settings,
+            encode,
+            partial_path: atomic_write::partial_path(&output_path),
+            output_path,
+        });
+    }
+
+    let job = ExportJob {
+        id: format!(
+            "export_{}_{}",
+            chrono::Utc::now().timestamp_millis(),
+            NEXT_JOB_NUMBER.fetch_add(1, Ordering::SeqCst)
+        ),
+        status: JobStatus::Queued,
+        progress: 0.0,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        targets: results,
+    };
+
+    EXPORT_JOBS.lock().map_err(|e| format!("Failed to lock export jobs: {}", e))?.push(job.clone());
+    CANCEL_FLAGS
+        .lock()
+        .map_err(|e| format!("Failed to lock export jobs: {}", e))?
+        .insert(job.id.clone(), Arc::new(AtomicBool::new(false)));
+
+    queue_sender()?
+        .send(QueuedJob {
+            id: job.id.clone(),
+            app,
+            clips,
+            targets: planned,
+            hooks,
+        })
+        .map_err(|e| format!("Failed to queue export job: {}", e))?;
+
+    println!("📋 Queued export job {} with {} target(s)", job.id, job.targets.len());
+    Ok(job)
+}
+
+#[command]
+pub async fn get_export_job(job_id: String) -> Result<ExportJob, String> {
+    let jobs = EXPORT_JOBS.lock().map_err(|e| format!("Failed to lock export jobs: {}", e))?;
+    jobs.iter()
+        .find(|j| j.id == job_id)
+        .cloned()
+        .ok_or_else(|| format!("Export job not found: {}", job_id))
+}
+
+#[command]
+pub async fn list_export_jobs() -> Result<Vec<ExportJob>, String> {
+    let jobs = EXPORT_JOBS.lock().map_err(|e| format!("Failed to lock export jobs: {}", e))?;
+    Ok(jobs.clone())
+}
+
+// Stops the running ffmpeg process; a queued job is dropped before it starts.
+// The native AVFoundation exporter can't be interrupted and finishes its current target.
+#[command]
+pub async fn cancel_export_job(job_id: String) -> Result<bool, String> {
+    let flags = CANCEL_FLAGS.lock().map_err(|e| format!("Failed to lock export jobs: {}", e))?;
+    match flags.get(&job_id) {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            println!("🛑 Cancelling export job {}", job_id);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+// Start the worker on first use and hand back the queue feeding it
+fn queue_sender() -> Result<Sender<QueuedJob>, String> {
+    let mut queue = JOB_QUEUE.lock().map_err(|e| format!("Failed to lock export queue: {}", e))?;
+    if let Some(sender) = queue.as_ref() {
+        return Ok(sender.clone());
+    }
+
+    let (sender, receiver) = mpsc::channel::<QueuedJob>();
+    std::thread::spawn(move || {
+        for job in receiver {
+            run_job(job);
+        }
+    });
+    *queue = Some(sender.clone());
+    Ok(sender)
+}
+
+// Name for a target in job results, e.g. "youtube" or "1080p_9x16_mp4"
+fn target_label(settings: &ExportSettings, encode: &EncodeSettings) -> String {
+    match &settings.export_preset {
+        Some(preset) => preset.clone(),
+        None => format!("{}_{}", encode.frame.label(), encode.container.extension()),
+    }
+}
+
+fn run_job(mut job: QueuedJob) {
+    let cancelled = CANCEL_FLAGS
+        .lock()
+        .ok()
+        .and_then(|flags| flags.get(&job.id).cloned())
+        .unwrap_or_else(|| Arc::new(AtomicBool::new(false)));
+
+    update_job(&job, |state| state.status = JobStatus::Running);
+    println!("🎬 Starting export job {}", job.id);
+
+    let prepared = match job.hooks.prepare.take() {
+        Some(prepare) => prepare().map(|clips| job.clips = clips),
+        None => Ok(()),
+    };
+    if let Err(e) = &prepared {
+        println!("❌ Export job {} failed before rendering: {}", job.id, e);
+        let all: Vec<usize> = (0..job.targets.len()).collect();
+        mark_targets(&job, &all, JobStatus::Failed, Some(e.clone()));
+    }
+
+    // Video targets that go through ffmpeg share one decode and composite;
+    // native, animated and audio-only targets each run on their own
+    let (shared, single): (Vec<usize>, Vec<usize>) = (0..job.targets.len()).partition(|&i| {
+        let target = &job.targets[i];
+        target.encode.video.is_some() && !target.encode.container.is_animated() && !target.is_native()
+    });
+
+    if !shared.is_empty() && prepared.is_ok() && !cancelled.load(Ordering::SeqCst) {
+        let outputs: Vec<(String, &EncodeSettings)> = shared
+            .iter()
+            .map(|&i| (job.targets[i].partial_path.clone(), &job.targets[i].encode))
+            .collect();
+        mark_targets(&job, &shared, JobStatus::Running, None);
+
+        let on_progress = |progress: f64| set_target_progress(&job, &shared, progress);
+        let control = RunControl {
+            cancelled: &cancelled,
+            on_progress: &on_progress,
+        };
+        let result = ffmpeg_export::export_video_targets(&job.clips, &outputs, Some(&control));
+
+        for &i in &shared {
+            let result = result.clone().and_then(|_| finish_target(&job.clips, &job.targets[i]));
+            complete_target(&job, i, result, &cancelled);
+        }
+    }
+
+    for i in single {
+        if prepared.is_err() || cancelled.load(Ordering::SeqCst) {
+            break;
+        }
+        mark_targets(&job, &[i], JobStatus::Running, None);
+
+        let on_progress = |progress: f64| set_target_progress(&job, &[i], progress);
+        let control = RunControl {
+            cancelled: &cancelled,
+            on_progress: &on_progress,
+        };
+        let result = export_single(&job.clips, &job.targets[i], &control).and_then(|_| finish_target(&job.clips, &job.targets[i]));
+        complete_target(&job, i, result, &cancelled);
+    }
+
+    // Anything that never started was cut off by a cancel
+    update_job(&job, |state| {
+        for target in state.targets.iter_mut() {
+            if target.status == JobStatus::Queued || target.status == JobStatus::Running {
+                target.status = JobStatus::Cancelled;
+            }
+        }
+        state.status = if state.targets.iter().all(|t| t.status == JobStatus::Completed) {
+            JobStatus::Completed
+        } else if state.targets.iter().any(|t| t.status == JobStatus::Cancelled) {
+            JobStatus::Cancelled
+        } else {
+            JobStatus::Failed
+        };
+    });
+
+    if let Ok(mut flags) = CANCEL_FLAGS.lock() {
+        flags.remove(&job.id);
+    }
+    println!("🏁 Export job {} finished", job.id);
+
+    if let Some(on_finished) = job.hooks.on_finished.take() {
+        let state = EXPORT_JOBS.lock().ok().and_then(|jobs| jobs.iter().find(|j| j.id == job.id).cloned());
+        if let Some(state) = state {
+            on_finished(&state);
+        }
+    }
+}
+
+fn export_single(clips: &[VideoClip], target: &PlannedTarget, control: &RunControl) -> Result<(), String> {
+    let encode = &target.encode;
+    let path = &target.partial_path;
+    if encode.container.is_animated() {
+        let options = target.settings.animation.clone().unwrap_or_default();
+        ffmpeg_export::export_animated(clips, path, encode.container, &encode.frame, &options, Some(control))
+    } else if encode.container.is_audio_only() {
+        let audio = encode.audio.as_ref().ok_or("Audio export settings are missing")?;
+        ffmpeg_export::export_audio(clips, path, encode.container, audio, Some(control))
+    } else if target.is_native() {
+        video_processing::export_with_avfoundation(clips.to_vec(), path, encode).map(|_| ())
+    } else {
+        ffmpeg_export::export_video(clips, path, encode, Some(control))
+    }
+}
+
+// Post-processing that runs once the encode itself is done, then the move into place.
+// Returns the final path, which the collision policy may have changed.
+fn finish_target(clips: &[VideoClip], target: &PlannedTarget) -> Result<String, String> {
+    if let Some(caption_options) = &target.settings.captions {
+        crate::commands::captions::apply_captions_in_place(&target.partial_path, caption_options, &target.encode)?;
+    }
+    if target.settings.chapters == Some(true) {
+        crate::commands::markers::apply_chapters(clips, &target.partial_path, &target.output_path, target.encode.container)?;
+    }
+    output_paths::commit_export(&target.partial_path, &target.output_path)
+}
+
+fn complete_target(job: &QueuedJob, index: usize, result: Result<String, String>, cancelled: &AtomicBool) {
+    let target = &job.targets[index];
+    if result.is_err() {
+        atomic_write::discard(&target.partial_path);
+    }
+
+    match result {
+        Ok(output_path) => {
+            println!("✅ Export target ready: {}", output_path);
+            crate::commands::tray::remember_export(&output_path);
+            update_job(job, |state| state.targets[index].output_path = output_path);
+            mark_targets(job, &[index], JobStatus::Completed, None);
+        }
+        Err(_) if cancelled.load(Ordering::SeqCst) => {
+            mark_targets(job, &[index], JobStatus::Cancelled, None);
+        }
+        Err(e) => {
+            println!("❌ Export target failed: {} ({})", job.targets[index].output_path, e);
+            mark_targets(job, &[index], JobStatus::Failed, Some(e));
+        }
+    }
+}
+
+fn mark_targets(job: &QueuedJob, indices: &[usize], status: JobStatus, error: Option<String>) {
+    update_job(job, |state| {
+        for &i in indices {
+            state.targets[i].status = status;
+            state.targets[i].error = error.clone();
+            if status == JobStatus::Completed {
+                state.targets[i].progress = 1.0;
+            }
+        }
+    });
+}
+
+fn set_target_progress(job: &QueuedJob, indices: &[usize], progress: f64) {
+    update_job(job, |state| {
+        for &i in indices {
+            state.targets[i].progress = progress;
+        }
+    });
+}
+
+// Apply a change to the job's shared state and tell the frontend about it
+fn update_job(job: &QueuedJob, change: impl FnOnce(&mut ExportJob)) {
+    let snapshot = {
+        let Ok(mut jobs) = EXPORT_JOBS.lock() else {
+            return;
+        };
+        let Some(state) = jobs.iter_mut().find(|j| j.id == job.id) else {
+            return;
+        };
+        change(state);
+        state.progress = state.targets.iter().map(|t| t.progress).sum::<f64>() / state.targets.len().max(1) as f64;
+        state.clone()
+    };
+    let _ = job.app.emit("export-job-progress", snapshot);
+}
@@ -0,0 +1,807 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::captions::CaptionExportOptions;
+use crate::commands::ffmpeg_export::AnimatedExportOptions;
+use crate::commands::reframe::{AspectRatio, FitMode, FocusPoint, NamedResolution, OutputFrame, Resolution};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Container {
+    #[serde(alias = "mpeg4")]
+    Mp4,
+    #[serde(alias = "quicktime")]
+    Mov,
+    Webm,
+    Mkv,
+    Gif,
+    Webp,
+    Mp3,
+    #[serde(alias = "aac")]
+    M4a,
+    Wav,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    H264,
+    #[serde(alias = "h265")]
+    Hevc,
+    Vp9,
+    Av1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    Aac,
+    Opus,
+    Mp3,
+    Pcm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PixelFormat {
+    Yuv420p,
+    Yuv422p,
+    Yuv444p,
+    Yuv420p10le,
+}
+
+// Shorthand kept from the original settings; expands to a per-codec CRF
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+    High,
+    Medium,
+    Low,
+}
+
+// x264-style speed/efficiency trade-off, mapped onto each encoder's own scale
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncoderPreset {
+    Ultrafast,
+    Superfast,
+    Veryfast,
+    Faster,
+    Fast,
+    Medium,
+    Slow,
+    Slower,
+    Veryslow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum RateControl {
+    Crf { crf: u32 },
+    Bitrate { kbps: u32 },
+}
+
+/// Export settings as sent by the frontend. Everything is optional: a named
+/// `export_preset` is expanded first and explicit fields override it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+    pub export_preset: Option<String>,
+    #[serde(alias = "format")]
+    pub container: Option<Container>,
+    pub resolution: Option<Resolution>,
+    pub aspect_ratio: Option<AspectRatio>,
+    pub fit: Option<FitMode>,
+    pub focus: Option<FocusPoint>,
+    pub background_color: Option<String>,
+    pub quality: Option<Quality>,
+    pub preset: Option<EncoderPreset>,
+    pub video_codec: Option<VideoCodec>,
+    pub rate_control: Option<RateControl>,
+    pub max_bitrate_kbps: Option<u32>,
+    pub gop: Option<u32>,
+    pub pixel_format: Option<PixelFormat>,
+    pub frame_rate: Option<f64>,
+    pub audio_codec: Option<AudioCodec>,
+    pub audio_bitrate_kbps: Option<u32>,
+    pub audio_sample_rate: Option<u32>,
+    pub audio_channels: Option<u32>,
+    pub captions: Option<CaptionExportOptions>,
+    pub animation: Option<AnimatedExportOptions>, // used when the container is gif or webp
+    pub chapters: Option<bool>, // embed recording markers as chapters and write a chapter list
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VideoEncodeSettings {
+    pub codec: VideoCodec,
+    pub rate_control: RateControl,
+    pub max_bitrate_kbps: Option<u32>,
+    pub gop: Option<u32>,
+    pub pixel_format: PixelFormat,
+    pub frame_rate: Option<f64>, // None keeps the source rate
+    pub preset: EncoderPreset,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AudioEncodeSettings {
+    pub codec: AudioCodec,
+    pub bitrate_kbps: u32, // ignored for PCM
+    pub sample_rate: u32,
+    pub channels: u32,
+}
+
+/// Fully resolved and validated settings the exporters work from
+#[derive(Debug, Clone, Serialize)]
+pub struct EncodeSettings {
+    pub container: Container,
+    pub frame: OutputFrame,
+    pub quality: Quality,
+    pub video: Option<VideoEncodeSettings>, // None for audio-only and animated containers
+    pub audio: Option<AudioEncodeSettings>, // None for animated containers
+    pub native_compatible: bool, // plain H.264 MP4/MOV that AVFoundation can produce itself
+}
+
+pub const NAMED_PRESETS: [&str; 10] = [
+    "web", "youtube", "high-quality", "source", "mobile", "webm", "gif", "podcast", "vertical", "square",
+];
+
+impl Container {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mov => "mov",
+            Container::Webm => "webm",
+            Container::Mkv => "mkv",
+            Container::Gif => "gif",
+            Container::Webp => "webp",
+            Container::Mp3 => "mp3",
+            Container::M4a => "m4a",
+            Container::Wav => "wav",
+        }
+    }
+
+    pub fn is_animated(&self) -> bool {
+        matches!(self, Container::Gif | Container::Webp)
+    }
+
+    pub fn is_audio_only(&self) -> bool {
+        matches!(self, Container::Mp3 | Container::M4a | Container::Wav)
+    }
+
+    pub fn supports_chapters(&self) -> bool {
+        matches!(self, Container::Mp4 | Container::Mov | Container::Mkv | Container::Webm | Container::M4a)
+    }
+
+    fn supports_video(&self, codec: VideoCodec) -> bool {
+        match self {
+            Container::Mp4 => matches!(codec, VideoCodec::H264 | VideoCodec::Hevc | VideoCodec::Av1),
+            Container::Mov => matches!(codec, VideoCodec::H264 | VideoCodec::Hevc),
+            Container::Webm => matches!(codec, VideoCodec::Vp9 | VideoCodec::Av1),
+            Container::Mkv => true,
+            _ => false,
+        }
+    }
+
+    fn supports_audio(&self, codec: AudioCodec) -> bool {
+        match self {
+            Container::Mp4 => matches!(codec, AudioCodec::Aac | AudioCodec::Mp3 | AudioCodec::Opus),
+            Container::Mov => matches!(codec, AudioCodec::Aac | AudioCodec::Pcm),
+            Container::Webm => codec == AudioCodec::Opus,
+            Container::Mkv => true,
+            Container::Mp3 => codec == AudioCodec::Mp3,
+            Container::M4a => codec == AudioCodec::Aac,
+            Container::Wav => codec == AudioCodec::Pcm,
+            Container::Gif | Container::Webp => false,
+        }
+    }
+
+    fn default_video_codec(&self) -> VideoCodec {
+        match self {
+            Container::Webm => VideoCodec::Vp9,
+            _ => VideoCodec::H264,
+        }
+    }
+
+    fn default_audio_codec(&self) -> AudioCodec {
+        match self {
+            Container::Webm => AudioCodec::Opus,
+            Container::Mp3 => AudioCodec::Mp3,
+            Container::Wav => AudioCodec::Pcm,
+            _ => AudioCodec::Aac,
+        }
+    }
+}
+
+impl VideoCodec {
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::Hevc => "libx265",
+            VideoCodec::Vp9 => "libvpx-vp9",
+            VideoCodec::Av1 => "libsvtav1",
+        }
+    }
+
+    fn max_crf(&self) -> u32 {
+        match self {
+            VideoCodec::H264 | VideoCodec::Hevc => 51,
+            VideoCodec::Vp9 | VideoCodec::Av1 => 63,
+        }
+    }
+
+    fn crf_for(&self, quality: Quality) -> u32 {
+        match (self, quality) {
+            (VideoCodec::H264, Quality::High) => 18,
+            (VideoCodec::H264, Quality::Medium) => 23,
+            (VideoCodec::H264, Quality::Low) => 28,
+            (VideoCodec::Hevc, Quality::High) => 20,
+            (VideoCodec::Hevc, Quality::Medium) => 26,
+            (VideoCodec::Hevc, Quality::Low) => 30,
+            (VideoCodec::Vp9, Quality::High) => 24,
+            (VideoCodec::Vp9, Quality::Medium) => 31,
+            (VideoCodec::Vp9, Quality::Low) => 37,
+            (VideoCodec::Av1, Quality::High) => 24,
+            (VideoCodec::Av1, Quality::Medium) => 32,
+            (VideoCodec::Av1, Quality::Low) => 40,
+        }
+    }
+}
+
+impl AudioCodec {
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "libopus",
+            AudioCodec::Mp3 => "libmp3lame",
+            AudioCodec::Pcm => "pcm_s16le",
+        }
+    }
+}
+
+impl PixelFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PixelFormat::Yuv420p => "yuv420p",
+            PixelFormat::Yuv422p => "yuv422p",
+            PixelFormat::Yuv444p => "yuv444p",
+            PixelFormat::Yuv420p10le => "yuv420p10le",
+        }
+    }
+}
+
+impl Quality {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Quality::High => "high",
+            Quality::Medium => "medium",
+            Quality::Low => "low",
+        }
+    }
+
+    fn audio_bitrate_kbps(&self) -> u32 {
+        match self {
+            Quality::High => 192,
+            Quality::Medium => 128,
+            Quality::Low => 96,
+        }
+    }
+}
+
+impl EncoderPreset {
+    fn index(&self) -> u32 {
+        *self as u32
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            EncoderPreset::Ultrafast => "ultrafast",
+            EncoderPreset::Superfast => "superfast",
+            EncoderPreset::Veryfast => "veryfast",
+            EncoderPreset::Faster => "faster",
+            EncoderPreset::Fast => "fast",
+            EncoderPreset::Medium => "medium",
+            EncoderPreset::Slow => "slow",
+            EncoderPreset::Slower => "slower",
+            EncoderPreset::Veryslow => "veryslow",
+        }
+    }
+}
+
+impl ExportSettings {
+    /// Expand one of the named presets (the same ids the export dialog offers)
+    pub fn named(name: &str) -> Result<Self, String> {
+        let settings = match name {
+            "web" => ExportSettings {
+                container: Some(Container::Mp4),
+                resolution: Some(Resolution::Named(NamedResolution::P720)),
+                quality: Some(Quality::Medium),
+                preset: Some(EncoderPreset::Fast),
+                audio_bitrate_kbps: Some(128),
+                ..Default::default()
+            },
+            "youtube" => ExportSettings {
+                container: Some(Container::Mp4),
+                resolution: Some(Resolution::Named(NamedResolution::P1080)),
+                quality: Some(Quality::High),
+                preset: Some(EncoderPreset::Medium),
+                audio_bitrate_kbps: Some(192),
+                ..Default::default()
+            },
+            "high-quality" => ExportSettings {
+                container: Some(Container::Mp4),
+                resolution: Some(Resolution::Named(NamedResolution::P1080)),
+                quality: Some(Quality::High),
+                preset: Some(EncoderPreset::Slow),
+                audio_bitrate_kbps: Some(256),
+                ..Default::default()
+            },
+            "source" => ExportSettings {
+                container: Some(Container::Mp4),
+                resolution: Some(Resolution::Named(NamedResolution::Source)),
+                quality: Some(Quality::High),
+                preset: Some(EncoderPreset::Medium),
+                audio_bitrate_kbps: Some(256),
+                ..Default::default()
+            },
+            "mobile" => ExportSettings {
+                container: Some(Container::Mp4),
+                resolution: Some(Resolution::Named(NamedResolution::P480)),
+                quality: Some(Quality::Low),
+                preset: Some(EncoderPreset::Fast),
+                audio_bitrate_kbps: Some(96),
+                ..Default::default()
+            },
+            "webm" => ExportSettings {
+                container: Some(Container::Webm),
+                resolution: Some(Resolution::Named(NamedResolution::P720)),
+                quality: Some(Quality::Medium),
+                video_codec: Some(VideoCodec::Vp9),
+                audio_bitrate_kbps: Some(128),
+                ..Default::default()
+            },
+            "gif" => ExportSettings {
+                container: Some(Container::Gif),
+                resolution: Some(Resolution::Named(NamedResolution::P480)),
+                quality: Some(Quality::Low),
+                animation: Some(AnimatedExportOptions {
+                    fps: Some(15),
+                    max_width: Some(854),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            // Short-form vertical and square crops of a landscape recording
+            "vertical" => ExportSettings {
+                container: Some(Container::Mp4),
+                resolution: Some(Resolution::Named(NamedResolution::P1080)),
+                aspect_ratio: Some(AspectRatio::Vertical),
+                fit: Some(FitMode::Fill),
+                quality: Some(Quality::High),
+                audio_bitrate_kbps: Some(192),
+                ..Default::default()
+            },
+            "square" => ExportSettings {
+                container: Some(Container::Mp4),
+                resolution: Some(Resolution::Named(NamedResolution::P1080)),
+                aspect_ratio: Some(AspectRatio::Square),
+                fit: Some(FitMode::Fill),
+                quality: Some(Quality::High),
+                audio_bitrate_kbps: Some(192),
+                ..Default::default()
+            },
+            "podcast" => ExportSettings {
+                container: Some(Container::Mp3),
+                quality: Some(Quality::High),
+                audio_bitrate_kbps: Some(192),
+                ..Default::default()
+            },
+            _ => {
+                return Err(format!(
+                    "Unknown export preset '{}', expected one of {:?}",
+                    name, NAMED_PRESETS
+                ))
+            }
+        };
+
+        Ok(ExportSettings {
+            export_preset: Some(name.to_string()),
+            ..settings
+        })
+    }
+
+    // Fields set on `self` win over the ones from `base`
+    fn overlay(self, base: ExportSettings) -> ExportSettings {
+        ExportSettings {
+            export_preset: self.export_preset.or(base.export_preset),
+            container: self.container.or(base.container),
+            resolution: self.resolution.or(base.resolution),
+            aspect_ratio: self.aspect_ratio.or(base.aspect_ratio),
+            fit: self.fit.or(base.fit),
+            focus: self.focus.or(base.focus),
+            background_color: self.background_color.or(base.background_color),
+            quality: self.quality.or(base.quality),
+            preset: self.preset.or(base.preset),
+            video_codec: self.video_codec.or(base.video_codec),
+            rate_control: self.rate_control.or(base.rate_control),
+            max_bitrate_kbps: self.max_bitrate_kbps.or(base.max_bitrate_kbps),
+            gop: self.gop.or(base.gop),
+            pixel_format: self.pixel_format.or(base.pixel_format),
+            frame_rate: self.frame_rate.or(base.frame_rate),
+            audio_codec: self.audio_codec.or(base.audio_codec),
+            audio_bitrate_kbps: self.audio_bitrate_kbps.or(base.audio_bitrate_kbps),
+            audio_sample_rate: self.audio_sample_rate.or(base.audio_sample_rate),
+            audio_channels: self.audio_channels.or(base.audio_channels),
+            captions: self.captions.or(base.captions),
+            animation: self.animation.or(base.animation),
+            chapters: self.chapters.or(base.chapters),
+        }
+    }
+
+    /// Expand the named preset, fill in defaults and reject unsupported combinations
+    pub fn resolve(&self) -> Result<EncodeSettings, String> {
+        let settings = match &self.export_preset {
+            Some(name) => self.clone().overlay(ExportSettings::named(name)?),
+            None => self.clone(),
+        };
+
+        let container = settings.container.unwrap_or(Container::Mp4);
+        let quality = settings.quality.unwrap_or(Quality::Medium);
+
+        let frame = OutputFrame::new(
+            settings.resolution.unwrap_or(Resolution::Named(NamedResolution::P1080)),
+            settings.aspect_ratio,
+            settings.fit,
+            settings.focus,
+            settings.background_color.clone(),
+        )?;
+
+        if container.is_animated() || container.is_audio_only() {
+            let video_fields_set = settings.video_codec.is_some()
+                || settings.rate_control.is_some()
+                || settings.max_bitrate_kbps.is_some()
+                || settings.gop.is_some()
+                || settings.pixel_format.is_some();
+            if video_fields_set {
+                return Err(format!(
+                    "Video encoder settings don't apply to {} exports",
+                    container.extension().to_uppercase()
+                ));
+            }
+        }
+        if settings.captions.is_some() && (container.is_animated() || container.is_audio_only()) {
+            return Err(format!("Captions can't be added to {} exports", container.extension().to_uppercase()));
+        }
+        if container.is_animated() && (settings.audio_codec.is_some() || settings.audio_bitrate_kbps.is_some()) {
+            return Err(format!("{} exports have no audio track", container.extension().to_uppercase()));
+        }
+
+        let video = if container.is_animated() || container.is_audio_only() {
+            None
+        } else {
+            Some(resolve_video(&settings, container, quality)?)
+        };
+        let audio = if container.is_animated() {
+            None
+        } else {
+            Some(resolve_audio(&settings, container, quality)?)
+        };
+
+        // The Objective-C exporter only knows how to scale to its fixed list of sizes
+        let native_resolution = matches!(
+            frame.resolution,
+            Resolution::Named(
+                NamedResolution::Source
+                    | NamedResolution::P1080
+                    | NamedResolution::P720
+                    | NamedResolution::P480
+                    | NamedResolution::P360
+            )
+        );
+        let native_compatible = matches!(container, Container::Mp4 | Container::Mov)
+            && native_resolution
+            && frame.is_plain_scale()
+            && settings.video_codec.unwrap_or(VideoCodec::H264) == VideoCodec::H264
+            && settings.rate_control.is_none()
+            && settings.max_bitrate_kbps.is_none()
+            && settings.gop.is_none()
+            && settings.pixel_format.unwrap_or(PixelFormat::Yuv420p) == PixelFormat::Yuv420p
+            && settings.frame_rate.is_none()
+            // The x264 speed preset has no AVFoundation counterpart and is ignored there
+            && settings.audio_codec.unwrap_or(AudioCodec::Aac) == AudioCodec::Aac
+            && settings.audio_bitrate_kbps.is_none_or(|kbps| kbps == native_audio_bitrate(quality));
+
+        Ok(EncodeSettings {
+            container,
+            frame,
+            quality,
+            video,
+            audio,
+            native_compatible,
+        })
+    }
+}
+
+// Audio bitrate the Objective-C exporter uses for each quality (get_audio_bitrate)
+fn native_audio_bitrate(quality: Quality) -> u32 {
+    match quality {
+        Quality::High => 192,
+        Quality::Medium => 128,
+        Quality::Low => 96,
+    }
+}
+
+fn resolve_video(settings: &ExportSettings, container: Container, quality: Quality) -> Result<VideoEncodeSettings, String> {
+    let codec = settings.video_codec.unwrap_or_else(|| container.default_video_codec());
+    if !container.supports_video(codec) {
+        return Err(format!(
+            "{:?} video can't be stored in a {} file",
+            codec,
+            container.extension().to_uppercase()
+        ));
+    }
+
+    let rate_control = settings
+        .rate_control
+        .unwrap_or(RateControl::Crf { crf: codec.crf_for(quality) });
+    match rate_control {
+        RateControl::Crf { crf } if crf > codec.max_crf() => {
+            return Err(format!("CRF {} is out of range for {:?} (0-{})", crf, codec, codec.max_crf()));
+        }
+        RateControl::Bitrate { kbps } if !(100..=200_000).contains(&kbps) => {
+            return Err(format!("Video bitrate {} kbps is out of range (100-200000)", kbps));
+        }
+        _ => {}
+    }
+
+    if let Some(max) = settings.max_bitrate_kbps {
+        if let RateControl::Bitrate { kbps } = rate_control {
+            if max < kbps {
+                return Err(format!("Max bitrate {} kbps is below the target bitrate {} kbps", max, kbps));
+            }
+        }
+        if max < 100 {
+            return Err(format!("Max bitrate {} kbps is too low", max));
+        }
+    }
+
+    if let Some(gop) = settings.gop {
+        if !(1..=1000).contains(&gop) {
+            return Err(format!("GOP length {} is out of range (1-1000)", gop));
+        }
+    }
+
+    let pixel_format = settings.pixel_format.unwrap_or(PixelFormat::Yuv420p);
+    if codec == VideoCodec::H264 && pixel_format == PixelFormat::Yuv420p10le {
+        return Err("10-bit H.264 is not widely playable; use HEVC, VP9 or AV1 for 10-bit output".to_string());
+    }
+    if codec == VideoCodec::Av1 && matches!(pixel_format, PixelFormat::Yuv422p | PixelFormat::Yuv444p) {
+        return Err("The AV1 encoder only supports 4:2:0 output".to_string());
+    }
+
+    if let Some(fps) = settings.frame_rate {
+        if !(1.0..=120.0).contains(&fps) {
+            return Err(format!("Frame rate {} is out of range (1-120)", fps));
+        }
+    }
+
+    Ok(VideoEncodeSettings {
+        codec,
+        rate_control,
+        max_bitrate_kbps: settings.max_bitrate_kbps,
+        gop: settings.gop,
+        pixel_format,
+        frame_rate: settings.frame_rate,
+        preset: settings.preset.unwrap_or(EncoderPreset::Medium),
+    })
+}
+
+fn resolve_audio(settings: &ExportSettings, container: Container, quality: Quality) -> Result<AudioEncodeSettings, String> {
+    let codec = settings.audio_codec.unwrap_or_else(|| container.default_audio_codec());
+    if !container.supports_audio(codec) {
+        return Err(format!(
+            "{:?} audio can't be stored in a {} file",
+            codec,
+            container.extension().to_uppercase()
+        ));
+    }
+
+    let bitrate_kbps = settings.audio_bitrate_kbps.unwrap_or_else(|| quality.audio_bitrate_kbps());
+    if codec != AudioCodec::Pcm && !(32..=512).contains(&bitrate_kbps) {
+        return Err(format!("Audio bitrate {} kbps is out of range (32-512)", bitrate_kbps));
+    }
+    if codec == AudioCodec::Mp3 && bitrate_kbps > 320 {
+        return Err("MP3 bitrate can't exceed 320 kbps".to_string());
+    }
+
+    let sample_rate = settings.audio_sample_rate.unwrap_or(48000);
+    if !(8000..=192000).contains(&sample_rate) {
+        return Err(format!("Unsupported sample rate: {} Hz", sample_rate));
+    }
+    if codec == AudioCodec::Opus && sample_rate != 48000 {
+        return Err("Opus audio must be encoded at 48000 Hz".to_string());
+    }
+
+    let channels = settings.audio_channels.unwrap_or(2);
+    if !(1..=2).contains(&channels) {
+        return Err("Only mono or stereo audio is supported".to_string());
+    }
+
+    Ok(AudioEncodeSettings {
+        codec,
+        bitrate_kbps,
+        sample_rate,
+        channels,
+    })
+}
+
+impl VideoEncodeSettings {
+    pub fn ffmpeg_args(&self, container: Container) -> Vec<String> {
+        let mut args = vec!["-c:v".to_string(), self.codec.encoder().to_string()];
+
+        // Each encoder has its own speed scale; EncoderPreset indexes ultrafast..veryslow
+        match self.codec {
+            VideoCodec::H264 | VideoCodec::Hevc => {
+                args.extend(["-preset".to_string(), self.preset.as_str().to_string()]);
+            }
+            VideoCodec::Vp9 => {
+                let cpu_used = 8 - self.preset.index();
+                args.extend([
+                    "-deadline".to_string(),
+                    "good".to_string(),
+                    "-cpu-used".to_string(),
+                    cpu_used.to_string(),
+                    "-row-mt".to_string(),
+                    "1".to_string(),
+                ]);
+            }
+            VideoCodec::Av1 => {
+                let svt_preset = [12, 11, 10, 9, 8, 7, 5, 4, 2][self.preset.index() as usize];
+                args.extend(["-preset".to_string(), svt_preset.to_string()]);
+            }
+        }
+
+        match self.rate_control {
+            RateControl::Crf { crf } => {
+                args.extend(["-crf".to_string(), crf.to_string()]);
+                // libvpx only runs in constant-quality mode with a zero target bitrate
+                if self.codec == VideoCodec::Vp9 && self.max_bitrate_kbps.is_none() {
+                    args.extend(["-b:v".to_string(), "0".to_string()]);
+                }
+            }
+            RateControl::Bitrate { kbps } => {
+                args.extend(["-b:v".to_string(), format!("{}k", kbps)]);
+            }
+        }
+
+        if let Some(max) = self.max_bitrate_kbps {
+            // libvpx caps constant quality through -b:v; a bitrate target already set it
+            if self.codec == VideoCodec::Vp9 && matches!(self.rate_control, RateControl::Crf { .. }) {
+                args.extend(["-b:v".to_string(), format!("{}k", max)]);
+            } else {
+                args.extend([
+                    "-maxrate".to_string(),
+                    format!("{}k", max),
+                    "-bufsize".to_string(),
+                    format!("{}k", max * 2),
+                ]);
+            }
+        }
+
+        if let Some(gop) = self.gop {
+            args.extend(["-g".to_string(), gop.to_string()]);
+        }
+        if let Some(fps) = self.frame_rate {
+            args.extend(["-r".to_string(), fps.to_string()]);
+        }
+        args.extend(["-pix_fmt".to_string(), self.pixel_format.as_str().to_string()]);
+
+        // QuickTime and Safari only recognize HEVC tagged as hvc1
+        if self.codec == VideoCodec::Hevc && matches!(container, Container::Mp4 | Container::Mov) {
+            args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+        }
+
+        args
+    }
+}
+
+impl AudioEncodeSettings {
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        let mut args = vec!["-c:a".to_string(), self.codec.encoder().to_string()];
+        if self.codec != AudioCodec::Pcm {
+            args.extend(["-b:a".to_string(), format!("{}k", self.bitrate_kbps)]);
+        }
+        args.extend([
+            "-ar".to_string(),
+            self.sample_rate.to_string(),
+            "-ac".to_string(),
+            self.channels.to_string(),
+        ]);
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_json(json: serde_json::Value) -> ExportSettings {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn export_dialog_defaults_stay_native() {
+        // What ExportDialog.vue sends before anything is changed
+        let settings = from_json(serde_json::json!({
+            "resolution": "1080p",
+            "quality": "high",
+            "format": "mp4",
+            "preset": "medium",
+        }));
+        assert!(settings.resolve().unwrap().native_compatible);
+    }
+
+    #[test]
+    fn native_allows_the_audio_bitrate_it_would_use_anyway() {
+        let youtube = ExportSettings { export_preset: Some("youtube".to_string()), ..Default::default() };
+        assert!(youtube.resolve().unwrap().native_compatible);
+
+        let high_quality = ExportSettings { export_preset: Some("high-quality".to_string()), ..Default::default() };
+        assert!(!high_quality.resolve().unwrap().native_compatible);
+    }
+
+    #[test]
+    fn rejects_codecs_the_container_cant_hold() {
+        let cases = [
+            serde_json::json!({ "container": "webm", "video_codec": "h264" }),
+            serde_json::json!({ "container": "mov", "video_codec": "vp9" }),
+            serde_json::json!({ "container": "mp4", "audio_codec": "pcm" }),
+            serde_json::json!({ "container": "webm", "audio_codec": "aac" }),
+            serde_json::json!({ "container": "m4a", "audio_codec": "mp3" }),
+            serde_json::json!({ "container": "gif", "video_codec": "h264" }),
+            serde_json::json!({ "container": "gif", "audio_codec": "aac" }),
+        ];
+        for json in cases {
+            assert!(from_json(json.clone()).resolve().is_err(), "{} should be rejected", json);
+        }
+    }
+
+    #[test]
+    fn rejects_unplayable_encoder_settings() {
+        let cases = [
+            serde_json::json!({ "video_codec": "h264", "pixel_format": "yuv420p10le" }),
+            serde_json::json!({ "container": "webm", "video_codec": "av1", "pixel_format": "yuv444p" }),
+            serde_json::json!({ "container": "webm", "audio_sample_rate": 44100 }),
+            serde_json::json!({ "container": "mp3", "audio_bitrate_kbps": 384 }),
+            serde_json::json!({ "rate_control": { "mode": "bitrate", "kbps": 4000 }, "max_bitrate_kbps": 2000 }),
+        ];
+        for json in cases {
+            assert!(from_json(json.clone()).resolve().is_err(), "{} should be rejected", json);
+        }
+    }
+
+    #[test]
+    fn accepts_matching_codecs() {
+        let webm = from_json(serde_json::json!({ "container": "webm", "video_codec": "av1" })).resolve().unwrap();
+        assert_eq!(webm.video.unwrap().codec, VideoCodec::Av1);
+        assert_eq!(webm.audio.unwrap().codec, AudioCodec::Opus);
+
+        let gif = from_json(serde_json::json!({ "container": "gif" })).resolve().unwrap();
+        assert!(gif.video.is_none() && gif.audio.is_none());
+    }
+
+    #[test]
+    fn only_plain_h264_is_native() {
+        let cases = [
+            serde_json::json!({ "container": "webm" }),
+            serde_json::json!({ "video_codec": "hevc" }),
+            serde_json::json!({ "resolution": "1440p" }),
+            serde_json::json!({ "aspect_ratio": "9:16" }),
+            serde_json::json!({ "frame_rate": 30 }),
+            serde_json::json!({ "rate_control": { "mode": "crf", "crf": 18 } }),
+            serde_json::json!({ "quality": "medium", "audio_bitrate_kbps": 256 }),
+        ];
+        for json in cases {
+            assert!(!from_json(json.clone()).resolve().unwrap().native_compatible, "{} isn't native", json);
+        }
+        assert!(from_json(serde_json::json!({ "container": "mov", "resolution": "720p" })).resolve().unwrap().native_compatible);
+    }
+}
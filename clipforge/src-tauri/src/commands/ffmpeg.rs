@@ -0,0 +1,168 @@
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+lazy_static::lazy_static! {
+    static ref VIDEO_SIZE_RE: regex::Regex =
+        regex::Regex::new(r"Stream #.*Video:.*?, (\d{2,5})x(\d{2,5})").unwrap();
+    static ref VIDEO_FPS_RE: regex::Regex =
+        regex::Regex::new(r"Stream #.*Video:.*?, (\d+(?:\.\d+)?) fps").unwrap();
+    static ref DURATION_RE: regex::Regex =
+        regex::Regex::new(r"Duration: (\d+):(\d{2}):(\d{2}(?:\.\d+)?)").unwrap();
+}
+
+// Helpers for spawning the ffmpeg CLI. The binary is looked up on PATH unless
+// VIDVEIL_FFMPEG points at a bundled copy.
+pub fn ffmpeg_binary() -> String {
+    std::env::var("VIDVEIL_FFMPEG").unwrap_or_else(|_| "ffmpeg".to_string())
+}
+
+// Run ffmpeg to completion and return its output, turning a non-zero exit into an error
+pub fn run_ffmpeg(args: &[String]) -> Result<Output, String> {
+    let output = Command::new(ffmpeg_binary())
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("ffmpeg failed: {}", stderr_tail(&output.stderr)));
+    }
+
+    Ok(output)
+}
+
+// Lets a caller follow a long ffmpeg run and stop it early
+pub struct RunControl<'a> {
+    pub cancelled: &'a AtomicBool,
+    pub on_progress: &'a dyn Fn(f64),
+}
+
+// Run ffmpeg, reporting progress as a 0-1 fraction of `duration` seconds of output.
+// Without a control this is the same as run_ffmpeg.
+pub fn run_ffmpeg_tracked(args: &[String], duration: f64, control: Option<&RunControl>) -> Result<(), String> {
+    let Some(control) = control else {
+        return run_ffmpeg(args).map(|_| ());
+    };
+
+    let mut child = Command::new(ffmpeg_binary())
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-nostats")
+        .arg("-progress")
+        .arg("pipe:1")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    // Drain stderr on its own thread so a chatty ffmpeg can't block on a full pipe
+    let mut stderr = child.stderr.take().ok_or("Failed to capture ffmpeg stderr")?;
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = stderr.read_to_end(&mut buffer);
+        buffer
+    });
+
+    let stdout = child.stdout.take().ok_or("Failed to capture ffmpeg stdout")?;
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if control.cancelled.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("Export cancelled".to_string());
+        }
+        // -progress reports out_time_us (out_time_ms is also microseconds, despite the name)
+        if let Some(value) = line.strip_prefix("out_time_us=").or_else(|| line.strip_prefix("out_time_ms=")) {
+            if let (Ok(micros), true) = (value.trim().parse::<f64>(), duration > 0.0) {
+                (control.on_progress)((micros / 1_000_000.0 / duration).clamp(0.0, 1.0));
+            }
+        }
+    }
+
+    let status = child.wait().map_err(|e| format!("Failed to wait for ffmpeg: {}", e))?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    if control.cancelled.load(Ordering::SeqCst) {
+        return Err("Export cancelled".to_string());
+    }
+    if !status.success() {
+        return Err(format!("ffmpeg failed: {}", stderr_tail(&stderr)));
+    }
+
+    (control.on_progress)(1.0);
+    Ok(())
+}
+
+// Run "ffmpeg -i <file>" with no output and return the stream summary it prints.
+// ffmpeg exits non-zero here by design, so only a failure to spawn is an error.
+pub fn probe(file_path: &str) -> Result<String, String> {
+    let output = Command::new(ffmpeg_binary())
+        .arg("-hide_banner")
+        .arg("-nostdin")
+        .arg("-i")
+        .arg(file_path)
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    Ok(String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+pub fn has_audio_stream(file_path: &str) -> Result<bool, String> {
+    Ok(probe(file_path)?.lines().any(|l| l.contains("Stream #") && l.contains("Audio:")))
+}
+
+// Length of the file in seconds, when ffmpeg can tell
+pub fn media_duration(file_path: &str) -> Option<f64> {
+    parse_duration(&probe(file_path).ok()?)
+}
+
+// Width and height of the first video stream
+pub fn video_dimensions(file_path: &str) -> Result<(u32, u32), String> {
+    let summary = probe(file_path)?;
+    let caps = VIDEO_SIZE_RE
+        .captures(&summary)
+        .ok_or_else(|| format!("No video stream found in {}", file_path))?;
+    Ok((caps[1].parse().unwrap_or(0), caps[2].parse().unwrap_or(0)))
+}
+
+// Frame rate of the first video stream
+pub fn video_frame_rate(file_path: &str) -> Result<f64, String> {
+    let summary = probe(file_path)?;
+    VIDEO_FPS_RE
+        .captures(&summary)
+        .and_then(|caps| caps[1].parse().ok())
+        .ok_or_else(|| format!("No frame rate found for {}", file_path))
+}
+
+// Last few lines of ffmpeg's stderr, which is where the actual error ends up
+pub fn stderr_tail(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let lines: Vec<&str> = stderr.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(3)..].join("\n")
+}
+
+// Parse the "Duration: HH:MM:SS.ss" line ffmpeg prints for its first input
+pub fn parse_duration(stderr: &str) -> Option<f64> {
+    let caps = DURATION_RE.captures(stderr)?;
+    let hours: f64 = caps[1].parse().ok()?;
+    let minutes: f64 = caps[2].parse().ok()?;
+    let seconds: f64 = caps[3].parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+// Escape a value for use inside a quoted ffmpeg filter argument
+pub fn escape_filter_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'").replace(':', "\\:")
+}
+
+// Unquoted text (drawtext's text=) escaped once as an option value, then again for the filtergraph
+pub fn escape_graph_text(text: &str) -> String {
+    escape_filter_value(text).chars().fold(String::new(), |mut out, c| {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            out.push('\\');
+        }
+        out.push(c);
+        out
+    })
+}
@@ -0,0 +1,266 @@
+use crate::commands::atomic_write;
+use crate::commands::audio_devices::{self, AudioSource};
+use crate::commands::ffmpeg::{self, RunControl};
+use crate::commands::video_devices::{self, WebcamSelection};
+use std::process::Command;
+
+// Screen recording through ffmpeg's platform grabbers, for headless use where the
+// native capture pipeline and its permission prompts aren't available
+
+#[derive(Debug, Clone)]
+pub struct CaptureOptions {
+    pub duration: f64,
+    pub fps: u32,
+    pub display: Option<String>, // X11 display, AVFoundation screen name or gdigrab target
+    pub audio: Vec<AudioSource>, // mixed into the recording, each at its own gain
+    pub show_cursor: bool,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        CaptureOptions {
+            duration: 10.0,
+            fps: 30,
+            display: None,
+            audio: Vec::new(),
+            show_cursor: true,
+        }
+    }
+}
+
+/// ffmpeg input arguments that grab the screen on this platform
+pub fn screen_input_args(options: &CaptureOptions) -> Result<Vec<String>, String> {
+    let framerate = options.fps.to_string();
+    let cursor = if options.show_cursor { "1" } else { "0" }.to_string();
+
+    if cfg!(target_os = "linux") {
+        let display = options
+            .display
+            .clone()
+            .or_else(|| std::env::var("DISPLAY").ok())
+            .ok_or("No X11 display found. Set DISPLAY or pass a display; Wayland sessions aren't supported")?;
+        Ok(vec![
+            "-f".to_string(), "x11grab".to_string(),
+            "-framerate".to_string(), framerate,
+            "-draw_mouse".to_string(), cursor,
+            "-i".to_string(), display,
+        ])
+    } else if cfg!(target_os = "macos") {
+        let screen = options.display.clone().unwrap_or_else(|| "Capture screen 0".to_string());
+        Ok(vec![
+            "-f".to_string(), "avfoundation".to_string(),
+            "-framerate".to_string(), framerate,
+            "-capture_cursor".to_string(), cursor,
+            "-i".to_string(), format!("{}:none", screen),
+        ])
+    } else if cfg!(target_os = "windows") {
+        Ok(vec![
+            "-f".to_string(), "gdigrab".to_string(),
+            "-framerate".to_string(), framerate,
+            "-draw_mouse".to_string(), cursor,
+            "-i".to_string(), options.display.clone().unwrap_or_else(|| "desktop".to_string()),
+        ])
+    } else {
+        Err("Screen capture through ffmpeg isn't supported on this platform".to_string())
+    }
+}
+
+/// ffmpeg input arguments for a camera: a V4L2 device (or a fake one backed by a
+/// video file), an AVFoundation device index/name or a DirectShow device name.
+/// `default_fps` applies when the selection doesn't pick a rate.
+pub fn webcam_input_args(selection: &WebcamSelection, default_fps: u32) -> Result<Vec<String>, String> {
+    let device = selection.device_id.as_str();
+    if video_devices::is_fake_device(device) {
+        // Loop the file in real time so it behaves like a live camera
+        return Ok(vec![
+            "-re".to_string(),
+            "-stream_loop".to_string(), "-1".to_string(),
+            "-i".to_string(), device.to_string(),
+        ]);
+    }
+
+    let mut args = Vec::new();
+    let format = if cfg!(target_os = "linux") {
+        "v4l2"
+    } else if cfg!(target_os = "macos") {
+        "avfoundation"
+    } else if cfg!(target_os = "windows") {
+        "dshow"
+    } else {
+        return Err("Camera capture through ffmpeg isn't supported on this platform".to_string());
+    };
+    args.extend(["-f".to_string(), format.to_string()]);
+
+    if let Some(pixel_format) = &selection.pixel_format {
+        // v4l2 takes compressed formats through -input_format as well
+        let flag = if cfg!(target_os = "linux") { "-input_format" } else { "-pixel_format" };
+        args.extend([flag.to_string(), pixel_format.clone()]);
+    }
+    if let (Some(width), Some(height)) = (selection.width, selection.height) {
+        args.extend(["-video_size".to_string(), format!("{}x{}", width, height)]);
+    }
+    let fps = selection.fps.unwrap_or(default_fps as f64);
+    args.extend(["-framerate".to_string(), format!("{}", fps)]);
+
+    let input = if cfg!(target_os = "macos") {
+        format!("{}:none", device)
+    } else if cfg!(target_os = "windows") {
+        format!("video={}", device)
+    } else {
+        device.to_string()
+    };
+    args.extend(["-i".to_string(), input]);
+    Ok(args)
+}
+
+/// ffmpeg input arguments for an audio source: a PulseAudio source (including
+/// monitors for system audio), an AVFoundation audio device or a DirectShow device
+pub fn audio_input_args(device: Option<&str>) -> Result<Vec<String>, String> {
+    if cfg!(target_os = "linux") {
+        Ok(vec![
+            "-f".to_string(), "pulse".to_string(),
+            "-i".to_string(), device.unwrap_or("default").to_string(),
+        ])
+    } else if cfg!(target_os = "macos") {
+        Ok(vec![
+            "-f".to_string(), "avfoundation".to_string(),
+            "-i".to_string(), format!("none:{}", device.unwrap_or("0")),
+        ])
+    } else if cfg!(target_os = "windows") {
+        let device = device.ok_or("Pass the DirectShow name of the audio device")?;
+        Ok(vec![
+            "-f".to_string(), "dshow".to_string(),
+            "-i".to_string(), format!("audio={}", device),
+        ])
+    } else {
+        Err("Audio capture through ffmpeg isn't supported on this platform".to_string())
+    }
+}
+
+/// Record the screen for `options.duration` seconds into an H.264 file
+pub fn record_screen(output_path: &str, options: &CaptureOptions, control: Option<&RunControl>) -> Result<(), String> {
+    if options.duration <= 0.0 {
+        return Err("Recording duration must be positive".to_string());
+    }
+    if !(1..=120).contains(&options.fps) {
+        return Err(format!("Frame rate {} is out of range (1-120)", options.fps));
+    }
+
+    audio_devices::validate_sources(&options.audio)?;
+
+    let partial_path = atomic_write::partial_path(output_path);
+    let mut args = vec!["-y".to_string()];
+    args.extend(screen_input_args(options)?);
+    for source in &options.audio {
+        args.extend(audio_input_args(Some(&source.device_id))?);
+    }
+    args.extend(["-t".to_string(), format!("{:.3}", options.duration), "-map".to_string(), "0:v".to_string()]);
+    args.extend(live_video_encode_args());
+    if !options.audio.is_empty() {
+        args.extend([
+            "-filter_complex".to_string(),
+            audio_mix_filter(&options.audio, 1, "mixed"),
+            "-map".to_string(),
+            "[mixed]".to_string(),
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-b:a".to_string(),
+            "192k".to_string(),
+        ]);
+    }
+    args.push(partial_path.clone());
+
+    println!(
+        "🔴 Recording screen for {:.1}s at {} fps with {} audio source(s)",
+        options.duration,
+        options.fps,
+        options.audio.len()
+    );
+    let result = ffmpeg::run_ffmpeg_tracked(&args, options.duration, control)
+        .and_then(|_| atomic_write::commit(&partial_path, output_path));
+    if result.is_err() {
+        atomic_write::discard(&partial_path);
+    }
+    result
+}
+
+/// Encoder settings for live capture: fast enough to keep up, even dimensions for yuv420p
+pub fn live_video_encode_args() -> Vec<String> {
+    [
+        "-c:v", "libx264",
+        "-preset", "veryfast",
+        "-crf", "23",
+        "-pix_fmt", "yuv420p",
+        "-vf", "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+// Each source at its gain, mixed into [label]. Sources are ffmpeg inputs from `first_input` on.
+fn audio_mix_filter(sources: &[AudioSource], first_input: usize, label: &str) -> String {
+    let mut filter = String::new();
+    for (i, source) in sources.iter().enumerate() {
+        let gain = audio_devices::gain_filter(source).unwrap_or_else(|| "anull".to_string());
+        filter.push_str(&format!("[{}:a]{}[src{}];", first_input + i, gain, i));
+    }
+    for i in 0..sources.len() {
+        filter.push_str(&format!("[src{}]", i));
+    }
+    filter.push_str(&format!("amix=inputs={}:normalize=0:dropout_transition=0[{}]", sources.len(), label));
+    filter
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Video,
+    Audio,
+}
+
+/// (id, name) of the AVFoundation or DirectShow devices ffmpeg reports. It prints them
+/// to stderr and always exits with an error for these probes.
+/// AVFoundation: "[AVFoundation indev @ 0x..] AVFoundation audio devices:" then "[..] [0] MacBook Pro Microphone"
+/// DirectShow:   "[dshow @ 0x..] "Microphone (Realtek Audio)" (audio)"
+pub fn list_ffmpeg_devices(kind: DeviceKind) -> Result<Vec<(String, String)>, String> {
+    let args: &[&str] = if cfg!(target_os = "macos") {
+        &["-f", "avfoundation", "-list_devices", "true", "-i", ""]
+    } else if cfg!(target_os = "windows") {
+        &["-f", "dshow", "-list_devices", "true", "-i", "dummy"]
+    } else {
+        return Err("ffmpeg device listing is only used on macOS and Windows".to_string());
+    };
+    let output = Command::new(ffmpeg::ffmpeg_binary())
+        .arg("-hide_banner")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    let log = String::from_utf8_lossy(&output.stderr);
+
+    let (section, suffix) = match kind {
+        DeviceKind::Video => ("AVFoundation video devices", " (video)"),
+        DeviceKind::Audio => ("AVFoundation audio devices", " (audio)"),
+    };
+    let mut devices = Vec::new();
+    let mut in_section = false;
+    for line in log.lines() {
+        // Drop the "[indev @ 0x..] " prefix
+        let text = match line.find("] ") {
+            Some(i) if line.starts_with('[') => &line[i + 2..],
+            _ => line,
+        };
+
+        if text.contains("AVFoundation") && text.contains("devices:") {
+            in_section = text.contains(section);
+        } else if in_section {
+            // "[0] MacBook Pro Microphone"
+            if let Some((index, name)) = text.strip_prefix('[').and_then(|t| t.split_once("] ")) {
+                devices.push((index.to_string(), name.to_string()));
+            }
+        } else if let Some(name) = text.strip_suffix(suffix) {
+            let name = name.trim_matches('"').to_string();
+            devices.push((name.clone(), name));
+        }
+    }
+    Ok(devices)
+}
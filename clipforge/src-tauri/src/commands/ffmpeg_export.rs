@@ -0,0 +1,565 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::atomic_write;
+use crate::commands::export_settings::{AudioEncodeSettings, Container, EncodeSettings, ExportSettings};
+use crate::commands::auto_zoom;
+use crate::commands::ffmpeg::{self, RunControl};
+use crate::commands::input_events;
+use crate::commands::layers;
+use crate::commands::output_paths;
+use crate::commands::redaction;
+use crate::commands::reframe::OutputFrame;
+use crate::commands::video_processing::VideoClip;
+
+// Animated formats are meant for short snippets; longer exports balloon in size
+const MAX_ANIMATED_DURATION: f64 = 60.0;
+const MAX_ANIMATED_FPS: u32 = 50;
+// Slack before a clip counts as trimmed; container and stream lengths differ by a few frames
+const TRIM_TOLERANCE_SECS: f64 = 0.25;
+
+const DITHER_MODES: [&str; 6] = ["bayer", "heckbert", "floyd_steinberg", "sierra2", "sierra2_4a", "none"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnimatedExportOptions {
+    pub fps: Option<u32>,         // default 15
+    pub max_width: Option<u32>,   // default 640, never upscales
+    pub dither: Option<String>,   // GIF only, one of DITHER_MODES
+    pub bayer_scale: Option<u32>, // GIF only, 0-5, used with "bayer"
+    pub max_colors: Option<u32>,  // GIF only, 2-256
+    pub quality: Option<u32>,     // WebP only, 0-100
+    pub loop_count: Option<u32>,  // 0 loops forever
+}
+
+/// Render the timeline (screen clips with the webcam and layers overlaid) to an animated GIF or WebP
+pub fn export_animated(
+    clips: &[VideoClip],
+    output_path: &str,
+    container: Container,
+    frame: &OutputFrame,
+    options: &AnimatedExportOptions,
+    control: Option<&RunControl>,
+) -> Result<(), String> {
+    let format = container.extension();
+    let Composition {
+        inputs,
+        mut filter,
+        screen_clips,
+        source_width,
+        source_height,
+        ..
+    } = compose_timeline(clips)?;
+    let duration: f64 = screen_clips.iter().map(|c| c.duration).sum();
+    if duration > MAX_ANIMATED_DURATION {
+        return Err(format!(
+            "Animated {} export is limited to {}s of footage, timeline is {:.1}s",
+            format.to_uppercase(),
+            MAX_ANIMATED_DURATION,
+            duration
+        ));
+    }
+
+    let fps = options.fps.unwrap_or(15);
+    if fps == 0 || fps > MAX_ANIMATED_FPS {
+        return Err(format!("Animated export fps must be between 1 and {}", MAX_ANIMATED_FPS));
+    }
+    let max_width = options.max_width.unwrap_or(640);
+    if max_width < 16 {
+        return Err("Animated export width must be at least 16 pixels".to_string());
+    }
+
+    let mut args = vec!["-y".to_string()];
+    args.extend(input_args(&inputs));
+
+    // Same picture as a video export, reframed if asked, then drop frames and downscale
+    let mut head = "composed";
+    if !frame.is_plain_scale() {
+        filter.push(';');
+        filter.push_str(&frame.filter("composed", "framed", source_width, source_height));
+        head = "framed";
+    }
+    filter.push_str(&format!(
+        ";[{}]fps={},scale=w=min(iw\\,{}):h=-1:flags=lanczos",
+        head, fps, max_width
+    ));
+
+    match format {
+        "gif" => {
+            let dither = options.dither.as_deref().unwrap_or("sierra2_4a");
+            if !DITHER_MODES.contains(&dither) {
+                return Err(format!("Unknown dither mode '{}', expected one of {:?}", dither, DITHER_MODES));
+            }
+            let max_colors = options.max_colors.unwrap_or(256);
+            if !(2..=256).contains(&max_colors) {
+                return Err("GIF palette size must be between 2 and 256 colors".to_string());
+            }
+
+            let mut paletteuse = format!("paletteuse=dither={}", dither);
+            if dither == "bayer" {
+                paletteuse.push_str(&format!(":bayer_scale={}", options.bayer_scale.unwrap_or(2).min(5)));
+            }
+
+            // Two-pass palette: generate an optimal palette from the frames, then map onto it
+            filter.push_str(&format!(
+                ",split[a][b];[a]palettegen=max_colors={}:stats_mode=diff[palette];[b][palette]{}[out]",
+                max_colors, paletteuse
+            ));
+            args.extend(["-filter_complex".to_string(), filter, "-map".to_string(), "[out]".to_string()]);
+        }
+        "webp" => {
+            let quality = options.quality.unwrap_or(75).min(100);
+            filter.push_str("[out]");
+            args.extend([
+                "-filter_complex".to_string(),
+                filter,
+                "-map".to_string(),
+                "[out]".to_string(),
+                "-c:v".to_string(),
+                "libwebp_anim".to_string(),
+                "-lossless".to_string(),
+                "0".to_string(),
+                "-q:v".to_string(),
+                quality.to_string(),
+            ]);
+        }
+        _ => return Err(format!("Unsupported animated format: {}", format)),
+    }
+
+    args.extend([
+        "-loop".to_string(),
+        options.loop_count.unwrap_or(0).to_string(),
+        output_path.to_string(),
+    ]);
+
+    println!("🎞️ Exporting {:.1}s to {} at {} fps, max width {}", duration, format.to_uppercase(), fps, max_width);
+    ffmpeg::run_ffmpeg_tracked(&args, duration, control)?;
+    println!("✅ Animated export complete: {}", output_path);
+    Ok(())
+}
+
+/// Mix every clip's audio at its timeline position into a single audio file
+pub fn export_audio(
+    clips: &[VideoClip],
+    output_path: &str,
+    container: Container,
+    audio: &AudioEncodeSettings,
+    control: Option<&RunControl>,
+) -> Result<(), String> {
+    let mut sorted: Vec<&VideoClip> = clips.iter().filter(|c| !layers::is_layer(c)).collect();
+    sorted.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    for clip in &sorted {
+        if !std::path::Path::new(&clip.path).exists() {
+            return Err(format!("Clip file not found: {}", clip.path));
+        }
+    }
+
+    // Screen and webcam recordings may or may not carry audio; only mix the ones that do
+    let mut audio_clips = Vec::new();
+    for clip in sorted {
+        if ffmpeg::has_audio_stream(&clip.path)? {
+            audio_clips.push(clip);
+        }
+    }
+    if audio_clips.is_empty() {
+        return Err("None of the timeline clips contain audio".to_string());
+    }
+
+    // The output starts where the first clip starts
+    let timeline_start = audio_clips[0].start_time;
+    let mix_inputs: Vec<(usize, f64)> = audio_clips
+        .iter()
+        .enumerate()
+        .map(|(i, clip)| (i, clip.start_time - timeline_start))
+        .collect();
+
+    let mut args = vec!["-y".to_string()];
+    args.extend(input_args(&audio_clips));
+    args.extend([
+        "-filter_complex".to_string(),
+        audio_mix_filter(&mix_inputs, "mixed"),
+        "-map".to_string(),
+        "[mixed]".to_string(),
+    ]);
+    args.extend(audio.ffmpeg_args());
+    if container == Container::M4a {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args.push(output_path.to_string());
+
+    println!("🎧 Exporting audio from {} clip(s) to {}", audio_clips.len(), container.extension().to_uppercase());
+    let duration = audio_clips
+        .iter()
+        .map(|c| c.start_time - timeline_start + c.duration)
+        .fold(0.0, f64::max);
+    ffmpeg::run_ffmpeg_tracked(&args, duration, control)?;
+    println!("✅ Audio export complete: {}", output_path);
+    Ok(())
+}
+
+/// Copy a file with its audio normalized to `target_lufs` integrated loudness (EBU R128).
+/// Video is stream-copied; the output should be a container that takes any codec, such as mkv.
+pub fn normalize_loudness(input: &str, output: &str, target_lufs: f64) -> Result<(), String> {
+    if !(-70.0..=-5.0).contains(&target_lufs) {
+        return Err(format!("Loudness target {} LUFS is out of range (-70 to -5)", target_lufs));
+    }
+    if !ffmpeg::has_audio_stream(input)? {
+        return Err(format!("No audio stream found in {}", input));
+    }
+
+    let args = vec![
+        "-y".to_string(),
+        "-i".to_string(), input.to_string(),
+        "-map".to_string(), "0:v?".to_string(),
+        "-map".to_string(), "0:a:0".to_string(),
+        "-c:v".to_string(), "copy".to_string(),
+        "-af".to_string(), format!("loudnorm=I={}:TP=-1.5:LRA=11", target_lufs),
+        "-c:a".to_string(), "aac".to_string(),
+        "-b:a".to_string(), "192k".to_string(),
+        "-ar".to_string(), "48000".to_string(),
+        output.to_string(),
+    ];
+    ffmpeg::run_ffmpeg(&args)?;
+    println!("🔊 Normalized audio to {} LUFS: {}", target_lufs, output);
+    Ok(())
+}
+
+/// Render an export through ffmpeg into a partial file, add captions and move it into
+/// place, returning where it ended up (see output_paths::commit_export). Whatever was
+/// written is removed again if any step fails.
+pub fn render_to_file(
+    clips: &[VideoClip],
+    settings: &ExportSettings,
+    encode: &EncodeSettings,
+    output_path: &str,
+    control: Option<&RunControl>,
+) -> Result<String, String> {
+    let partial_path = atomic_write::partial_path(output_path);
+    let rendered = if encode.container.is_animated() {
+        let options = settings.animation.clone().unwrap_or_default();
+        export_animated(clips, &partial_path, encode.container, &encode.frame, &options, control)
+    } else if encode.container.is_audio_only() {
+        let audio = encode.audio.as_ref().ok_or("Audio export settings are missing")?;
+        export_audio(clips, &partial_path, encode.container, audio, control)
+    } else {
+        export_video(clips, &partial_path, encode, control)
+    };
+
+    let finished = rendered
+        .and_then(|_| match &settings.captions {
+            Some(caption_options) => crate::commands::captions::apply_captions_in_place(&partial_path, caption_options, encode),
+            None => Ok(()),
+        })
+        .and_then(|_| match settings.chapters {
+            Some(true) => crate::commands::markers::apply_chapters(clips, &partial_path, output_path, encode.container),
+            _ => Ok(()),
+        })
+        .and_then(|_| output_paths::commit_export(&partial_path, output_path));
+    if finished.is_err() {
+        atomic_write::discard(&partial_path);
+    }
+    finished
+}
+
+/// Render the timeline to a video file: screen clips back to back, the webcam
+/// overlaid at its PiP position, and every clip's audio mixed underneath
+pub fn export_video(
+    clips: &[VideoClip],
+    output_path: &str,
+    encode: &EncodeSettings,
+    control: Option<&RunControl>,
+) -> Result<(), String> {
+    export_video_targets(clips, &[(output_path.to_string(), encode)], control)
+}
+
+/// Render one timeline to several video outputs in a single ffmpeg run. The clips
+/// are decoded and composited once, then split into each target's frame and encoder.
+pub fn export_video_targets(
+    clips: &[VideoClip],
+    targets: &[(String, &EncodeSettings)],
+    control: Option<&RunControl>,
+) -> Result<(), String> {
+    if targets.is_empty() {
+        return Ok(());
+    }
+    for (_, encode) in targets {
+        if encode.video.is_none() {
+            return Err(format!("{} is not a video container", encode.container.extension()));
+        }
+    }
+
+    let Composition {
+        mut inputs,
+        mut filter,
+        screen_clips,
+        timeline_start,
+        source_width,
+        source_height,
+        has_webcam,
+    } = compose_timeline(clips)?;
+
+    // Separately recorded audio (ISO bundles) only joins the mix
+    inputs.extend(clips.iter().filter(|c| c.clip_type == "audio" && std::path::Path::new(&c.path).exists()));
+
+    filter.push_str(&format!(";[composed]split={}", targets.len()));
+    for i in 0..targets.len() {
+        filter.push_str(&format!("[split{}]", i));
+    }
+    for (i, (_, encode)) in targets.iter().enumerate() {
+        filter.push(';');
+        filter.push_str(&encode.frame.filter(&format!("split{}", i), &format!("vout{}", i), source_width, source_height));
+    }
+
+    let mut mix_inputs = Vec::new();
+    if targets.iter().any(|(_, encode)| encode.audio.is_some()) {
+        for (i, clip) in inputs.iter().enumerate() {
+            if ffmpeg::has_audio_stream(&clip.path)? {
+                mix_inputs.push((i, clip.start_time - timeline_start));
+            }
+        }
+    }
+    if !mix_inputs.is_empty() {
+        filter.push(';');
+        filter.push_str(&audio_mix_filter(&mix_inputs, "mixed"));
+        filter.push_str(&format!(";[mixed]asplit={}", targets.len()));
+        for i in 0..targets.len() {
+            filter.push_str(&format!("[aout{}]", i));
+        }
+    }
+
+    let mut args = vec!["-y".to_string()];
+    args.extend(input_args(&inputs));
+    args.extend(["-filter_complex".to_string(), filter]);
+
+    for (i, (output_path, encode)) in targets.iter().enumerate() {
+        args.extend(["-map".to_string(), format!("[vout{}]", i)]);
+        if let Some(video) = &encode.video {
+            args.extend(video.ffmpeg_args(encode.container));
+        }
+        if let (Some(audio), false) = (&encode.audio, mix_inputs.is_empty()) {
+            args.extend(["-map".to_string(), format!("[aout{}]", i)]);
+            args.extend(audio.ffmpeg_args());
+        }
+        if matches!(encode.container, Container::Mp4 | Container::Mov) {
+            args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+        }
+        args.push(output_path.clone());
+    }
+
+    println!(
+        "🎬 Exporting {} screen clip(s){} to {} target(s)",
+        screen_clips.len(),
+        if has_webcam { " + webcam" } else { "" },
+        targets.len()
+    );
+    let duration: f64 = screen_clips.iter().map(|c| c.duration).sum();
+    ffmpeg::run_ffmpeg_tracked(&args, duration, control)?;
+    for (output_path, _) in targets {
+        println!("✅ Video export complete: {}", output_path);
+    }
+    Ok(())
+}
+
+// The timeline composited at source size, ending in [composed]: screen clips back to
+// back with their effects, the webcam overlaid at its PiP position and layers on top.
+// Each export reframes and encodes the shared picture afterwards.
+struct Composition<'a> {
+    inputs: Vec<&'a VideoClip>,
+    filter: String,
+    screen_clips: Vec<&'a VideoClip>,
+    timeline_start: f64,
+    source_width: u32,
+    source_height: u32,
+    has_webcam: bool,
+}
+
+fn compose_timeline(clips: &[VideoClip]) -> Result<Composition<'_>, String> {
+    let screen_clips = timeline_clips(clips, "screen")?;
+    let timeline_start = screen_clips[0].start_time;
+    let webcam_clip = clips
+        .iter()
+        .filter(|c| c.clip_type == "webcam" && std::path::Path::new(&c.path).exists())
+        .min_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    let mut inputs = screen_clips.clone();
+    if let Some(webcam) = webcam_clip {
+        inputs.push(webcam);
+    }
+
+    let (source_width, source_height) = ffmpeg::video_dimensions(&screen_clips[0].path)?;
+    let mut filter = concat_video_filter(&screen_clips, "joined")?;
+    let mut picture = "joined";
+
+    if let Some(webcam) = webcam_clip {
+        let pip = webcam.pip_config.clone().unwrap_or(crate::commands::video_processing::PipConfig {
+            x: 0.8,
+            y: 0.8,
+            width: 0.2,
+            height: 0.2,
+            shape: "rectangle".to_string(),
+        });
+        if pip.shape != "rectangle" {
+            println!("ℹ️ PiP shape '{}' is rendered as a rectangle by the ffmpeg exporter", pip.shape);
+        }
+
+        let cam_index = inputs.len() - 1;
+        let offset = (webcam.start_time - timeline_start).max(0.0);
+        filter.push_str(&format!(
+            ";[{}:v]setpts=PTS-STARTPTS+{:.3}/TB[cam];[cam][joined]scale2ref=w=main_w*{}:h=ow/dar[cam_scaled][base_ref];[base_ref][cam_scaled]overlay=x=main_w*{}:y=main_h*{}:eof_action=pass[with_cam]",
+            cam_index, offset, pip.width, pip.x, pip.y
+        ));
+        picture = "with_cam";
+    }
+
+    // Titles, text and images go over everything else
+    let layer_clips = layers::timeline_layers(clips)?;
+    if layer_clips.is_empty() {
+        filter.push_str(&format!(";[{}]null[composed]", picture));
+    } else {
+        let first_image_input = inputs.len();
+        inputs.extend(layer_clips.iter().filter(|c| c.clip_type == "image"));
+        filter.push(';');
+        filter.push_str(&layers::layer_filter(
+            &layer_clips,
+            first_image_input,
+            timeline_start,
+            picture,
+            "composed",
+            source_width,
+            source_height,
+        ));
+    }
+
+    Ok(Composition {
+        inputs,
+        filter,
+        screen_clips,
+        timeline_start,
+        source_width,
+        source_height,
+        has_webcam: webcam_clip.is_some(),
+    })
+}
+
+// Clips of one type in timeline order, with their source files checked up front
+pub fn timeline_clips<'a>(clips: &'a [VideoClip], clip_type: &str) -> Result<Vec<&'a VideoClip>, String> {
+    let mut selected: Vec<&VideoClip> = clips.iter().filter(|c| c.clip_type == clip_type).collect();
+    if selected.is_empty() {
+        return Err(format!("No {} clips found in the timeline", clip_type));
+    }
+    selected.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    for clip in &selected {
+        if !std::path::Path::new(&clip.path).exists() {
+            return Err(format!("Clip file not found: {}", clip.path));
+        }
+    }
+
+    Ok(selected)
+}
+
+// One trimmed input per clip: seek to the clip's in-point and read only its duration
+pub fn input_args(clips: &[&VideoClip]) -> Vec<String> {
+    clips
+        .iter()
+        .flat_map(|clip| {
+            if clip.clip_type == "image" {
+                return layers::image_input_args(clip);
+            }
+            vec![
+                "-ss".to_string(),
+                format!("{:.3}", clip.source_offset),
+                "-t".to_string(),
+                format!("{:.3}", clip.duration),
+                "-i".to_string(),
+                clip.path.clone(),
+            ]
+        })
+        .collect()
+}
+
+// "[0:v]...[1:v]...concat" graph joining every clip's video into [label]. Each clip
+// gets its effects (click rings, zoom, keystrokes) before the join.
+pub fn concat_video_filter(clips: &[&VideoClip], label: &str) -> Result<String, String> {
+    let mut filter = String::new();
+    for (i, clip) in clips.iter().enumerate() {
+        match clip_effects_filter(clip, &format!("pre{}", i), &format!("v{}", i))? {
+            Some(effects) => filter.push_str(&format!("[{}:v]setpts=PTS-STARTPTS[pre{}];{};", i, i, effects)),
+            None => filter.push_str(&format!("[{}:v]setpts=PTS-STARTPTS[v{}];", i, i)),
+        }
+    }
+    for i in 0..clips.len() {
+        filter.push_str(&format!("[v{}]", i));
+    }
+    filter.push_str(&format!("concat=n={}:v=1:a=0[{}]", clips.len(), label));
+    Ok(filter)
+}
+
+type ClipEffect = fn(&VideoClip, &str, &str) -> Result<Option<String>, String>;
+
+// A clip's effects chained from [input] to [output] in drawing order: redactions and
+// click rings are zoomed with the picture, keystroke captions stay put on top
+fn clip_effects_filter(clip: &VideoClip, input: &str, output: &str) -> Result<Option<String>, String> {
+    let effects: [ClipEffect; 4] = [
+        redaction::redaction_filter,
+        input_events::click_ring_filter,
+        auto_zoom::zoom_filter,
+        input_events::keystroke_filter,
+    ];
+    let mut filters = Vec::new();
+    let mut current = input.to_string();
+    for (i, effect) in effects.iter().enumerate() {
+        let next = format!("{}_fx{}", output, i);
+        if let Some(filter) = effect(clip, &current, &next)? {
+            filters.push(filter);
+            current = next;
+        }
+    }
+    if filters.is_empty() {
+        return Ok(None);
+    }
+    filters.push(format!("[{}]null[{}]", current, output));
+    Ok(Some(filters.join(";")))
+}
+
+/// Whether the timeline has effects or edits only the ffmpeg compositor renders, which
+/// rules out the AVFoundation exporter. That one takes the first screen recording (and
+/// webcam) whole, so several screen pieces, trimmed clips and separate audio need ffmpeg.
+pub fn needs_compositor(clips: &[VideoClip]) -> bool {
+    let screen_clips = clips.iter().filter(|c| c.clip_type == "screen").count();
+    screen_clips > 1
+        || clips.iter().any(|c| {
+            layers::is_layer(c)
+                || input_events::has_overlay(c)
+                || redaction::has_redactions(c)
+                || c.clip_type == "audio"
+                || (c.clip_type == "screen" && c.auto_zoom.is_some())
+                || (matches!(c.clip_type.as_str(), "screen" | "webcam") && is_trimmed(c))
+        })
+}
+
+// Starts past the file's beginning or ends before its end. A file ffmpeg can't probe
+// is taken as whole.
+fn is_trimmed(clip: &VideoClip) -> bool {
+    clip.source_offset.abs() > TRIM_TOLERANCE_SECS
+        || ffmpeg::media_duration(&clip.path).is_some_and(|length| clip.duration < length - TRIM_TOLERANCE_SECS)
+}
+
+// Delay each (input index, timeline offset) audio stream into place and mix them into [label]
+pub fn audio_mix_filter(inputs: &[(usize, f64)], label: &str) -> String {
+    let mut filter = String::new();
+    for (input, offset) in inputs {
+        let delay_ms = (offset * 1000.0).round().max(0.0) as u64;
+        filter.push_str(&format!(
+            "[{}:a]asetpts=PTS-STARTPTS,adelay={}:all=1[a{}];",
+            input, delay_ms, input
+        ));
+    }
+    for (input, _) in inputs {
+        filter.push_str(&format!("[a{}]", input));
+    }
+    filter.push_str(&format!(
+        "amix=inputs={}:normalize=0:dropout_transition=0[{}]",
+        inputs.len(),
+        label
+    ));
+    filter
+}
@@ -0,0 +1,607 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::command;
+
+use crate::commands::atomic_write;
+use crate::commands::ffmpeg;
+use crate::commands::recorder::{self, RecorderState, RecordingSettings};
+use crate::commands::video_processing::VideoClip;
+
+// Input log of a recording: clicks for highlight rings, key presses for a keystroke
+// overlay and pointer positions for auto-zoom. Like markers, times are seconds of
+// recorded footage (pauses excluded) and positions are pixels of the captured display.
+// The log is saved beside the recording as "<file>.events.json" and drawn onto its
+// screen clips at export. Input is read with the X11 tools (xinput for keys and
+// buttons, xdotool for the pointer); elsewhere settings that need a log are rejected.
+
+lazy_static::lazy_static! {
+    static ref EVENT_SESSION: Arc<Mutex<Option<EventSession>>> = Arc::new(Mutex::new(None));
+}
+
+// How long a click ring stays up
+const RING_SECS: f64 = 0.5;
+// Keys closer together than this share one caption
+const KEY_GROUP_GAP: f64 = 1.0;
+// How long a keystroke caption stays up after its last key
+const KEY_HOLD_SECS: f64 = 1.5;
+const MAX_CAPTION_CHARS: usize = 32;
+// Pointer polling while tracking the cursor; smaller moves than MIN_MOVE_PX aren't logged
+const CURSOR_SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+const MIN_MOVE_PX: f64 = 2.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputEventKind {
+    Click { x: f64, y: f64, button: u8 },
+    Key { key: String }, // "a", "Enter", "Ctrl+Shift+P"
+    Move { x: f64, y: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputEvent {
+    pub time: f64, // seconds into the recording
+    #[serde(flatten)]
+    pub kind: InputEventKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputEventLog {
+    pub started_at: String,
+    pub width: Option<u32>, // size of the display the positions refer to
+    pub height: Option<u32>,
+    pub settings: RecordingSettings,
+    pub events: Vec<InputEvent>,
+}
+
+/// What to draw from a screen clip's input log at export; unset uses what was recorded
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputOverlay {
+    pub click_rings: bool,
+    pub keystrokes: bool,
+}
+
+struct EventSession {
+    output_path: String,
+    log: Arc<Mutex<InputEventLog>>,
+    collector: Child,
+    reader: JoinHandle<()>,
+    sampling: Arc<AtomicBool>,
+    sampler: Option<JoinHandle<()>>, // pointer polling, when tracking the cursor
+}
+
+// Consecutive keys shown together at the bottom of the frame
+struct KeyCaption {
+    start: f64,
+    last: f64,
+    end: f64,
+    text: String,
+    typing: bool, // the text ends with a typed character, so the next one runs on
+}
+
+/// The input log saved with `media_path`, if it has one
+#[command]
+pub fn get_input_events(media_path: String) -> Result<Option<InputEventLog>, String> {
+    load_log(&media_path)
+}
+
+/// Fail before a capture starts when its settings need an input log that can't be
+/// recorded here (see RecordingSettings::logged_settings for `native_clicks`)
+pub fn check_settings(settings: &RecordingSettings, display: Option<&str>, native_clicks: bool) -> Result<(), String> {
+    let logged = settings.logged_settings(native_clicks);
+    if logged.is_empty() {
+        return Ok(());
+    }
+    collector_available(display).map_err(|e| format!("{} needs an input log, but {}", logged.join(" and "), e))
+}
+
+/// Start logging input for the recording that just started, if its settings ask for it.
+/// check_settings has vetted them, so a collector failing now only loses the log.
+pub fn start_logging(output_path: &str, display: Option<&str>, settings: &RecordingSettings, native_clicks: bool) {
+    if settings.logged_settings(native_clicks).is_empty() {
+        return;
+    }
+    if let Err(e) = spawn_collector(output_path, display, settings) {
+        println!("⚠️ Recording without an input log: {}", e);
+    }
+}
+
+/// Stop logging and save the log beside the recording. No-op when nothing was logging.
+pub fn finish_logging() -> Result<(), String> {
+    let Some(session) = EVENT_SESSION.lock().map_err(|e| e.to_string())?.take() else {
+        return Ok(());
+    };
+    let EventSession { output_path, log, mut collector, reader, sampling, sampler } = session;
+    sampling.store(false, Ordering::SeqCst);
+    let _ = collector.kill();
+    let _ = collector.wait();
+    let _ = reader.join();
+    if let Some(sampler) = sampler {
+        let _ = sampler.join();
+    }
+
+    let log = log.lock().map_err(|e| e.to_string())?.clone();
+    let json = serde_json::to_string_pretty(&log).map_err(|e| e.to_string())?;
+    atomic_write::write_atomic(events_path(&output_path), json.as_bytes())
+        .map_err(|e| format!("Failed to save input log: {}", e))?;
+    println!("⌨️ Saved {} input event(s) for {}", log.events.len(), output_path);
+    Ok(())
+}
+
+pub fn events_path(media_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.events.json", media_path))
+}
+
+pub fn load_log(media_path: &str) -> Result<Option<InputEventLog>, String> {
+    let path = events_path(media_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read input log: {}", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Invalid input log {}: {}", path.display(), e))
+}
+
+/// Whether export has anything to draw from the clip's input log
+pub fn has_overlay(clip: &VideoClip) -> bool {
+    if clip.clip_type != "screen" {
+        return false;
+    }
+    match load_log(&clip.path) {
+        Ok(Some(log)) => {
+            let overlay = clip_overlay(clip, &log);
+            (overlay.click_rings || overlay.keystrokes) && !log.events.is_empty()
+        }
+        _ => false,
+    }
+}
+
+/// Overlay from [input] to [output] drawing a ring around every click in a screen clip,
+/// or None when its recording has no clicks to draw. The clip's input must start at its
+/// in-point (see ffmpeg_export::input_args).
+pub fn click_ring_filter(clip: &VideoClip, input: &str, output: &str) -> Result<Option<String>, String> {
+    let Some(log) = load_log(&clip.path)? else {
+        return Ok(None);
+    };
+    if !clip_overlay(clip, &log).click_rings {
+        return Ok(None);
+    }
+    let clicks: Vec<(f64, f64, f64)> = clip_events(clip, &log)
+        .filter_map(|(t, kind)| match kind {
+            InputEventKind::Click { x, y, .. } => Some((t, *x, *y)),
+            _ => None,
+        })
+        .collect();
+    if clicks.is_empty() {
+        return Ok(None);
+    }
+
+    let (width, height) = ffmpeg::video_dimensions(&clip.path)?;
+    // Positions are display pixels; HiDPI and scaled captures don't match the video
+    let scale = log.width.filter(|w| *w > 0).map(|w| width as f64 / w as f64).unwrap_or(1.0);
+    let size = ring_size(height);
+    let ring = format!("{}_ring", output);
+    Ok(Some(format!(
+        "{};[{}][{}]{}[{}]",
+        ring_source(size, clip.duration, &ring),
+        input,
+        ring,
+        ring_overlay(&clicks, scale, size),
+        output
+    )))
+}
+
+/// Captions from [input] to [output] showing the keys pressed in a screen clip, or None
+/// when its recording has no keystrokes to draw
+pub fn keystroke_filter(clip: &VideoClip, input: &str, output: &str) -> Result<Option<String>, String> {
+    let Some(log) = load_log(&clip.path)? else {
+        return Ok(None);
+    };
+    if !clip_overlay(clip, &log).keystrokes {
+        return Ok(None);
+    }
+    let keys: Vec<(f64, &str)> = clip_events(clip, &log)
+        .filter_map(|(t, kind)| match kind {
+            InputEventKind::Key { key } => Some((t, key.as_str())),
+            _ => None,
+        })
+        .collect();
+    let captions = keystroke_captions(&keys);
+    if captions.is_empty() {
+        return Ok(None);
+    }
+
+    let (_, height) = ffmpeg::video_dimensions(&clip.path)?;
+    let drawtexts: Vec<String> = captions.iter().map(|caption| keystroke_drawtext(caption, height)).collect();
+    Ok(Some(format!("[{}]{}[{}]", input, drawtexts.join(","), output)))
+}
+
+/// The log's events that fall inside the clip, with times relative to its in-point
+pub fn clip_events<'a>(clip: &'a VideoClip, log: &'a InputEventLog) -> impl Iterator<Item = (f64, &'a InputEventKind)> + 'a {
+    log.events
+        .iter()
+        .map(|e| (e.time - clip.source_offset, &e.kind))
+        .filter(|(t, _)| *t >= 0.0 && *t < clip.duration)
+}
+
+// What the clip asks for, or else what the recording was set up to show
+fn clip_overlay(clip: &VideoClip, log: &InputEventLog) -> InputOverlay {
+    clip.input_overlay.clone().unwrap_or(InputOverlay {
+        click_rings: log.settings.highlight_clicks,
+        keystrokes: log.settings.capture_keystrokes,
+    })
+}
+
+fn collector_available(display: Option<&str>) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("input logging is only available on X11".to_string());
+    }
+    x11_display(display)?;
+    Command::new("xinput")
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("failed to run xinput: {}", e))?;
+    Ok(())
+}
+
+// x11grab targets like ":0.0+100,200" carry a grab offset
+fn x11_display(display: Option<&str>) -> Result<String, String> {
+    display
+        .map(|d| d.split('+').next().unwrap_or(d).to_string())
+        .or_else(|| std::env::var("DISPLAY").ok())
+        .ok_or_else(|| "no X11 display".to_string())
+}
+
+fn spawn_collector(output_path: &str, display: Option<&str>, settings: &RecordingSettings) -> Result<(), String> {
+    let display = x11_display(display)?;
+    let started_at = match recorder::current_state()? {
+        RecorderState::Recording { started_at, .. } => started_at,
+        other => return Err(format!("recorder is {}", other.name())),
+    };
+
+    let keymap = load_keymap(&display);
+    let size = display_size(&display);
+    let mut collector = Command::new("xinput")
+        .args(["test-xi2", "--root"])
+        .env("DISPLAY", &display)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("failed to run xinput: {}", e))?;
+    let stdout = collector.stdout.take().ok_or("xinput stdout unavailable")?;
+
+    let log = Arc::new(Mutex::new(InputEventLog {
+        started_at,
+        width: size.map(|(w, _)| w),
+        height: size.map(|(_, h)| h),
+        settings: settings.clone(),
+        events: Vec::new(),
+    }));
+    let reader = std::thread::spawn({
+        let log = log.clone();
+        let settings = settings.clone();
+        let display = display.clone();
+        move || read_xinput(stdout, &display, &keymap, &settings, &log)
+    });
+    let sampling = Arc::new(AtomicBool::new(true));
+    let sampler = settings.track_cursor.then(|| {
+        let log = log.clone();
+        let sampling = sampling.clone();
+        std::thread::spawn(move || sample_pointer(&display, &sampling, &log))
+    });
+
+    let mut session = EVENT_SESSION.lock().map_err(|e| e.to_string())?;
+    if let Some(mut previous) = session.take() {
+        previous.sampling.store(false, Ordering::SeqCst);
+        let _ = previous.collector.kill();
+    }
+    *session = Some(EventSession {
+        output_path: output_path.to_string(),
+        log,
+        collector,
+        reader,
+        sampling,
+        sampler,
+    });
+    println!("⌨️ Logging input for {}", output_path);
+    Ok(())
+}
+
+// `xinput test-xi2 --root` prints a block per event; raw events reach the root window
+// whatever has focus:
+//   EVENT type 13 (RawKeyPress)
+//       device: 3 (10)
+//       detail: 38
+fn read_xinput(
+    stdout: ChildStdout,
+    display: &str,
+    keymap: &HashMap<u32, (String, String)>,
+    settings: &RecordingSettings,
+    log: &Mutex<InputEventLog>,
+) {
+    let mut event_type = String::new();
+    let mut held: Vec<&'static str> = Vec::new();
+
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("EVENT type ") {
+            event_type = rest.split('(').nth(1).unwrap_or_default().trim_end_matches(')').to_string();
+            continue;
+        }
+        let Some(detail) = line.strip_prefix("detail:").and_then(|d| d.trim().parse::<u32>().ok()) else {
+            continue;
+        };
+
+        let kind = match std::mem::take(&mut event_type).as_str() {
+            event @ ("RawKeyPress" | "RawKeyRelease") => {
+                let Some((plain, shifted)) = keymap.get(&detail) else {
+                    continue;
+                };
+                let pressed = event == "RawKeyPress";
+                if let Some(modifier) = modifier_name(plain) {
+                    held.retain(|m| *m != modifier);
+                    if pressed {
+                        held.push(modifier);
+                    }
+                    continue;
+                }
+                if !pressed || !settings.capture_keystrokes {
+                    continue;
+                }
+                InputEventKind::Key { key: key_label(plain, shifted, &held) }
+            }
+            // 1-3 are the buttons; 4-7 are scroll steps
+            "RawButtonPress" if (settings.highlight_clicks || settings.track_cursor) && (1..=3).contains(&detail) => {
+                let Some((x, y)) = pointer_position(display) else {
+                    continue;
+                };
+                InputEventKind::Click { x, y, button: detail as u8 }
+            }
+            _ => continue,
+        };
+        push_event(log, kind);
+    }
+}
+
+// Poll the pointer and log it whenever it has moved, until sampling is switched off
+fn sample_pointer(display: &str, sampling: &AtomicBool, log: &Mutex<InputEventLog>) {
+    let mut last: Option<(f64, f64)> = None;
+    while sampling.load(Ordering::SeqCst) {
+        if let Some((x, y)) = pointer_position(display) {
+            let moved = last.is_none_or(|(lx, ly)| (x - lx).abs().max((y - ly).abs()) >= MIN_MOVE_PX);
+            if moved && push_event(log, InputEventKind::Move { x, y }) {
+                last = Some((x, y));
+            }
+        }
+        std::thread::sleep(CURSOR_SAMPLE_INTERVAL);
+    }
+}
+
+// Stamp an event with the current footage time; input while paused isn't part of it
+fn push_event(log: &Mutex<InputEventLog>, kind: InputEventKind) -> bool {
+    let Ok(state @ RecorderState::Recording { .. }) = recorder::current_state() else {
+        return false;
+    };
+    let Ok(mut log) = log.lock() else {
+        return false;
+    };
+    let time = (state.elapsed_seconds() * 1000.0).round() / 1000.0;
+    log.events.push(InputEvent { time, kind });
+    true
+}
+
+// `xmodmap -pke`: "keycode  38 = a A a A" -> 38: ("a", "A"). Empty if xmodmap is missing.
+fn load_keymap(display: &str) -> HashMap<u32, (String, String)> {
+    let Ok(output) = Command::new("xmodmap").arg("-pke").env("DISPLAY", display).output() else {
+        println!("⚠️ xmodmap not found; keystrokes won't be logged");
+        return HashMap::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (code, syms) = line.strip_prefix("keycode")?.split_once('=')?;
+            let mut syms = syms.split_whitespace();
+            let plain = syms.next()?.to_string();
+            let shifted = syms.next().map(|s| s.to_string()).unwrap_or_else(|| plain.clone());
+            Some((code.trim().parse().ok()?, (plain, shifted)))
+        })
+        .collect()
+}
+
+// `xdotool getdisplaygeometry`: "1920 1080"
+fn display_size(display: &str) -> Option<(u32, u32)> {
+    let output = Command::new("xdotool").arg("getdisplaygeometry").env("DISPLAY", display).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut parts = text.split_whitespace();
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+// `xdotool getmouselocation`: "x:812 y:394 screen:0 window:62914567"
+fn pointer_position(display: &str) -> Option<(f64, f64)> {
+    let output = Command::new("xdotool").arg("getmouselocation").env("DISPLAY", display).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let field = |name: &str| text.split_whitespace().find_map(|f| f.strip_prefix(name)?.parse::<f64>().ok());
+    Some((field("x:")?, field("y:")?))
+}
+
+fn modifier_name(keysym: &str) -> Option<&'static str> {
+    match keysym {
+        "Control_L" | "Control_R" => Some("Ctrl"),
+        "Alt_L" | "Alt_R" | "Meta_L" | "Meta_R" => Some("Alt"),
+        "Super_L" | "Super_R" | "Hyper_L" | "Hyper_R" => Some("Super"),
+        "Shift_L" | "Shift_R" => Some("Shift"),
+        "ISO_Level3_Shift" | "Mode_switch" => Some("AltGr"),
+        _ => None,
+    }
+}
+
+// "Ctrl+Shift+P" for shortcuts; the character itself when typing, shifted or not
+fn key_label(plain: &str, shifted: &str, held: &[&str]) -> String {
+    let shift = held.contains(&"Shift");
+    let chord: Vec<&str> = ["Ctrl", "Alt", "Super"].into_iter().filter(|m| held.contains(m)).collect();
+    if chord.is_empty() {
+        let shifted_name = display_name(shifted);
+        return match (shift, display_name(plain)) {
+            (true, _) if shifted_name.chars().count() == 1 => shifted_name,
+            (true, name) => format!("Shift+{}", name),
+            (false, name) => name,
+        };
+    }
+
+    let mut parts: Vec<String> = chord.iter().map(|m| m.to_string()).collect();
+    if shift {
+        parts.push("Shift".to_string());
+    }
+    let name = display_name(plain);
+    parts.push(if name.chars().count() == 1 { name.to_uppercase() } else { name });
+    parts.join("+")
+}
+
+// X keysym -> what a viewer expects to read
+fn display_name(keysym: &str) -> String {
+    let name = match keysym {
+        "Return" | "KP_Enter" => "Enter",
+        "BackSpace" => "Backspace",
+        "Escape" => "Esc",
+        "space" => "Space",
+        "ISO_Left_Tab" => "Tab",
+        "Delete" => "Del",
+        "Prior" => "PgUp",
+        "Next" => "PgDn",
+        "comma" => ",",
+        "period" => ".",
+        "slash" => "/",
+        "backslash" => "\\",
+        "minus" => "-",
+        "equal" => "=",
+        "semicolon" => ";",
+        "apostrophe" => "'",
+        "grave" => "`",
+        "bracketleft" => "[",
+        "bracketright" => "]",
+        "exclam" => "!",
+        "at" => "@",
+        "numbersign" => "#",
+        "dollar" => "$",
+        "percent" => "%",
+        "asciicircum" => "^",
+        "ampersand" => "&",
+        "asterisk" => "*",
+        "parenleft" => "(",
+        "parenright" => ")",
+        "underscore" => "_",
+        "plus" => "+",
+        "braceleft" => "{",
+        "braceright" => "}",
+        "colon" => ":",
+        "quotedbl" => "\"",
+        "less" => "<",
+        "greater" => ">",
+        "question" => "?",
+        "bar" => "|",
+        "asciitilde" => "~",
+        other => other,
+    };
+    name.to_string()
+}
+
+// Keys close together become one caption: typed characters run on, other keys and
+// shortcuts are spaced out
+fn keystroke_captions(keys: &[(f64, &str)]) -> Vec<KeyCaption> {
+    let mut captions: Vec<KeyCaption> = Vec::new();
+    for (time, key) in keys {
+        let typed = match *key {
+            "Space" => Some(' '),
+            key if key.chars().count() == 1 => key.chars().next(),
+            _ => None,
+        };
+        match captions.last_mut() {
+            Some(caption) if time - caption.last < KEY_GROUP_GAP && caption.text.chars().count() < MAX_CAPTION_CHARS => {
+                match typed {
+                    Some(c) if caption.typing => caption.text.push(c),
+                    _ => caption.text.push_str(&format!(" {}", key)),
+                }
+                caption.last = *time;
+                caption.typing = typed.is_some();
+            }
+            _ => captions.push(KeyCaption {
+                start: *time,
+                last: *time,
+                end: 0.0,
+                text: key.to_string(),
+                typing: typed.is_some(),
+            }),
+        }
+    }
+
+    // Each caption stays up a while after its last key, but gives way to the next one
+    let starts: Vec<f64> = captions.iter().skip(1).map(|c| c.start).collect();
+    for (i, caption) in captions.iter_mut().enumerate() {
+        let hold = caption.last + KEY_HOLD_SECS;
+        caption.end = starts.get(i).map(|next| hold.min(*next)).unwrap_or(hold);
+        caption.text = caption.text.trim().to_string();
+    }
+    captions.retain(|c| !c.text.is_empty());
+    captions
+}
+
+fn keystroke_drawtext(caption: &KeyCaption, height: u32) -> String {
+    let font_size = (height / 22).max(14);
+    format!(
+        "drawtext=expansion=none:text={}:fontcolor=white:fontsize={}:box=1:boxcolor=black@0.6:boxborderw={}:x=(w-text_w)/2:y=h-text_h-{}:enable='between(t,{:.3},{:.3})'",
+        ffmpeg::escape_graph_text(&caption.text),
+        font_size,
+        font_size / 3,
+        height / 12,
+        caption.start,
+        caption.end
+    )
+}
+
+// Ring diameter in video pixels, even for yuv420p
+fn ring_size(height: u32) -> u32 {
+    ((height / 14).max(24) / 2) * 2
+}
+
+// A transparent square with a ring in it, lasting as long as the clip
+fn ring_source(size: u32, duration: f64, label: &str) -> String {
+    let center = size as f64 / 2.0;
+    let thickness = (size as f64 / 10.0).max(2.0);
+    format!(
+        "color=c=black@0.0:s={}x{}:d={:.3},format=rgba,geq=r='255':g='204':b='0':a='if(between(hypot(X-{c},Y-{c}),{},{}),220,0)'[{}]",
+        size,
+        size,
+        duration,
+        center - thickness,
+        center - 1.0,
+        label,
+        c = center
+    )
+}
+
+// One overlay that jumps to each click while its ring is up and sits off-frame otherwise
+fn ring_overlay(clicks: &[(f64, f64, f64)], scale: f64, size: u32) -> String {
+    let hidden = -2.0 * size as f64;
+    let half = size as f64 / 2.0;
+    let mut x = format!("{}", hidden);
+    let mut y = format!("{}", hidden);
+    for (i, (time, click_x, click_y)) in clicks.iter().enumerate() {
+        let until = clicks
+            .get(i + 1)
+            .map(|(next, _, _)| next.min(time + RING_SECS))
+            .unwrap_or(time + RING_SECS);
+        let window = format!("gte(t,{:.3})*lt(t,{:.3})", time, until);
+        x.push_str(&format!("+{}*{:.1}", window, click_x * scale - half - hidden));
+        y.push_str(&format!("+{}*{:.1}", window, click_y * scale - half - hidden));
+    }
+    format!("overlay=x='{}':y='{}':eof_action=pass", x, y)
+}
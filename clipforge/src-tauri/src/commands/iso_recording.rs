@@ -0,0 +1,449 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle};
+
+use crate::commands::atomic_write;
+use crate::commands::audio_meter::{self, MeterMode};
+use crate::commands::audio_devices::{self, AudioSource};
+use crate::commands::ffmpeg;
+use crate::commands::ffmpeg_capture::{self, CaptureOptions};
+use crate::commands::input_events;
+use crate::commands::output_paths;
+use crate::commands::recorder::{self, RecorderState, RecordingSettings};
+use crate::commands::screen_capture;
+use crate::commands::video_devices::{self, WebcamSelection};
+use crate::commands::video_processing::VideoClip;
+
+// ISO recording: screen, webcam and every audio source go to separate files so the
+// PiP and mix can still be changed in the editor. All tracks are captured by one
+// ffmpeg process with wall-clock timestamps; each track's offset from the earliest
+// one is stored in a "bundle.json" manifest beside them. Audio sources are metered
+// while recording (see audio_meter).
+
+lazy_static::lazy_static! {
+    static ref ISO_SESSION: Arc<Mutex<Option<IsoSession>>> = Arc::new(Mutex::new(None));
+}
+
+const MANIFEST_NAME: &str = "bundle.json";
+// How long the capture gets to open its devices before it counts as started
+const STARTUP_GRACE: Duration = Duration::from_millis(1500);
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IsoRecordingOptions {
+    pub display: Option<String>, // see CaptureOptions::display
+    pub webcam: Option<WebcamSelection>, // None records no webcam track
+    #[serde(default)]
+    pub audio_sources: Vec<AudioSource>, // one file per source, e.g. a mic and a system audio monitor
+    #[serde(flatten)]
+    pub settings: RecordingSettings, // fps, cursor and input log, given inline
+    pub output_dir: Option<String>, // defaults to the export directory
+    pub name: Option<String>,       // bundle folder name
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackKind {
+    Screen,
+    Webcam,
+    Audio,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleTrack {
+    pub kind: TrackKind,
+    pub source: String, // device the track was captured from
+    pub path: String,
+    pub offset: f64, // seconds after the earliest track's first frame
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub gain_db: f64, // applied while recording (audio tracks)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingBundle {
+    pub id: String, // shared link id of the bundle's clips
+    pub manifest_path: String,
+    pub started_at: String,
+    pub tracks: Vec<BundleTrack>,
+}
+
+struct IsoSession {
+    child: Child,
+    bundle: RecordingBundle,
+    // Input index -> wall-clock time of its first packet, parsed from ffmpeg's log
+    input_starts: Arc<Mutex<Vec<Option<f64>>>>,
+    log_reader: JoinHandle<String>,
+}
+
+/// Start an ISO recording. Stop it with stop_iso_recording (or stop_screen_recording,
+/// the tray, shortcuts and control API, which all end whichever capture is running).
+#[command]
+pub async fn start_iso_recording(app: AppHandle, options: IsoRecordingOptions) -> Result<RecordingBundle, String> {
+    options.settings.validate()?;
+    input_events::check_settings(&options.settings, options.display.as_deref(), false)?;
+    let fps = options.settings.fps();
+
+    audio_devices::validate_sources(&options.audio_sources)?;
+    if let Some(webcam) = &options.webcam {
+        video_devices::validate_selection(webcam)?;
+    }
+    let bundle = plan_bundle(&options)?;
+    let display = options.display.clone();
+    let settings = options.settings.clone();
+    recorder::transition_from(&app, &RecorderState::Idle, RecorderState::Starting { output_path: bundle.manifest_path.clone() })?;
+
+    let started = tauri::async_runtime::spawn_blocking({
+        let bundle = bundle.clone();
+        let app = app.clone();
+        move || spawn_capture(app, &options, fps, bundle)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    let session = match started {
+        Ok(session) => session,
+        Err(e) => {
+            recorder::transition(&app, RecorderState::Idle)?;
+            return Err(e);
+        }
+    };
+    *ISO_SESSION.lock().map_err(|e| e.to_string())? = Some(session);
+
+    recorder::transition(&app, RecorderState::Recording {
+        output_path: bundle.manifest_path.clone(),
+        started_at: bundle.started_at.clone(),
+        resumed_at: None,
+        elapsed_before: 0.0,
+    })?;
+    // The log belongs to the screen track (always the first)
+    input_events::start_logging(&bundle.tracks[0].path, display.as_deref(), &settings, false);
+    println!("🎙️ ISO recording started: {} track(s) in {}", bundle.tracks.len(), bundle.manifest_path);
+    Ok(bundle)
+}
+
+/// Stop the ISO recording and return the finished bundle
+#[command]
+pub async fn stop_iso_recording(app: AppHandle) -> Result<RecordingBundle, String> {
+    if !is_running() {
+        return Err("No ISO recording in progress".to_string());
+    }
+    let manifest_path = screen_capture::stop_screen_recording(app).await?;
+    load_bundle(&manifest_path)
+}
+
+/// Clips for the timeline: one per track at its offset, all sharing the bundle's link id
+#[command]
+pub fn import_recording_bundle(manifest_path: String) -> Result<Vec<VideoClip>, String> {
+    let bundle = load_bundle(&manifest_path)?;
+    bundle
+        .tracks
+        .iter()
+        .map(|track| {
+            let duration = match track.duration {
+                Some(duration) => duration,
+                None => probe_duration(&track.path)?,
+            };
+            Ok(VideoClip {
+                path: track.path.clone(),
+                start_time: track.offset,
+                duration,
+                source_offset: 0.0,
+                clip_type: match track.kind {
+                    TrackKind::Screen => "screen",
+                    TrackKind::Webcam => "webcam",
+                    TrackKind::Audio => "audio",
+                }
+                .to_string(),
+                pip_config: None,
+                link_id: Some(bundle.id.clone()),
+                input_overlay: None,
+                auto_zoom: None,
+                redactions: Vec::new(),
+                layer: None,
+            })
+        })
+        .collect()
+}
+
+pub fn is_running() -> bool {
+    ISO_SESSION.lock().map(|s| s.is_some()).unwrap_or(false)
+}
+
+/// The running bundle's screen track, which the input log and markers belong to
+pub fn screen_track_path() -> Option<String> {
+    ISO_SESSION.lock().ok()?.as_ref().map(|s| s.bundle.tracks[0].path.clone())
+}
+
+pub fn load_bundle(manifest_path: &str) -> Result<RecordingBundle, String> {
+    let content = std::fs::read_to_string(manifest_path).map_err(|e| format!("Failed to read recording bundle: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid recording bundle {}: {}", manifest_path, e))
+}
+
+/// End the running capture, commit its tracks and write the manifest. Blocks until
+/// ffmpeg has finalized the files; the recorder transitions are up to the caller.
+pub fn stop_capture() -> Result<RecordingBundle, String> {
+    let session = ISO_SESSION
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or("No ISO recording in progress")?;
+    let IsoSession { mut child, mut bundle, input_starts, log_reader } = session;
+
+    // "q" lets ffmpeg write the trailers; kill it if it doesn't finish in time
+    if let Some(stdin) = child.stdin.as_mut() {
+        let _ = stdin.write_all(b"q\n");
+    }
+    let deadline = Instant::now() + STOP_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(100)),
+            _ => {
+                println!("⚠️ ISO capture didn't stop in time, killing it");
+                let _ = child.kill();
+                break child.wait().ok();
+            }
+        }
+    };
+    let log = log_reader.join().unwrap_or_default();
+
+    let finished = bundle
+        .tracks
+        .iter()
+        .all(|t| Path::new(&atomic_write::partial_path(&t.path)).exists());
+    if !finished {
+        for track in &bundle.tracks {
+            atomic_write::discard(&atomic_write::partial_path(&track.path));
+        }
+        return Err(format!(
+            "ISO recording failed ({}): {}",
+            status.map(|s| s.to_string()).unwrap_or_default(),
+            log
+        ));
+    }
+
+    let starts = input_starts.lock().map(|s| s.clone()).unwrap_or_default();
+    let earliest = starts.iter().flatten().copied().fold(f64::INFINITY, f64::min);
+    for (i, track) in bundle.tracks.iter_mut().enumerate() {
+        atomic_write::commit(&atomic_write::partial_path(&track.path), &track.path)?;
+        track.offset = match starts.get(i).copied().flatten() {
+            Some(start) if earliest.is_finite() => ((start - earliest) * 1000.0).round() / 1000.0,
+            _ => 0.0,
+        };
+        track.duration = probe_duration(&track.path).ok();
+    }
+
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    atomic_write::write_atomic(&bundle.manifest_path, json.as_bytes())?;
+    println!("✅ ISO recording saved: {}", bundle.manifest_path);
+    Ok(bundle)
+}
+
+// Folder and file names for every track; nothing is created yet apart from the folder
+fn plan_bundle(options: &IsoRecordingOptions) -> Result<RecordingBundle, String> {
+    let dir = match &options.output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => output_paths::default_export_dir()?,
+    };
+    let name = options
+        .name
+        .as_deref()
+        .map(output_paths::sanitize_filename)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("recording-{}", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    let bundle_dir = output_paths::resolve_collision(&dir.join(&name).to_string_lossy())?;
+    std::fs::create_dir_all(&bundle_dir).map_err(|e| format!("Failed to create recording folder: {}", e))?;
+
+    let track_path = |file: String| Path::new(&bundle_dir).join(file).to_string_lossy().to_string();
+    let mut tracks = vec![BundleTrack {
+        kind: TrackKind::Screen,
+        source: options.display.clone().unwrap_or_else(|| "default".to_string()),
+        path: track_path("screen.mp4".to_string()),
+        offset: 0.0,
+        duration: None,
+        gain_db: 0.0,
+    }];
+    if let Some(webcam) = &options.webcam {
+        tracks.push(BundleTrack {
+            kind: TrackKind::Webcam,
+            source: webcam.device_id.clone(),
+            path: track_path("webcam.mp4".to_string()),
+            offset: 0.0,
+            duration: None,
+            gain_db: 0.0,
+        });
+    }
+    for (i, source) in options.audio_sources.iter().enumerate() {
+        tracks.push(BundleTrack {
+            kind: TrackKind::Audio,
+            source: source.device_id.clone(),
+            path: track_path(format!("audio-{}.wav", i + 1)),
+            offset: 0.0,
+            duration: None,
+            gain_db: source.gain_db,
+        });
+    }
+
+    Ok(RecordingBundle {
+        id: Path::new(&bundle_dir).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or(name),
+        manifest_path: track_path(MANIFEST_NAME.to_string()),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        tracks,
+    })
+}
+
+// One input per track, in track order, each stamped with the wall clock
+fn capture_args(options: &IsoRecordingOptions, fps: u32, bundle: &RecordingBundle) -> Result<Vec<String>, String> {
+    let mut args = vec!["-y".to_string()];
+    for track in &bundle.tracks {
+        args.extend([
+            "-thread_queue_size".to_string(), "1024".to_string(),
+            "-use_wallclock_as_timestamps".to_string(), "1".to_string(),
+        ]);
+        args.extend(match track.kind {
+            TrackKind::Screen => ffmpeg_capture::screen_input_args(&CaptureOptions {
+                fps,
+                display: options.display.clone(),
+                show_cursor: options.settings.show_cursor,
+                ..CaptureOptions::default()
+            })?,
+            TrackKind::Webcam => {
+                let selection = options.webcam.clone().unwrap_or_else(|| WebcamSelection {
+                    device_id: track.source.clone(),
+                    ..WebcamSelection::default()
+                });
+                ffmpeg_capture::webcam_input_args(&selection, fps)?
+            }
+            TrackKind::Audio => ffmpeg_capture::audio_input_args(Some(&track.source))?,
+        });
+    }
+
+    // Each audio source is split after its gain: one branch to its file, one to the meter
+    let audio_tracks: Vec<(usize, AudioSource)> = bundle
+        .tracks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.kind == TrackKind::Audio)
+        .map(|(i, t)| (i, AudioSource { device_id: t.source.clone(), gain_db: t.gain_db }))
+        .collect();
+    if !audio_tracks.is_empty() {
+        let mut filter = String::new();
+        for (i, source) in &audio_tracks {
+            let gain = audio_devices::gain_filter(source).unwrap_or_else(|| "anull".to_string());
+            filter.push_str(&format!("[{}:a]{},asplit=2[rec{}][met{}];", i, gain, i, i));
+        }
+        // Gain is already applied before the split
+        let unity: Vec<AudioSource> = audio_tracks.iter().map(|(_, s)| AudioSource { gain_db: 0.0, ..s.clone() }).collect();
+        let meter_inputs: Vec<(String, &AudioSource)> = audio_tracks
+            .iter()
+            .zip(&unity)
+            .map(|((i, _), source)| (format!("met{}", i), source))
+            .collect();
+        filter.push_str(&audio_meter::meter_filter(&meter_inputs, "meter"));
+        args.extend(["-filter_complex".to_string(), filter]);
+    }
+
+    for (i, track) in bundle.tracks.iter().enumerate() {
+        match track.kind {
+            TrackKind::Screen | TrackKind::Webcam => {
+                args.extend(["-map".to_string(), format!("{}:v", i)]);
+                args.extend(ffmpeg_capture::live_video_encode_args());
+            }
+            TrackKind::Audio => {
+                args.extend(["-map".to_string(), format!("[rec{}]", i), "-c:a".to_string(), "pcm_s16le".to_string()]);
+            }
+        }
+        args.push(atomic_write::partial_path(&track.path));
+    }
+    if !audio_tracks.is_empty() {
+        args.extend(audio_meter::meter_output_args("meter", "pipe:1"));
+    }
+    Ok(args)
+}
+
+fn spawn_capture(app: AppHandle, options: &IsoRecordingOptions, fps: u32, bundle: RecordingBundle) -> Result<IsoSession, String> {
+    let args = capture_args(options, fps, &bundle)?;
+    let mut child = Command::new(ffmpeg::ffmpeg_binary())
+        .args(["-hide_banner", "-nostats"])
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+
+    // Audio levels come out on stdout while the tracks are written
+    let audio_ids: Vec<String> = bundle
+        .tracks
+        .iter()
+        .filter(|t| t.kind == TrackKind::Audio)
+        .map(|t| t.source.clone())
+        .collect();
+    if let (Some(stdout), false) = (child.stdout.take(), audio_ids.is_empty()) {
+        std::thread::spawn(move || audio_meter::run_meter(app, stdout, audio_ids, MeterMode::Recording));
+    }
+
+    let input_starts = Arc::new(Mutex::new(vec![None; bundle.tracks.len()]));
+    let stderr = child.stderr.take().ok_or("ffmpeg stderr unavailable")?;
+    let log_reader = std::thread::spawn({
+        let input_starts = input_starts.clone();
+        move || read_capture_log(stderr, &input_starts)
+    });
+
+    // A missing device or permission makes ffmpeg exit right away
+    let deadline = Instant::now() + STARTUP_GRACE;
+    while Instant::now() < deadline {
+        if let Ok(Some(status)) = child.try_wait() {
+            let log = log_reader.join().unwrap_or_default();
+            for track in &bundle.tracks {
+                atomic_write::discard(&atomic_write::partial_path(&track.path));
+            }
+            return Err(format!("ISO capture exited ({}): {}", status, log));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    Ok(IsoSession {
+        child,
+        bundle,
+        input_starts,
+        log_reader,
+    })
+}
+
+// Record each input's start time ("Input #1, v4l2, ..." followed by "Duration: N/A,
+// start: 1718000000.123456, ...") and keep the end of the log for error messages
+fn read_capture_log(stderr: std::process::ChildStderr, input_starts: &Mutex<Vec<Option<f64>>>) -> String {
+    let mut current_input: Option<usize> = None;
+    let mut tail: Vec<String> = Vec::new();
+
+    for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+        if let Some(rest) = line.strip_prefix("Input #") {
+            current_input = rest.split(',').next().and_then(|n| n.trim().parse().ok());
+        } else if let (Some(input), Some(start)) = (current_input, line.split("start: ").nth(1)) {
+            if let Ok(start) = start.split(',').next().unwrap_or_default().trim().parse::<f64>() {
+                if let Some(slot) = input_starts.lock().ok().as_mut().and_then(|s| s.get_mut(input)) {
+                    *slot = Some(start);
+                }
+            }
+            current_input = None;
+        }
+
+        tail.push(line);
+        if tail.len() > 8 {
+            tail.remove(0);
+        }
+    }
+    tail.join("\n")
+}
+
+fn probe_duration(path: &str) -> Result<f64, String> {
+    ffmpeg::parse_duration(&ffmpeg::probe(path)?).ok_or_else(|| format!("Couldn't read the duration of {}", path))
+}
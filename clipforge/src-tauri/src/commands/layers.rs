@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::ffmpeg;
+use crate::commands::video_processing::VideoClip;
+
+// Overlay layers: clips drawn over the composited timeline rather than joined into it.
+//   "title": a full-frame card with centered text
+//   "text":  a lower-third caption with an optional background box
+//   "image": an image or logo (the clip's path) scaled, placed and faded
+// Like the webcam, a layer's start_time is measured from where the screen clips start.
+// Title and text clips have no file; their path is ignored.
+
+pub const LAYER_CLIP_TYPES: [&str; 3] = ["title", "text", "image"];
+
+// Font sizes are given as at 1080p and scaled with the frame
+const REFERENCE_HEIGHT: f64 = 1080.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextStyle {
+    pub font: Option<String>, // fontconfig family ("DejaVu Sans") or a font file
+    pub size: Option<f64>,    // defaults to 72 for titles, 42 for text
+    pub color: String,
+    pub background: Option<String>, // title card fill (black if unset), or the box behind text
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            font: None,
+            size: None,
+            color: "white".to_string(),
+            background: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayerConfig {
+    pub text: String, // title and text clips
+    pub style: TextStyle,
+    pub x: f64, // top-left as fractions of the frame, like PipConfig; titles are centered
+    pub y: f64,
+    pub width: f64,   // image clips: fraction of the frame width
+    pub opacity: f64, // 0-1
+}
+
+impl Default for LayerConfig {
+    fn default() -> Self {
+        LayerConfig {
+            text: String::new(),
+            style: TextStyle::default(),
+            x: 0.05,
+            y: 0.8,
+            width: 0.15,
+            opacity: 1.0,
+        }
+    }
+}
+
+pub fn is_layer(clip: &VideoClip) -> bool {
+    LAYER_CLIP_TYPES.contains(&clip.clip_type.as_str())
+}
+
+/// The clip's layer settings, or the defaults
+pub fn layer_config(clip: &VideoClip) -> LayerConfig {
+    clip.layer.clone().unwrap_or_default()
+}
+
+pub fn validate(clip: &VideoClip) -> Result<(), String> {
+    let config = layer_config(clip);
+    if !(0.0..=1.0).contains(&config.opacity) {
+        return Err(format!("Opacity {} is out of range (0-1)", config.opacity));
+    }
+    if !(0.0..=1.0).contains(&config.x) || !(0.0..=1.0).contains(&config.y) {
+        return Err(format!("{} layer at {:.1}s is outside the frame", clip.clip_type, clip.start_time));
+    }
+    match clip.clip_type.as_str() {
+        "image" => {
+            if !std::path::Path::new(&clip.path).exists() {
+                return Err(format!("Image not found: {}", clip.path));
+            }
+            if config.width <= 0.0 || config.width > 1.0 {
+                return Err(format!("Image width {} is out of range (0-1)", config.width));
+            }
+        }
+        _ => {
+            if config.text.trim().is_empty() {
+                return Err(format!("{} layer at {:.1}s has no text", clip.clip_type, clip.start_time));
+            }
+            if config.style.size.is_some_and(|s| !(4.0..=500.0).contains(&s)) {
+                return Err("Font size is out of range (4-500)".to_string());
+            }
+            let colors = std::iter::once(&config.style.color).chain(config.style.background.as_ref());
+            for color in colors {
+                if color.is_empty() || !color.chars().all(|c| c.is_ascii_alphanumeric() || c == '#' || c == '@' || c == '.') {
+                    return Err(format!("Invalid color: {}", color));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Layer clips in drawing order (later clips on top)
+pub fn timeline_layers(clips: &[VideoClip]) -> Result<Vec<&VideoClip>, String> {
+    let mut layers: Vec<&VideoClip> = clips.iter().filter(|c| is_layer(c)).collect();
+    layers.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+    for layer in &layers {
+        validate(layer)?;
+    }
+    Ok(layers)
+}
+
+/// Draw the layers from [input] to [output] on a width x height picture. Image
+/// layers read input `first_image_input` onwards, one each in order.
+pub fn layer_filter(
+    layers: &[&VideoClip],
+    first_image_input: usize,
+    timeline_start: f64,
+    input: &str,
+    output: &str,
+    width: u32,
+    height: u32,
+) -> String {
+    let mut filters = Vec::new();
+    let mut current = input.to_string();
+    let mut image_input = first_image_input;
+    for (i, layer) in layers.iter().enumerate() {
+        let next = if i + 1 == layers.len() { output.to_string() } else { format!("{}_l{}", output, i) };
+        let config = layer_config(layer);
+        let start = (layer.start_time - timeline_start).max(0.0);
+        let enable = format!("between(t,{:.3},{:.3})", start, start + layer.duration);
+        let x = (config.x * width as f64).round();
+        let y = (config.y * height as f64).round();
+
+        let filter = match layer.clip_type.as_str() {
+            "image" => {
+                let image = format!("{}_img", next);
+                let scaled_width = (((config.width * width as f64) as u32) / 2 * 2).max(2);
+                let filter = format!(
+                    "[{}:v]format=rgba,scale={}:-2,colorchannelmixer=aa={:.3},setpts=PTS-STARTPTS+{:.3}/TB[{}];[{}][{}]overlay=x={}:y={}:eof_action=pass:enable='{}'[{}]",
+                    image_input, scaled_width, config.opacity, start, image, current, image, x, y, enable, next
+                );
+                image_input += 1;
+                filter
+            }
+            "title" => {
+                let background = config.style.background.clone().unwrap_or_else(|| "black".to_string());
+                format!(
+                    "[{}]drawbox=x=0:y=0:w=iw:h=ih:color={}:t=fill:enable='{}',{}[{}]",
+                    current,
+                    with_opacity(&background, config.opacity),
+                    enable,
+                    drawtext(&config, 72.0, height, "(w-text_w)/2", "(h-text_h)/2", false, &enable),
+                    next
+                )
+            }
+            _ => format!(
+                "[{}]{}[{}]",
+                current,
+                drawtext(&config, 42.0, height, &x.to_string(), &y.to_string(), true, &enable),
+                next
+            ),
+        };
+        filters.push(filter);
+        current = next;
+    }
+    filters.join(";")
+}
+
+/// Extra input arguments an image layer needs in place of a trimmed read: loop the
+/// single frame for the clip's duration
+pub fn image_input_args(clip: &VideoClip) -> Vec<String> {
+    vec![
+        "-loop".to_string(),
+        "1".to_string(),
+        "-t".to_string(),
+        format!("{:.3}", clip.duration),
+        "-i".to_string(),
+        clip.path.clone(),
+    ]
+}
+
+fn drawtext(config: &LayerConfig, default_size: f64, height: u32, x: &str, y: &str, boxed: bool, enable: &str) -> String {
+    let style = &config.style;
+    let size = (style.size.unwrap_or(default_size) * height as f64 / REFERENCE_HEIGHT).round().max(4.0) as u32;
+    let mut filter = format!(
+        "drawtext=expansion=none:text={}:fontcolor={}:fontsize={}:x={}:y={}:alpha={:.3}",
+        ffmpeg::escape_graph_text(&config.text),
+        style.color,
+        size,
+        x,
+        y,
+        config.opacity
+    );
+    match style.font.as_deref() {
+        Some(font) if is_font_file(font) => filter.push_str(&format!(":fontfile={}", ffmpeg::escape_graph_text(font))),
+        Some(font) => filter.push_str(&format!(":font={}", ffmpeg::escape_graph_text(font))),
+        None => {}
+    }
+    if let (true, Some(background)) = (boxed, &style.background) {
+        filter.push_str(&format!(":box=1:boxcolor={}:boxborderw={}", with_opacity(background, config.opacity), size / 3));
+    }
+    filter.push_str(&format!(":enable='{}'", enable));
+    filter
+}
+
+fn is_font_file(font: &str) -> bool {
+    let lower = font.to_lowercase();
+    font.contains('/') || font.contains('\\') || [".ttf", ".otf", ".ttc"].iter().any(|ext| lower.ends_with(ext))
+}
+
+// ffmpeg color with its alpha scaled: "black@0.6" at 0.5 opacity -> "black@0.300"
+fn with_opacity(color: &str, opacity: f64) -> String {
+    let (name, alpha) = match color.split_once('@') {
+        Some((name, alpha)) => (name, alpha.parse::<f64>().unwrap_or(1.0)),
+        None => (color, 1.0),
+    };
+    format!("{}@{:.3}", name, alpha * opacity)
+}
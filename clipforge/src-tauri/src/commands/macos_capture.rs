@@ -3,6 +3,7 @@ use core_graphics::display::{CGDisplay, CGGetActiveDisplayList, CGDirectDisplayI
 use std::ffi::{CStr, CString};
 use std::sync::{Arc, Mutex};
 
+use crate::commands::recorder::RecordingSettings;
 use crate::commands::screen_capture::ScreenInfo;
 
 // C struct from Objective-C
@@ -17,16 +18,22 @@ extern "C" {
     fn start_screen_recording_objc(
         display_id: u32,
         include_audio: bool,
+        audio_device_index: i32,
         include_webcam: bool,
         pip_shape: i32,
         pip_x_percent: f32,
         pip_y_percent: f32,
         pip_size_percent: f32,
+        fps: u32,
+        capture_cursor: bool,
+        capture_clicks: bool,
         output_path: *const std::os::raw::c_char,
     ) -> CaptureResult;
     
     fn stop_screen_recording_objc() -> CaptureResult;
     
+    fn set_recording_paused_objc(paused: bool) -> CaptureResult;
+    
     fn is_recording_objc() -> bool;
     
     fn check_screen_recording_permission_objc(display_id: u32) -> std::os::raw::c_int;
@@ -74,12 +81,13 @@ pub fn get_screens() -> Result<Vec<ScreenInfo>, String> {
 
 pub async fn start_recording(
     screen_id: String,
-    include_audio: bool,
+    audio_device: Option<i32>, // index into the audio device list, -1 for the default; None records no audio
     include_webcam: bool,
     pip_shape: i32,
     pip_x: f32,
     pip_y: f32,
     pip_size: f32,
+    settings: &RecordingSettings,
     output_path: String,
 ) -> Result<(), String> {
     // Parse display ID
@@ -102,12 +110,17 @@ pub async fn start_recording(
     let result = unsafe {
         start_screen_recording_objc(
             display_id, 
-            include_audio, 
+            audio_device.is_some(),
+            audio_device.unwrap_or(-1),
             include_webcam,
             pip_shape,
             pip_x,
             pip_y,
             pip_size,
+            settings.fps(),
+            settings.show_cursor,
+            // There's no input log on macOS, so AVFoundation highlights clicks itself
+            settings.show_cursor && settings.highlight_clicks,
             c_path.as_ptr()
         )
     };
@@ -156,6 +169,23 @@ pub async fn stop_recording() -> Result<(), String> {
     Ok(())
 }
 
+/// Pause or resume the open recording without closing its file
+pub fn set_paused(paused: bool) -> Result<(), String> {
+    let result = unsafe {
+        set_recording_paused_objc(paused)
+    };
+    
+    if !result.success {
+        let error_cstr = unsafe {
+            CStr::from_ptr(result.error_message.as_ptr() as *const i8)
+        };
+        return Err(error_cstr.to_string_lossy().into_owned());
+    }
+    
+    println!("{} native screen recording", if paused { "⏸️ Paused" } else { "▶️ Resumed" });
+    Ok(())
+}
+
 pub fn is_recording() -> bool {
     unsafe {
         is_recording_objc()
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter};
+
+use crate::commands::atomic_write;
+use crate::commands::export_settings::Container;
+use crate::commands::ffmpeg;
+use crate::commands::iso_recording;
+use crate::commands::recorder::{self, RecorderState};
+use crate::commands::video_processing::VideoClip;
+
+// Markers dropped while recording ("bug shown here"). Times are seconds of recorded
+// footage, so pauses don't shift them. They are saved beside the recording (for an
+// ISO bundle, beside its screen track) as "<file>.markers.json" and can be turned
+// into chapters on export.
+
+lazy_static::lazy_static! {
+    static ref MARKER_SESSION: Arc<Mutex<Option<MarkerSession>>> = Arc::new(Mutex::new(None));
+}
+
+// YouTube ignores chapters shorter than this
+const MIN_CHAPTER_SECS: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingMarker {
+    pub time: f64, // seconds into the recording
+    pub label: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    pub start: f64, // seconds on the exported timeline
+    pub end: f64,
+    pub title: String,
+}
+
+// Markers of the recording in progress (or the last one), keyed by its start time
+struct MarkerSession {
+    started_at: String,
+    output_path: String,
+    markers: Vec<RecordingMarker>,
+}
+
+/// Drop a marker at the current position of the open recording
+#[command]
+pub fn add_recording_marker(app: AppHandle, label: Option<String>) -> Result<RecordingMarker, String> {
+    let state = recorder::current_state()?;
+    let started_at = match &state {
+        RecorderState::Recording { started_at, .. } | RecorderState::Paused { started_at, .. } => started_at.clone(),
+        other => return Err(format!("Recorder is {}, not recording", other.name())),
+    };
+    // An ISO recording's state names its manifest; its screen clips point at the screen track
+    let output_path = match iso_recording::screen_track_path() {
+        Some(screen_path) => screen_path,
+        None => state.output_path().unwrap_or_default().to_string(),
+    };
+
+    let mut session = MARKER_SESSION.lock().map_err(|e| e.to_string())?;
+    if session.as_ref().map(|s| &s.started_at) != Some(&started_at) {
+        *session = Some(MarkerSession {
+            started_at,
+            output_path: output_path.clone(),
+            markers: Vec::new(),
+        });
+    }
+    let session = session.as_mut().unwrap();
+
+    let marker = RecordingMarker {
+        time: state.elapsed_seconds(), // recorded footage, so the position in the file
+        label: label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| format!("Marker {}", session.markers.len() + 1)),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    session.markers.push(marker.clone());
+
+    // Web captures don't know their file yet; the frontend saves those with save_recording_markers
+    if !output_path.is_empty() {
+        write_markers(&output_path, &session.markers)?;
+    }
+
+    println!("📍 Marker at {:.1}s: {}", marker.time, marker.label);
+    let _ = app.emit("recording-marker-added", &marker);
+    Ok(marker)
+}
+
+/// Markers saved with `media_path`, or those of the current/last recording when None
+#[command]
+pub fn get_recording_markers(media_path: Option<String>) -> Result<Vec<RecordingMarker>, String> {
+    match media_path {
+        Some(path) => load_markers(&path),
+        None => Ok(MARKER_SESSION
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_ref()
+            .map(|s| s.markers.clone())
+            .unwrap_or_default()),
+    }
+}
+
+/// Replace the markers stored with `media_path` (edited in the UI, or attached to a web capture)
+#[command]
+pub fn save_recording_markers(media_path: String, markers: Vec<RecordingMarker>) -> Result<(), String> {
+    if !Path::new(&media_path).exists() {
+        return Err(format!("Recording not found: {}", media_path));
+    }
+    let mut markers = markers;
+    markers.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
+
+    if let Ok(mut session) = MARKER_SESSION.lock() {
+        if let Some(session) = session.as_mut().filter(|s| s.output_path == media_path) {
+            session.markers = markers.clone();
+        }
+    }
+    write_markers(&media_path, &markers)
+}
+
+pub fn markers_path(media_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.markers.json", media_path))
+}
+
+pub fn load_markers(media_path: &str) -> Result<Vec<RecordingMarker>, String> {
+    let path = markers_path(media_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read markers: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid markers file {}: {}", path.display(), e))
+}
+
+fn write_markers(media_path: &str, markers: &[RecordingMarker]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(markers).map_err(|e| e.to_string())?;
+    atomic_write::write_atomic(markers_path(media_path), json.as_bytes())
+        .map_err(|e| format!("Failed to save markers: {}", e))
+}
+
+/// Chapters for the exported timeline from the markers of its screen clips. The first
+/// chapter starts at 0:00 and markers closer than 10s to the previous one are dropped,
+/// as YouTube requires.
+pub fn timeline_chapters(clips: &[VideoClip]) -> Result<Vec<Chapter>, String> {
+    let mut points: Vec<(f64, String)> = Vec::new();
+    for clip in clips.iter().filter(|c| c.clip_type == "screen") {
+        for marker in load_markers(&clip.path)? {
+            let offset = marker.time - clip.source_offset;
+            if offset >= 0.0 && offset < clip.duration {
+                points.push((clip.start_time + offset, marker.label));
+            }
+        }
+    }
+    if points.is_empty() {
+        return Ok(Vec::new());
+    }
+    points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let total = clips.iter().map(|c| c.start_time + c.duration).fold(0.0, f64::max);
+    let mut starts: Vec<(f64, String)> = Vec::new();
+    if points[0].0 >= MIN_CHAPTER_SECS {
+        starts.push((0.0, "Intro".to_string()));
+    }
+    for (time, label) in points {
+        match starts.last() {
+            Some((previous, _)) if time - previous < MIN_CHAPTER_SECS => {
+                println!("⚠️ Skipping chapter '{}' at {:.1}s: too close to the previous one", label, time);
+            }
+            // A marker in the first seconds becomes the opening chapter
+            None => starts.push((0.0, label)),
+            Some(_) => starts.push((time, label)),
+        }
+    }
+
+    Ok(starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, title))| Chapter {
+            start: *start,
+            end: starts.get(i + 1).map(|(next, _)| *next).unwrap_or(total),
+            title: title.clone(),
+        })
+        .collect())
+}
+
+/// Embed the timeline's chapters into a rendered file (when the container has chapters)
+/// and write a YouTube chapter list beside `output_path`. No markers, no-op.
+pub fn apply_chapters(clips: &[VideoClip], render_path: &str, output_path: &str, container: Container) -> Result<(), String> {
+    let chapters = timeline_chapters(clips)?;
+    if chapters.is_empty() {
+        return Ok(());
+    }
+    if chapters.len() < 3 {
+        println!("⚠️ Only {} chapter(s); YouTube needs at least 3 to show them", chapters.len());
+    }
+
+    if container.supports_chapters() {
+        embed_chapters_in_place(render_path, &chapters)?;
+    }
+    let list_path = chapter_list_path(output_path);
+    atomic_write::write_atomic(&list_path, format_chapter_list(&chapters).as_bytes())
+        .map_err(|e| format!("Failed to write chapter list: {}", e))?;
+    println!("📑 Wrote {} chapter(s) to {}", chapters.len(), list_path.display());
+    Ok(())
+}
+
+// "demo.mp4" -> "demo.chapters.txt"
+pub fn chapter_list_path(output_path: &str) -> PathBuf {
+    Path::new(output_path).with_extension("chapters.txt")
+}
+
+/// "0:00 Intro" lines, with hours only when the video is that long
+pub fn format_chapter_list(chapters: &[Chapter]) -> String {
+    let with_hours = chapters.last().is_some_and(|c| c.start >= 3600.0);
+    chapters
+        .iter()
+        .map(|c| {
+            let secs = c.start as u64;
+            let stamp = if with_hours {
+                format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+            } else {
+                format!("{}:{:02}", secs / 60, secs % 60)
+            };
+            format!("{} {}\n", stamp, c.title)
+        })
+        .collect()
+}
+
+fn embed_chapters_in_place(path: &str, chapters: &[Chapter]) -> Result<(), String> {
+    let original = Path::new(path);
+    let file_name = original
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid export path")?;
+    let chaptered = original.with_file_name(format!("chaptered_{}", file_name)).to_string_lossy().to_string();
+    let metadata_path = format!("{}.chapters.ffmeta", path);
+
+    fs::write(&metadata_path, ffmetadata(chapters)).map_err(|e| format!("Failed to stage chapters: {}", e))?;
+    let args: Vec<String> = [
+        "-y", "-i", path, "-f", "ffmetadata", "-i", &metadata_path,
+        "-map", "0", "-map_metadata", "0", "-map_chapters", "1", "-c", "copy", &chaptered,
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    println!("📑 Embedding {} chapter(s) in {}", chapters.len(), path);
+    let result = ffmpeg::run_ffmpeg(&args)
+        .map(|_| ())
+        .and_then(|_| atomic_write::commit(&chaptered, path));
+    let _ = fs::remove_file(&metadata_path);
+    if result.is_err() {
+        let _ = fs::remove_file(&chaptered);
+    }
+    result
+}
+
+fn ffmetadata(chapters: &[Chapter]) -> String {
+    let escape = |s: &str| {
+        s.chars().fold(String::new(), |mut out, c| {
+            if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
+    };
+    let mut out = String::from(";FFMETADATA1\n");
+    for chapter in chapters {
+        out.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            (chapter.start * 1000.0).round() as u64,
+            (chapter.end * 1000.0).round() as u64,
+            escape(&chapter.title)
+        ));
+    }
+    out
+}
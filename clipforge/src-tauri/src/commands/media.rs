@@ -2,6 +2,9 @@ use tauri::command;
 use std::path::PathBuf;
 use std::fs;
 
+// File types the media library and watch folders pick up
+pub const MEDIA_EXTENSIONS: [&str; 4] = ["mp4", "mov", "webm", "avi"];
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct MediaMetadata {
 	pub duration: Option<f64>,
@@ -31,7 +34,7 @@ pub fn get_media_files(directory: String) -> Result<Vec<String>, String> {
 		if path.is_file() {
 			if let Some(ext) = path.extension() {
 				let ext_lower = ext.to_string_lossy().to_lowercase();
-				if ext_lower == "mp4" || ext_lower == "mov" || ext_lower == "webm" || ext_lower == "avi" {
+				if MEDIA_EXTENSIONS.contains(&ext_lower.as_str()) {
 					if let Some(path_str) = path.to_str() {
 						files.push(path_str.to_string());
 					}
@@ -94,14 +97,12 @@ pub fn save_recording(
 	file_name: String,
 	output_dir: String
 ) -> Result<String, String> {
-	let output_path = PathBuf::from(&output_dir).join(&file_name);
+	let output_path = crate::commands::output_paths::resolve_save_path(&output_dir, &file_name)?;
 	
-	fs::write(&output_path, content)
+	crate::commands::atomic_write::write_atomic(&output_path, &content)
 		.map_err(|e| format!("Failed to save recording: {}", e))?;
 	
-	Ok(output_path.to_str()
-		.ok_or("Failed to convert path to string")?
-		.to_string())
+	Ok(output_path)
 }
 
 #[command]
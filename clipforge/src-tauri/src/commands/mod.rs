@@ -1,6 +1,36 @@
 pub mod media;
 pub mod screen_capture;
 pub mod recording;
+#[cfg(target_os = "macos")]
 pub mod macos_capture;
 pub mod window;
 pub mod video_processing;
+pub mod ffmpeg;
+pub mod silence;
+pub mod captions;
+pub mod project;
+pub mod transcription;
+pub mod ffmpeg_export;
+pub mod export_jobs;
+pub mod export_settings;
+pub mod reframe;
+pub mod output_paths;
+pub mod atomic_write;
+pub mod settings_store;
+pub mod preview;
+pub mod ffmpeg_capture;
+pub mod watch_folders;
+pub mod recorder;
+pub mod scheduled_recording;
+pub mod markers;
+pub mod iso_recording;
+pub mod audio_devices;
+pub mod audio_meter;
+pub mod video_devices;
+pub mod input_events;
+pub mod auto_zoom;
+pub mod redaction;
+pub mod layers;
+pub mod control_api;
+pub mod shortcuts;
+pub mod tray;
@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle};
+
+use crate::commands::atomic_write;
+use crate::commands::settings_store;
+
+// Where saved recordings and exports end up: the default export directory,
+// the file name template and what to do when the target file already exists.
+// Saved in the settings store and restored at startup.
+
+const OUTPUT_CONFIG_KEY: &str = "output";
+
+lazy_static::lazy_static! {
+    static ref OUTPUT_CONFIG: Arc<Mutex<OutputConfig>> = Arc::new(Mutex::new(OutputConfig::default()));
+    static ref TOKEN_RE: regex::Regex = regex::Regex::new(r"\{([a-z]*)\}").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    #[default]
+    Increment, // name.ext -> name_(1).ext -> name_(2).ext ...
+    Overwrite,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputConfig {
+    pub export_dir: Option<String>, // None falls back to ~/Downloads
+    pub template: String,           // used when an export isn't given a name
+    pub collision: CollisionPolicy,
+}
+
+/// Values substituted into an output template
+#[derive(Debug, Clone, Default)]
+pub struct TemplateValues {
+    pub project: Option<String>,
+    pub resolution: Option<String>,
+    pub preset: Option<String>,
+    pub ext: String,
+}
+
+pub const DEFAULT_TEMPLATE: &str = "{project}-{date}-{resolution}.{ext}";
+const TEMPLATE_TOKENS: &[&str] = &["project", "date", "time", "resolution", "preset", "ext"];
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            export_dir: None,
+            template: DEFAULT_TEMPLATE.to_string(),
+            collision: CollisionPolicy::Increment,
+        }
+    }
+}
+
+#[command]
+pub fn configure_output(app: AppHandle, config: OutputConfig) -> Result<(), String> {
+    validate_template(&config.template)?;
+    if let Some(dir) = &config.export_dir {
+        if !Path::new(dir).is_absolute() {
+            return Err(format!("Export directory must be an absolute path: {}", dir));
+        }
+    }
+    settings_store::save(&app, OUTPUT_CONFIG_KEY, &config)?;
+    let mut current = OUTPUT_CONFIG.lock().map_err(|e| e.to_string())?;
+    *current = config;
+    Ok(())
+}
+
+#[command]
+pub fn get_output_config() -> Result<OutputConfig, String> {
+    Ok(OUTPUT_CONFIG.lock().map_err(|e| e.to_string())?.clone())
+}
+
+pub fn output_config() -> Result<OutputConfig, String> {
+    Ok(OUTPUT_CONFIG.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Load the saved output settings at startup; a template that no longer validates
+/// is dropped in favour of the defaults
+pub fn restore_config(app: &AppHandle) {
+    if let Some(config) = settings_store::load::<OutputConfig>(app, OUTPUT_CONFIG_KEY) {
+        if validate_template(&config.template).is_err() {
+            return;
+        }
+        if let Ok(mut current) = OUTPUT_CONFIG.lock() {
+            *current = config;
+        }
+    }
+}
+
+pub fn sanitize_filename(filename: &str) -> String {
+    filename
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            _ => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn validate_template(template: &str) -> Result<(), String> {
+    if template.trim().is_empty() {
+        return Err("Output template can't be empty".to_string());
+    }
+    for caps in TOKEN_RE.captures_iter(template) {
+        if !TEMPLATE_TOKENS.contains(&&caps[1]) {
+            return Err(format!(
+                "Unknown template token {{{}}}; expected one of {}",
+                &caps[1],
+                TEMPLATE_TOKENS.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Fill in a template such as "{project}-{date}-{resolution}.{ext}". Missing values
+/// collapse along with the separator in front of them.
+pub fn render_template(template: &str, values: &TemplateValues) -> Result<String, String> {
+    validate_template(template)?;
+    let now = chrono::Local::now();
+
+    let mut rendered = template.to_string();
+    for token in TEMPLATE_TOKENS {
+        let value = match *token {
+            "project" => values.project.clone(),
+            "date" => Some(now.format("%Y-%m-%d").to_string()),
+            "time" => Some(now.format("%H%M%S").to_string()),
+            "resolution" => values.resolution.clone(),
+            "preset" => values.preset.clone(),
+            _ => Some(values.ext.clone()),
+        };
+        let placeholder = format!("{{{}}}", token);
+        match value.map(|v| sanitize_filename(&v)).filter(|v| !v.is_empty()) {
+            Some(value) => rendered = rendered.replace(&placeholder, &value),
+            None => {
+                for separator in ["-", "_", " "] {
+                    rendered = rendered.replace(&format!("{}{}", separator, placeholder), "");
+                }
+                rendered = rendered.replace(&placeholder, "");
+            }
+        }
+    }
+
+    let rendered = sanitize_filename(rendered.trim_start_matches(['-', '_', '.']));
+    if rendered.is_empty() || rendered.starts_with('.') {
+        return Err(format!("Output template '{}' produced an empty file name", template));
+    }
+    Ok(rendered)
+}
+
+/// The directory exports go to when they aren't given an absolute path
+pub fn default_export_dir() -> Result<PathBuf, String> {
+    let config = output_config()?;
+    let dir = match config.export_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home_dir = std::env::var("HOME").map_err(|e| format!("Failed to get HOME directory: {}", e))?;
+            PathBuf::from(home_dir).join("Downloads")
+        }
+    };
+
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create export directory: {}", e))?;
+        println!("✅ Created export directory: {}", dir.display());
+    }
+    let metadata = std::fs::metadata(&dir).map_err(|e| format!("Failed to access export directory: {}", e))?;
+    if metadata.permissions().readonly() {
+        return Err(format!("Export directory is read-only: {}", dir.display()));
+    }
+    Ok(dir)
+}
+
+/// Resolve where an export is written. Absolute paths are used as given, names
+/// containing template tokens are rendered, bare names get the right extension,
+/// and an empty name uses the configured template. Relative results land in the
+/// default export directory. The collision policy is applied last.
+pub fn resolve_export_path(requested: &str, values: &TemplateValues) -> Result<String, String> {
+    let requested = requested.trim();
+    let config = output_config()?;
+
+    let path = if Path::new(requested).is_absolute() {
+        PathBuf::from(requested)
+    } else {
+        let file_name = if requested.is_empty() {
+            render_template(&config.template, values)?
+        } else if TOKEN_RE.is_match(requested) {
+            render_template(requested, values)?
+        } else {
+            with_extension(&sanitize_filename(requested), &values.ext)
+        };
+        default_export_dir()?.join(file_name)
+    };
+
+    apply_collision_policy(path, config.collision)
+}
+
+/// Resolve a save into an explicit directory, as the recording commands do
+pub fn resolve_save_path(output_dir: &str, file_name: &str) -> Result<String, String> {
+    let file_name = sanitize_filename(file_name);
+    if file_name.is_empty() {
+        return Err("File name can't be empty".to_string());
+    }
+    let policy = output_config()?.collision;
+    apply_collision_policy(PathBuf::from(output_dir).join(file_name), policy)
+}
+
+/// Apply the configured collision policy to a full path
+pub fn resolve_collision(path: &str) -> Result<String, String> {
+    apply_collision_policy(PathBuf::from(path), output_config()?.collision)
+}
+
+/// Move a finished export from its partial file to `path`, applying the collision
+/// policy again: another export may have taken the name since it was resolved.
+/// Returns where the file ended up.
+pub fn commit_export(partial: &str, path: &str) -> Result<String, String> {
+    let policy = output_config()?.collision;
+    if policy == CollisionPolicy::Overwrite {
+        atomic_write::commit(partial, path)?;
+        return Ok(path.to_string());
+    }
+
+    let mut target = PathBuf::from(path);
+    loop {
+        let target_str = target.to_string_lossy().to_string();
+        if atomic_write::commit_new(partial, &target_str)? {
+            return Ok(target_str);
+        }
+        match policy {
+            CollisionPolicy::Fail => return Err(format!("File already exists: {}", target_str)),
+            _ => {
+                target = next_free_path(&target);
+                println!("📁 {} was taken while exporting, using {}", target_str, target.display());
+            }
+        }
+    }
+}
+
+fn with_extension(file_name: &str, extension: &str) -> String {
+    let file_name = if file_name.is_empty() { "clipforge-export" } else { file_name };
+    if file_name.ends_with(&format!(".{}", extension)) {
+        return file_name.to_string();
+    }
+    match file_name.rfind('.') {
+        Some(dot_pos) if dot_pos > 0 => format!("{}.{}", &file_name[..dot_pos], extension),
+        _ => format!("{}.{}", file_name, extension),
+    }
+}
+
+fn apply_collision_policy(path: PathBuf, policy: CollisionPolicy) -> Result<String, String> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            return Err(format!("Output directory does not exist: {}", parent.display()));
+        }
+    }
+
+    if path.exists() {
+        match policy {
+            CollisionPolicy::Overwrite => {
+                println!("📁 Overwriting existing file: {}", path.display());
+            }
+            CollisionPolicy::Fail => {
+                return Err(format!("File already exists: {}", path.display()));
+            }
+            CollisionPolicy::Increment => {
+                let path = next_free_path(&path);
+                println!("📁 File already exists, using unique filename: {}", path.display());
+                return Ok(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+// First free "name_(N).ext" next to the given path
+fn next_free_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("export");
+    let (base_name, ext) = match file_name.rfind('.') {
+        Some(dot_pos) if dot_pos > 0 => (&file_name[..dot_pos], &file_name[dot_pos..]),
+        _ => (file_name, ""),
+    };
+
+    (1..)
+        .map(|counter| path.with_file_name(format!("{}_({}){}", base_name, counter, ext)))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(project: Option<&str>, resolution: Option<&str>, preset: Option<&str>) -> TemplateValues {
+        TemplateValues {
+            project: project.map(str::to_string),
+            resolution: resolution.map(str::to_string),
+            preset: preset.map(str::to_string),
+            ext: "mp4".to_string(),
+        }
+    }
+
+    #[test]
+    fn fills_in_every_token() {
+        let values = values(Some("Demo"), Some("1080p"), Some("web"));
+        assert_eq!(render_template("{project}-{resolution}-{preset}.{ext}", &values).unwrap(), "Demo-1080p-web.mp4");
+
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(render_template(DEFAULT_TEMPLATE, &values).unwrap(), format!("Demo-{}-1080p.mp4", date));
+    }
+
+    #[test]
+    fn missing_values_take_their_separator_with_them() {
+        let values = values(Some("Demo"), None, None);
+        assert_eq!(render_template("{project}-{resolution}_{preset}.{ext}", &values).unwrap(), "Demo.mp4");
+        assert_eq!(render_template("{resolution}-{project} {preset}.{ext}", &values).unwrap(), "Demo.mp4");
+    }
+
+    #[test]
+    fn rejects_unknown_tokens_and_empty_names() {
+        let values = values(None, None, None);
+        assert!(render_template("{project}-{name}.{ext}", &values).is_err());
+        assert!(render_template("  ", &values).is_err());
+        assert!(render_template("{project}", &values).is_err());
+    }
+
+    #[test]
+    fn token_values_are_sanitized() {
+        let values = values(Some("client/demo: v2?"), Some("1280x720"), None);
+        assert_eq!(render_template("{project}_{resolution}.{ext}", &values).unwrap(), "client_demo_ v2__1280x720.mp4");
+        assert_eq!(sanitize_filename("  a\\b*c<d>e|f\"g  "), "a_b_c_d_e_f_g");
+    }
+
+    #[test]
+    fn extension_replaces_the_existing_one() {
+        assert_eq!(with_extension("clip", "mp4"), "clip.mp4");
+        assert_eq!(with_extension("clip.mov", "mp4"), "clip.mp4");
+        assert_eq!(with_extension("clip.mp4", "mp4"), "clip.mp4");
+        assert_eq!(with_extension(".hidden", "mp4"), ".hidden.mp4");
+        assert_eq!(with_extension("", "mp4"), "clipforge-export.mp4");
+    }
+}
@@ -0,0 +1,68 @@
+use crate::commands::atomic_write;
+use crate::commands::ffmpeg;
+
+// Still images generated from media files: a poster frame and a rendered waveform
+
+/// Grab the frame at `time` seconds, scaled down to `width` pixels wide if it's larger
+pub fn extract_thumbnail(input: &str, output: &str, time: f64, width: Option<u32>) -> Result<(), String> {
+    if time < 0.0 {
+        return Err(format!("Thumbnail time must not be negative: {}", time));
+    }
+    if let Some(duration) = ffmpeg::parse_duration(&ffmpeg::probe(input)?) {
+        if time > duration {
+            return Err(format!("Thumbnail time {:.2}s is past the end of the file ({:.2}s)", time, duration));
+        }
+    }
+
+    let scale = match width {
+        Some(width) => format!("scale='min({},iw)':-2:flags=lanczos", width),
+        None => "null".to_string(),
+    };
+
+    let partial_path = atomic_write::partial_path(output);
+    let args = vec![
+        "-y".to_string(),
+        "-ss".to_string(), format!("{:.3}", time),
+        "-i".to_string(), input.to_string(),
+        "-frames:v".to_string(), "1".to_string(),
+        "-vf".to_string(), scale,
+        "-q:v".to_string(), "2".to_string(),
+        partial_path.clone(),
+    ];
+
+    let result = ffmpeg::run_ffmpeg(&args).and_then(|_| atomic_write::commit(&partial_path, output));
+    if result.is_err() {
+        atomic_write::discard(&partial_path);
+    }
+    result
+}
+
+/// Render the audio track as a waveform picture
+pub fn render_waveform(input: &str, output: &str, width: u32, height: u32, color: &str) -> Result<(), String> {
+    if !(16..=8192).contains(&width) || !(16..=4096).contains(&height) {
+        return Err(format!("Waveform size {}x{} is out of range", width, height));
+    }
+    if color.is_empty() || !color.chars().all(|c| c.is_ascii_alphanumeric() || c == '#') {
+        return Err(format!("Invalid waveform color: {}", color));
+    }
+    if !ffmpeg::has_audio_stream(input)? {
+        return Err(format!("No audio stream found in {}", input));
+    }
+
+    let partial_path = atomic_write::partial_path(output);
+    let args = vec![
+        "-y".to_string(),
+        "-i".to_string(), input.to_string(),
+        "-filter_complex".to_string(),
+        format!("[0:a]aformat=channel_layouts=mono,showwavespic=s={}x{}:colors={}[wave]", width, height, color),
+        "-map".to_string(), "[wave]".to_string(),
+        "-frames:v".to_string(), "1".to_string(),
+        partial_path.clone(),
+    ];
+
+    let result = ffmpeg::run_ffmpeg(&args).and_then(|_| atomic_write::commit(&partial_path, output));
+    if result.is_err() {
+        atomic_write::discard(&partial_path);
+    }
+    result
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::command;
+
+use crate::commands::atomic_write;
+use crate::commands::captions::CaptionTrack;
+use crate::commands::video_processing::VideoClip;
+
+// 2: title, text and image layer clips
+pub const PROJECT_FORMAT_VERSION: u32 = 2;
+
+// On-disk project file: the timeline clips plus everything layered on top of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub clips: Vec<VideoClip>,
+    #[serde(default)]
+    pub caption_tracks: Vec<CaptionTrack>,
+}
+
+fn default_version() -> u32 {
+    PROJECT_FORMAT_VERSION
+}
+
+impl Project {
+    pub fn load(file_path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read project file: {}", e))?;
+        let project: Project = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid project file {}: {}", file_path, e))?;
+
+        if project.version > PROJECT_FORMAT_VERSION {
+            return Err(format!(
+                "Project file version {} is newer than supported version {}",
+                project.version, PROJECT_FORMAT_VERSION
+            ));
+        }
+
+        Ok(project)
+    }
+
+    pub fn save(&self, file_path: &str) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize project: {}", e))?;
+        atomic_write::write_atomic(file_path, content.as_bytes())
+            .map_err(|e| format!("Failed to write project file: {}", e))
+    }
+}
+
+#[command]
+pub fn load_project(file_path: String) -> Result<Project, String> {
+    Project::load(&file_path)
+}
+
+#[command]
+pub fn save_project(project: Project, file_path: String) -> Result<String, String> {
+    let project = Project { version: PROJECT_FORMAT_VERSION, ..project };
+    project.save(&file_path)?;
+    Ok(file_path)
+}
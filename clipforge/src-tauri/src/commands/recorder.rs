@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+use crate::commands::screen_capture;
+
+// Recorder state machine. Every start/stop path (commands, tray, control API)
+// moves through these states so the UI and remote clients see the same thing.
+//
+//   Idle -> Starting -> Recording -> Stopping -> Idle
+//   Starting -> Idle when the capture fails to start
+//   Recording <-> Paused, Paused -> Stopping
+//   Idle -> Scheduled -> (Countdown ->) Starting, Idle -> Countdown -> Starting
+//   Scheduled/Countdown -> Idle when cancelled
+
+lazy_static::lazy_static! {
+    static ref RECORDER_STATE: Arc<Mutex<RecorderState>> = Arc::new(Mutex::new(RecorderState::Idle));
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum RecorderState {
+    Idle,
+    Scheduled {
+        output_path: String,
+        starts_at: String,
+        countdown: Option<u32>,
+        limits: RecordingLimits,
+    },
+    Countdown { output_path: String, remaining: u32 },
+    Starting { output_path: String },
+    Recording {
+        output_path: String,
+        started_at: String,
+        resumed_at: Option<String>, // set after a pause; elapsed time counts from here
+        #[serde(default)]
+        elapsed_before: f64, // seconds recorded before the last pause
+    },
+    Paused { output_path: String, started_at: String, elapsed: f64 },
+    Stopping { output_path: String },
+}
+
+/// Stop a recording automatically once either limit is reached
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordingLimits {
+    pub max_duration_secs: Option<f64>, // recorded time, pauses excluded
+    pub max_file_size_mb: Option<f64>,
+}
+
+impl RecordingLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_duration_secs.is_none() && self.max_file_size_mb.is_none()
+    }
+}
+
+/// How the screen is captured. Clicks and keystrokes go to an input log beside the
+/// recording (see input_events) and are drawn at export, not burned into the capture.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordingSettings {
+    pub fps: Option<u32>, // defaults to 30
+    pub show_cursor: bool,
+    pub highlight_clicks: bool,   // ring around every click
+    pub capture_keystrokes: bool, // keystroke overlay; off by default since it logs what is typed
+    pub track_cursor: bool,       // pointer positions and clicks for auto-zoom
+}
+
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        RecordingSettings {
+            fps: None,
+            show_cursor: true,
+            highlight_clicks: false,
+            capture_keystrokes: false,
+            track_cursor: false,
+        }
+    }
+}
+
+impl RecordingSettings {
+    pub fn fps(&self) -> u32 {
+        self.fps.unwrap_or(30)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=120).contains(&self.fps()) {
+            return Err(format!("Frame rate {} is out of range (1-120)", self.fps()));
+        }
+        Ok(())
+    }
+
+    /// The settings that need the input log, by name. `native_clicks` is set when the
+    /// capture draws clicks itself (AVFoundation), so highlight_clicks doesn't.
+    pub fn logged_settings(&self, native_clicks: bool) -> Vec<&'static str> {
+        [
+            ("highlight_clicks", self.highlight_clicks && !native_clicks),
+            ("capture_keystrokes", self.capture_keystrokes),
+            ("track_cursor", self.track_cursor),
+        ]
+        .into_iter()
+        .filter(|(_, logged)| *logged)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CountdownTick {
+    output_path: String,
+    remaining: u32,
+}
+
+impl RecorderState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecorderState::Idle => "idle",
+            RecorderState::Scheduled { .. } => "scheduled",
+            RecorderState::Countdown { .. } => "countdown",
+            RecorderState::Starting { .. } => "starting",
+            RecorderState::Recording { .. } => "recording",
+            RecorderState::Paused { .. } => "paused",
+            RecorderState::Stopping { .. } => "stopping",
+        }
+    }
+
+    /// Seconds actually recorded, not counting time spent paused
+    pub fn elapsed_seconds(&self) -> f64 {
+        match self {
+            RecorderState::Recording { started_at, resumed_at, elapsed_before, .. } => {
+                let since = resumed_at.as_deref().unwrap_or(started_at);
+                let since = chrono::DateTime::parse_from_rfc3339(since)
+                    .map(|t| t.with_timezone(&chrono::Utc))
+                    .unwrap_or_else(|_| chrono::Utc::now());
+                elapsed_before + (chrono::Utc::now() - since).num_milliseconds().max(0) as f64 / 1000.0
+            }
+            RecorderState::Paused { elapsed, .. } => *elapsed,
+            _ => 0.0,
+        }
+    }
+
+    /// Recording or paused: a capture is open
+    pub fn is_active(&self) -> bool {
+        matches!(self, RecorderState::Recording { .. } | RecorderState::Paused { .. })
+    }
+
+    /// Scheduled or counting down: a recording will start unless cancelled
+    pub fn is_pending(&self) -> bool {
+        matches!(self, RecorderState::Scheduled { .. } | RecorderState::Countdown { .. })
+    }
+
+    pub fn output_path(&self) -> Option<&str> {
+        match self {
+            RecorderState::Idle => None,
+            RecorderState::Scheduled { output_path, .. }
+            | RecorderState::Countdown { output_path, .. }
+            | RecorderState::Starting { output_path }
+            | RecorderState::Recording { output_path, .. }
+            | RecorderState::Paused { output_path, .. }
+            | RecorderState::Stopping { output_path } => Some(output_path),
+        }
+    }
+
+    fn can_move_to(&self, next: &RecorderState) -> bool {
+        matches!(
+            (self, next),
+            (RecorderState::Idle, RecorderState::Starting { .. })
+                | (RecorderState::Idle, RecorderState::Scheduled { .. })
+                | (RecorderState::Idle, RecorderState::Countdown { .. })
+                | (RecorderState::Scheduled { .. }, RecorderState::Countdown { .. })
+                | (RecorderState::Scheduled { .. }, RecorderState::Starting { .. })
+                | (RecorderState::Scheduled { .. }, RecorderState::Idle)
+                | (RecorderState::Countdown { .. }, RecorderState::Countdown { .. })
+                | (RecorderState::Countdown { .. }, RecorderState::Starting { .. })
+                | (RecorderState::Countdown { .. }, RecorderState::Idle)
+                | (RecorderState::Starting { .. }, RecorderState::Recording { .. })
+                | (RecorderState::Starting { .. }, RecorderState::Idle)
+                | (RecorderState::Recording { .. }, RecorderState::Paused { .. })
+                | (RecorderState::Paused { .. }, RecorderState::Recording { .. })
+                | (RecorderState::Recording { .. }, RecorderState::Stopping { .. })
+                | (RecorderState::Paused { .. }, RecorderState::Stopping { .. })
+                | (RecorderState::Stopping { .. }, RecorderState::Idle)
+        )
+    }
+}
+
+pub fn current_state() -> Result<RecorderState, String> {
+    Ok(RECORDER_STATE.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Move to `next`, rejecting transitions the machine doesn't allow, and broadcast the change
+pub fn transition(app: &AppHandle, next: RecorderState) -> Result<RecorderState, String> {
+    apply_transition(app, None, next)
+}
+
+/// Like `transition`, but only from exactly `expected`. Used where the state may have
+/// changed underneath us, e.g. a countdown or schedule cancelled while we slept.
+pub fn transition_from(app: &AppHandle, expected: &RecorderState, next: RecorderState) -> Result<RecorderState, String> {
+    apply_transition(app, Some(expected), next)
+}
+
+fn apply_transition(app: &AppHandle, expected: Option<&RecorderState>, next: RecorderState) -> Result<RecorderState, String> {
+    let previous = {
+        let mut state = RECORDER_STATE.lock().map_err(|e| e.to_string())?;
+        if expected.is_some_and(|expected| *state != *expected) {
+            return Err(match (expected, &*state) {
+                (Some(RecorderState::Scheduled { .. }), _) => "Scheduled recording was cancelled".to_string(),
+                (Some(RecorderState::Countdown { .. }), _) => "Countdown was cancelled".to_string(),
+                (_, RecorderState::Scheduled { .. }) => "A recording is already scheduled".to_string(),
+                _ => "Recording already in progress".to_string(),
+            });
+        }
+        if !state.can_move_to(&next) {
+            return Err(match (&*state, &next) {
+                (RecorderState::Idle, _) => "No recording in progress".to_string(),
+                (_, RecorderState::Starting { .. } | RecorderState::Scheduled { .. } | RecorderState::Countdown { .. }) => {
+                    "Recording already in progress".to_string()
+                }
+                (current, _) => format!("Recorder is {} and can't move to {}", current.name(), next.name()),
+            });
+        }
+        std::mem::replace(&mut *state, next.clone())
+    };
+
+    if previous.name() != next.name() {
+        println!("🎛️ Recorder: {} -> {}", previous.name(), next.name());
+    }
+    let _ = app.emit("recorder-state-changed", next.clone());
+    Ok(previous)
+}
+
+/// Count down from `seconds`, starting from `from` (idle or a due schedule). Emits a
+/// "recording-countdown" event every second and returns the last countdown state, which
+/// the caller moves on from with `transition_from`. Fails if the countdown is cancelled.
+pub async fn run_countdown(app: &AppHandle, from: &RecorderState, output_path: &str, seconds: u32) -> Result<RecorderState, String> {
+    let mut current = from.clone();
+    for remaining in (1..=seconds).rev() {
+        let next = RecorderState::Countdown { output_path: output_path.to_string(), remaining };
+        transition_from(app, &current, next.clone())?;
+        let _ = app.emit("recording-countdown", CountdownTick { output_path: output_path.to_string(), remaining });
+        current = next;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    Ok(current)
+}
+
+#[command]
+pub fn get_recorder_state() -> Result<RecorderState, String> {
+    current_state()
+}
+
+/// Pause the open recording. A native capture is paused here; one the frontend runs
+/// follows the "recorder-state-changed" event.
+#[command]
+pub fn pause_recording(app: AppHandle) -> Result<RecorderState, String> {
+    let current = current_state()?;
+    let RecorderState::Recording { output_path, started_at, .. } = &current else {
+        return Err(format!("Recorder is {}, not recording", current.name()));
+    };
+    screen_capture::set_capture_paused(true)?;
+    let paused = RecorderState::Paused {
+        output_path: output_path.clone(),
+        started_at: started_at.clone(),
+        elapsed: current.elapsed_seconds(),
+    };
+    transition(&app, paused.clone())?;
+    Ok(paused)
+}
+
+#[command]
+pub fn resume_recording(app: AppHandle) -> Result<RecorderState, String> {
+    let RecorderState::Paused { output_path, started_at, elapsed } = current_state()? else {
+        return Err("Recording is not paused".to_string());
+    };
+    screen_capture::set_capture_paused(false)?;
+    let resumed = RecorderState::Recording {
+        output_path,
+        started_at,
+        resumed_at: Some(chrono::Utc::now().to_rfc3339()),
+        elapsed_before: elapsed,
+    };
+    transition(&app, resumed.clone())?;
+    Ok(resumed)
+}
+
+/// Cancel a countdown or a scheduled recording before it starts
+#[command]
+pub fn cancel_pending_recording(app: AppHandle) -> Result<RecorderState, String> {
+    let current = current_state()?;
+    if !current.is_pending() {
+        return Err(format!("Recorder is {}, nothing to cancel", current.name()));
+    }
+    transition_from(&app, &current, RecorderState::Idle)?;
+    println!("🚫 Pending recording cancelled");
+    Ok(RecorderState::Idle)
+}
+
+/// For captures run outside the native pipeline (the webview's MediaRecorder):
+/// report that one started or stopped so the tray, shortcuts and control API see it.
+/// Reporting the state the recorder is already in is a no-op.
+#[command]
+pub fn report_recording(app: AppHandle, recording: bool, output_path: Option<String>) -> Result<RecorderState, String> {
+    let current = current_state()?;
+    let output_path = output_path.unwrap_or_default();
+
+    match (recording, &current) {
+        (true, RecorderState::Idle) => {
+            transition(&app, RecorderState::Starting { output_path: output_path.clone() })?;
+            transition(&app, RecorderState::Recording {
+                output_path,
+                started_at: chrono::Utc::now().to_rfc3339(),
+                resumed_at: None,
+                elapsed_before: 0.0,
+            })?;
+        }
+        (false, state) if state.is_active() => {
+            let output_path = state.output_path().unwrap_or_default().to_string();
+            transition(&app, RecorderState::Stopping { output_path })?;
+            transition(&app, RecorderState::Idle)?;
+        }
+        _ => {}
+    }
+    current_state()
+}
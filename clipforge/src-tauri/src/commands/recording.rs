@@ -1,5 +1,4 @@
 use tauri::command;
-use std::fs;
 
 #[command]
 pub fn save_screen_recording(
@@ -7,14 +6,12 @@ pub fn save_screen_recording(
 	file_name: String,
 	output_dir: String
 ) -> Result<String, String> {
-	let output_path = std::path::PathBuf::from(&output_dir).join(&file_name);
+	let output_path = crate::commands::output_paths::resolve_save_path(&output_dir, &file_name)?;
 	
-	fs::write(&output_path, content)
+	crate::commands::atomic_write::write_atomic(&output_path, &content)
 		.map_err(|e| format!("Failed to save recording: {}", e))?;
 	
-	Ok(output_path.to_str()
-		.ok_or("Failed to convert path to string")?
-		.to_string())
+	Ok(output_path)
 }
 
 #[command]
@@ -0,0 +1,302 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::commands::ffmpeg;
+use crate::commands::project::Project;
+use crate::commands::video_processing::VideoClip;
+
+// Privacy redaction: rectangles over a screen clip (an API key, an inbox) hidden at
+// export with a Gaussian blur, pixelation or a solid fill. Regions are stored on the
+// clip in seconds of its source file, like the input log, so trimming, splitting or
+// moving the clip keeps them on the footage; the commands below list and edit them
+// in timeline seconds.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionRect {
+    pub x: f64, // fractions of the frame, like PipConfig
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Where the rectangle's top-left corner is at `time`; it moves linearly between keyframes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionKeyframe {
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RedactionStyle {
+    Blur {
+        #[serde(default = "default_sigma")]
+        sigma: f64,
+    },
+    Pixelate {
+        #[serde(default = "default_block_size")]
+        block_size: u32, // source pixels per block
+    },
+    Fill {
+        #[serde(default = "default_fill_color")]
+        color: String, // ffmpeg color
+    },
+}
+
+fn default_sigma() -> f64 {
+    20.0
+}
+
+fn default_block_size() -> u32 {
+    16
+}
+
+fn default_fill_color() -> String {
+    "black".to_string()
+}
+
+impl Default for RedactionStyle {
+    fn default() -> Self {
+        RedactionStyle::Blur { sigma: default_sigma() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redaction {
+    #[serde(default)]
+    pub id: String, // assigned when saved
+    pub start: f64,
+    pub end: f64,
+    pub rect: RedactionRect, // size, and position when there are no keyframes
+    #[serde(default)]
+    pub keyframes: Vec<RedactionKeyframe>,
+    #[serde(default)]
+    pub style: RedactionStyle,
+}
+
+/// A clip's redaction with its times on the timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineRedaction {
+    pub clip_index: usize,
+    pub redaction: Redaction,
+}
+
+impl Redaction {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.end <= self.start {
+            return Err(format!("Redaction {} ends before it starts", self.id));
+        }
+        let rect = &self.rect;
+        if rect.width <= 0.0 || rect.height <= 0.0 || rect.width > 1.0 || rect.height > 1.0 {
+            return Err(format!("Redaction {} has an invalid size", self.id));
+        }
+        let positions = std::iter::once((rect.x, rect.y)).chain(self.keyframes.iter().map(|k| (k.x, k.y)));
+        for (x, y) in positions {
+            if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+                return Err(format!("Redaction {} is outside the frame", self.id));
+            }
+        }
+        match &self.style {
+            RedactionStyle::Blur { sigma } if !(1.0..=100.0).contains(sigma) => {
+                Err(format!("Blur strength {} is out of range (1-100)", sigma))
+            }
+            RedactionStyle::Pixelate { block_size } if !(2..=200).contains(block_size) => {
+                Err(format!("Pixel block size {} is out of range (2-200)", block_size))
+            }
+            RedactionStyle::Fill { color }
+                if color.is_empty() || !color.chars().all(|c| c.is_ascii_alphanumeric() || c == '#' || c == '@' || c == '.') =>
+            {
+                Err(format!("Invalid fill color: {}", color))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Same region with every time moved by `offset` seconds
+    fn shifted(&self, offset: f64) -> Redaction {
+        let mut redaction = self.clone();
+        redaction.start += offset;
+        redaction.end += offset;
+        for keyframe in &mut redaction.keyframes {
+            keyframe.time += offset;
+        }
+        redaction
+    }
+}
+
+/// Copies of the regions overlapping source seconds `start`..`end`, for a piece cut
+/// from a clip. Each copy gets its own id so editing one piece leaves the others alone.
+pub fn piece_redactions(redactions: &[Redaction], start: f64, end: f64) -> Vec<Redaction> {
+    redactions
+        .iter()
+        .filter(|r| r.end > start && r.start < end)
+        .map(|r| Redaction { id: new_id(), ..r.clone() })
+        .collect()
+}
+
+fn new_id() -> String {
+    let bytes: [u8; 4] = rand::thread_rng().gen();
+    format!("redaction-{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+// Source seconds -> timeline seconds for a clip
+fn timeline_offset(clip: &VideoClip) -> f64 {
+    clip.start_time - clip.source_offset
+}
+
+/// Every redaction in the project with timeline times, in timeline order. Ids are
+/// unique within a clip; edits name the clip too.
+#[command]
+pub fn list_redactions(project: Project) -> Result<Vec<TimelineRedaction>, String> {
+    let mut listed: Vec<TimelineRedaction> = project
+        .clips
+        .iter()
+        .enumerate()
+        .flat_map(|(clip_index, clip)| {
+            clip.redactions.iter().map(move |r| TimelineRedaction {
+                clip_index,
+                redaction: r.shifted(timeline_offset(clip)),
+            })
+        })
+        .collect();
+    listed.sort_by(|a, b| a.redaction.start.partial_cmp(&b.redaction.start).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(listed)
+}
+
+/// Add a redaction (times on the timeline) to a screen clip, or replace the clip's one
+/// with the same id. Returns the updated project for saving.
+#[command]
+pub fn save_redaction(project: Project, clip_index: usize, redaction: Redaction) -> Result<Project, String> {
+    let mut project = project;
+    let clip = project
+        .clips
+        .get(clip_index)
+        .ok_or_else(|| format!("No clip at index {}", clip_index))?;
+    if clip.clip_type != "screen" {
+        return Err(format!("Redactions apply to screen clips, not {}", clip.clip_type));
+    }
+
+    let mut redaction = redaction.shifted(-timeline_offset(clip));
+    if redaction.id.is_empty() {
+        redaction.id = new_id();
+    }
+    redaction.keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(std::cmp::Ordering::Equal));
+    redaction.validate()?;
+
+    let redactions = &mut project.clips[clip_index].redactions;
+    redactions.retain(|r| r.id != redaction.id);
+    redactions.push(redaction);
+    redactions.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(project)
+}
+
+/// Remove a redaction from the clip at `clip_index`. Returns the updated project.
+#[command]
+pub fn remove_redaction(project: Project, clip_index: usize, id: String) -> Result<Project, String> {
+    let mut project = project;
+    let clip = project
+        .clips
+        .get_mut(clip_index)
+        .ok_or_else(|| format!("No clip at index {}", clip_index))?;
+    let before = clip.redactions.len();
+    clip.redactions.retain(|r| r.id != id);
+    if clip.redactions.len() == before {
+        return Err(format!("Redaction not found: {}", id));
+    }
+    Ok(project)
+}
+
+/// Whether export has any region to hide in the clip
+pub fn has_redactions(clip: &VideoClip) -> bool {
+    clip.clip_type == "screen" && !clip.redactions.is_empty()
+}
+
+/// Filters from [input] to [output] hiding the clip's redaction regions, or None when
+/// none of them fall inside it. The clip's input must start at its in-point
+/// (setpts=PTS-STARTPTS).
+pub fn redaction_filter(clip: &VideoClip, input: &str, output: &str) -> Result<Option<String>, String> {
+    if !has_redactions(clip) {
+        return Ok(None);
+    }
+    // Clip-relative times, dropping regions outside the clip
+    let regions: Vec<Redaction> = clip
+        .redactions
+        .iter()
+        .map(|r| r.shifted(-clip.source_offset))
+        .filter(|r| r.end > 0.0 && r.start < clip.duration)
+        .collect();
+    if regions.is_empty() {
+        return Ok(None);
+    }
+
+    let (width, height) = ffmpeg::video_dimensions(&clip.path)?;
+    let mut filters = Vec::new();
+    let mut current = input.to_string();
+    for (i, region) in regions.iter().enumerate() {
+        region.validate()?;
+        let next = if i + 1 == regions.len() { output.to_string() } else { format!("{}_r{}", output, i) };
+        filters.push(region_filter(region, &current, &next, &format!("{}_r{}", output, i), width, height));
+        current = next;
+    }
+    Ok(Some(filters.join(";")))
+}
+
+// One region from [input] to [output]; `prefix` names its intermediate labels
+fn region_filter(region: &Redaction, input: &str, output: &str, prefix: &str, width: u32, height: u32) -> String {
+    // Even sizes keep yuv420p chroma aligned
+    let w = ((region.rect.width * width as f64) as u32 / 2 * 2).max(2);
+    let h = ((region.rect.height * height as f64) as u32 / 2 * 2).max(2);
+    let x = format!("clip(({})*{},0,{})", position_expr(region, |k| k.x, region.rect.x), width, width.saturating_sub(w));
+    let y = format!("clip(({})*{},0,{})", position_expr(region, |k| k.y, region.rect.y), height, height.saturating_sub(h));
+    let enable = format!("between(t,{:.3},{:.3})", region.start, region.end);
+
+    let effect = match &region.style {
+        RedactionStyle::Fill { color } => {
+            return format!(
+                "[{}]drawbox=x='{}':y='{}':w={}:h={}:color={}:t=fill:enable='{}'[{}]",
+                input, x, y, w, h, color, enable, output
+            );
+        }
+        RedactionStyle::Blur { sigma } => format!("gblur=sigma={:.1}", sigma),
+        // Shrink, then blow back up without smoothing
+        RedactionStyle::Pixelate { block_size } => format!(
+            "scale={}:{},scale={}:{}:flags=neighbor",
+            (w / block_size).max(1),
+            (h / block_size).max(1),
+            w,
+            h
+        ),
+    };
+    format!(
+        "[{input}]split[{p}_main][{p}_src];[{p}_src]crop=w={w}:h={h}:x='{x}':y='{y}',{effect}[{p}_patch];[{p}_main][{p}_patch]overlay=x='{x}':y='{y}':enable='{enable}'[{output}]",
+        input = input,
+        p = prefix,
+        w = w,
+        h = h,
+        x = x,
+        y = y,
+        effect = effect,
+        enable = enable,
+        output = output
+    )
+}
+
+// Fraction of the frame for one coordinate over time: the rect's value without
+// keyframes, otherwise linear between them and held before the first and after the last
+fn position_expr(region: &Redaction, value: impl Fn(&RedactionKeyframe) -> f64, fixed: f64) -> String {
+    let Some(first) = region.keyframes.first() else {
+        return format!("{:.4}", fixed);
+    };
+    let mut expr = format!("{:.4}", value(first));
+    for pair in region.keyframes.windows(2) {
+        let delta = value(&pair[1]) - value(&pair[0]);
+        let span = pair[1].time - pair[0].time;
+        if delta.abs() < 1e-4 || span <= 0.0 {
+            continue;
+        }
+        expr.push_str(&format!("{:+.4}*clip((t-{:.3})/{:.3},0,1)", delta, pair[0].time, span));
+    }
+    expr
+}
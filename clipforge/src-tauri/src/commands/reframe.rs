@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+
+// Output geometry for exports: how big the frame is, what shape it is and how
+// the source picture is placed inside it
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NamedResolution {
+    #[serde(rename = "source")]
+    Source,
+    #[serde(rename = "2160p", alias = "4k")]
+    P2160,
+    #[serde(rename = "1440p")]
+    P1440,
+    #[serde(rename = "1080p")]
+    P1080,
+    #[serde(rename = "720p")]
+    P720,
+    #[serde(rename = "480p", alias = "420p")]
+    P480,
+    #[serde(rename = "360p")]
+    P360,
+}
+
+/// Either one of the named sizes ("1080p", "source", ...) or an explicit width and height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Resolution {
+    Named(NamedResolution),
+    Custom { width: u32, height: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AspectRatio {
+    #[serde(rename = "16:9")]
+    Landscape,
+    #[serde(rename = "9:16")]
+    Vertical,
+    #[serde(rename = "1:1")]
+    Square,
+    #[serde(rename = "4:5")]
+    Portrait,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FitMode {
+    Fit,       // scale to fit inside the frame; output shrinks to the picture
+    Fill,      // scale to cover the frame and crop around the focus point
+    #[default]
+    Letterbox, // scale to fit and pad the rest with the background color
+}
+
+/// Where to keep the picture when cropping, as fractions of the source (0.5, 0.5 is the center)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FocusPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutputFrame {
+    pub resolution: Resolution,
+    pub aspect: Option<AspectRatio>,
+    pub fit: FitMode,
+    pub focus: FocusPoint,
+    pub background: String, // ffmpeg color for letterbox bars
+}
+
+const MAX_DIMENSION: u32 = 7680;
+
+impl Default for FocusPoint {
+    fn default() -> Self {
+        FocusPoint { x: 0.5, y: 0.5 }
+    }
+}
+
+impl NamedResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NamedResolution::Source => "source",
+            NamedResolution::P2160 => "2160p",
+            NamedResolution::P1440 => "1440p",
+            NamedResolution::P1080 => "1080p",
+            NamedResolution::P720 => "720p",
+            NamedResolution::P480 => "480p",
+            NamedResolution::P360 => "360p",
+        }
+    }
+
+    // Length of the short side; None keeps the source size
+    fn short_side(&self) -> Option<u32> {
+        match self {
+            NamedResolution::Source => None,
+            NamedResolution::P2160 => Some(2160),
+            NamedResolution::P1440 => Some(1440),
+            NamedResolution::P1080 => Some(1080),
+            NamedResolution::P720 => Some(720),
+            NamedResolution::P480 => Some(480),
+            NamedResolution::P360 => Some(360),
+        }
+    }
+}
+
+impl AspectRatio {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AspectRatio::Landscape => "16:9",
+            AspectRatio::Vertical => "9:16",
+            AspectRatio::Square => "1:1",
+            AspectRatio::Portrait => "4:5",
+        }
+    }
+
+    fn ratio(&self) -> (u32, u32) {
+        match self {
+            AspectRatio::Landscape => (16, 9),
+            AspectRatio::Vertical => (9, 16),
+            AspectRatio::Square => (1, 1),
+            AspectRatio::Portrait => (4, 5),
+        }
+    }
+}
+
+impl OutputFrame {
+    pub fn new(
+        resolution: Resolution,
+        aspect: Option<AspectRatio>,
+        fit: Option<FitMode>,
+        focus: Option<FocusPoint>,
+        background: Option<String>,
+    ) -> Result<Self, String> {
+        if let Resolution::Custom { width, height } = resolution {
+            if !(16..=MAX_DIMENSION).contains(&width) || !(16..=MAX_DIMENSION).contains(&height) {
+                return Err(format!(
+                    "Custom resolution {}x{} is out of range (16-{} per side)",
+                    width, height, MAX_DIMENSION
+                ));
+            }
+            if width % 2 != 0 || height % 2 != 0 {
+                return Err(format!("Custom resolution {}x{} must use even dimensions", width, height));
+            }
+            if aspect.is_some() {
+                return Err("An aspect preset can't be combined with a custom width and height".to_string());
+            }
+        }
+
+        let focus = focus.unwrap_or_default();
+        if !(0.0..=1.0).contains(&focus.x) || !(0.0..=1.0).contains(&focus.y) {
+            return Err(format!("Focus point ({}, {}) must be within 0-1", focus.x, focus.y));
+        }
+
+        let background = background.unwrap_or_else(|| "black".to_string());
+        let valid_color = background.chars().all(|c| c.is_ascii_alphanumeric() || c == '#')
+            && !background.is_empty();
+        if !valid_color {
+            return Err(format!("Invalid background color: {}", background));
+        }
+
+        Ok(OutputFrame {
+            resolution,
+            aspect,
+            fit: fit.unwrap_or_default(),
+            focus,
+            background,
+        })
+    }
+
+    /// True when the frame just scales the source, which is all the native exporter can do
+    pub fn is_plain_scale(&self) -> bool {
+        self.aspect.is_none() && matches!(self.resolution, Resolution::Named(_))
+    }
+
+    /// Short name for file names, e.g. "1080p", "1080p_9x16" or "1280x720"
+    pub fn label(&self) -> String {
+        let label = match self.resolution {
+            Resolution::Named(named) => named.as_str().to_string(),
+            Resolution::Custom { width, height } => format!("{}x{}", width, height),
+        };
+        match self.aspect {
+            Some(aspect) => format!("{}_{}", label, aspect.as_str().replace(':', "x")),
+            None => label,
+        }
+    }
+
+    /// Width and height of the output frame for a source of the given size
+    pub fn target_size(&self, source_width: u32, source_height: u32) -> (u32, u32) {
+        let (width, height) = match (self.resolution, self.aspect) {
+            (Resolution::Custom { width, height }, _) => (width, height),
+            (Resolution::Named(named), None) => match named.short_side() {
+                // Named sizes without an aspect preset keep the source shape with that
+                // short side, so a portrait 1080p stays 1080 wide
+                Some(short) => {
+                    let long = |long_side: u32, short_side: u32| {
+                        (short as f64 * long_side as f64 / short_side.max(1) as f64).round() as u32
+                    };
+                    if source_width >= source_height {
+                        (long(source_width, source_height), short)
+                    } else {
+                        (short, long(source_height, source_width))
+                    }
+                }
+                None => (source_width, source_height),
+            },
+            (Resolution::Named(named), Some(aspect)) => {
+                let short = named.short_side().unwrap_or(source_width.min(source_height));
+                let (aw, ah) = aspect.ratio();
+                if aw >= ah {
+                    (short * aw / ah, short)
+                } else {
+                    (short, short * ah / aw)
+                }
+            }
+        };
+
+        (even(width), even(height))
+    }
+
+    /// ffmpeg filter chain taking [input] to [output] at the target size
+    pub fn filter(&self, input: &str, output: &str, source_width: u32, source_height: u32) -> String {
+        let (width, height) = self.target_size(source_width, source_height);
+
+        let chain = match self.fit {
+            FitMode::Fit => format!(
+                "scale={}:{}:force_original_aspect_ratio=decrease:force_divisible_by=2:flags=lanczos,setsar=1",
+                width, height
+            ),
+            FitMode::Letterbox => format!(
+                "scale={w}:{h}:force_original_aspect_ratio=decrease:force_divisible_by=2:flags=lanczos,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:color={bg},setsar=1",
+                w = width,
+                h = height,
+                bg = self.background
+            ),
+            // Scale to cover, then slide the crop window toward the focus point
+            FitMode::Fill => format!(
+                "scale={w}:{h}:force_original_aspect_ratio=increase:flags=lanczos,crop={w}:{h}:(iw-{w})*{fx}:(ih-{h})*{fy},setsar=1",
+                w = width,
+                h = height,
+                fx = self.focus.x,
+                fy = self.focus.y
+            ),
+        };
+
+        format!("[{}]{}[{}]", input, chain, output)
+    }
+}
+
+fn even(value: u32) -> u32 {
+    (value / 2 * 2).max(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(resolution: NamedResolution, aspect: Option<AspectRatio>) -> OutputFrame {
+        OutputFrame::new(Resolution::Named(resolution), aspect, None, None, None).unwrap()
+    }
+
+    #[test]
+    fn named_size_is_the_short_side_of_the_source_shape() {
+        let p720 = frame(NamedResolution::P720, None);
+        assert_eq!(p720.target_size(1920, 1080), (1280, 720));
+        assert_eq!(p720.target_size(1080, 1920), (720, 1280));
+        assert_eq!(p720.target_size(1000, 1000), (720, 720));
+        // 2560x1080 at 720p is 1706.67 wide, rounded and then made even
+        assert_eq!(p720.target_size(2560, 1080), (1706, 720));
+    }
+
+    #[test]
+    fn source_keeps_the_source_size() {
+        let source = frame(NamedResolution::Source, None);
+        assert_eq!(source.target_size(1920, 1080), (1920, 1080));
+        assert_eq!(source.target_size(1081, 1921), (1080, 1920));
+    }
+
+    #[test]
+    fn aspect_preset_sets_the_shape_whatever_the_source() {
+        let vertical = frame(NamedResolution::P1080, Some(AspectRatio::Vertical));
+        assert_eq!(vertical.target_size(1920, 1080), (1080, 1920));
+        assert_eq!(vertical.target_size(1080, 1920), (1080, 1920));
+
+        let landscape = frame(NamedResolution::P720, Some(AspectRatio::Landscape));
+        assert_eq!(landscape.target_size(1080, 1920), (1280, 720));
+
+        let portrait = frame(NamedResolution::P1080, Some(AspectRatio::Portrait));
+        assert_eq!(portrait.target_size(1920, 1080), (1080, 1350));
+
+        // With the source size, the short side comes from the source
+        let square = frame(NamedResolution::Source, Some(AspectRatio::Square));
+        assert_eq!(square.target_size(1280, 720), (720, 720));
+        assert_eq!(square.target_size(720, 1280), (720, 720));
+    }
+
+    #[test]
+    fn custom_size_ignores_the_source() {
+        let custom = OutputFrame::new(Resolution::Custom { width: 1280, height: 720 }, None, None, None, None).unwrap();
+        assert_eq!(custom.target_size(1080, 1920), (1280, 720));
+        assert!(OutputFrame::new(Resolution::Custom { width: 1281, height: 720 }, None, None, None, None).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+
+use crate::commands::recorder::{self, RecorderState, RecordingLimits};
+use crate::commands::screen_capture::{self, RecordingRequest};
+
+// Recordings that start at a wall-clock time. The schedule lives in the recorder
+// state machine (Scheduled), so it shows up in the tray and control API and is
+// cancelled with cancel_pending_recording like a countdown.
+
+/// Schedule a native recording to start at `starts_at` (RFC 3339), with an optional
+/// countdown before it and limits that stop it automatically
+#[command]
+pub fn schedule_recording(
+    app: AppHandle,
+    request: RecordingRequest,
+    starts_at: String,
+    countdown: Option<u32>,
+    limits: Option<RecordingLimits>,
+) -> Result<RecorderState, String> {
+    let start = chrono::DateTime::parse_from_rfc3339(&starts_at)
+        .map_err(|e| format!("Invalid start time '{}': {}", starts_at, e))?
+        .with_timezone(&chrono::Utc);
+    if start <= chrono::Utc::now() {
+        return Err("Scheduled start time is in the past".to_string());
+    }
+
+    let scheduled = RecorderState::Scheduled {
+        output_path: request.output_path.clone(),
+        starts_at: start.to_rfc3339(),
+        countdown,
+        limits: limits.unwrap_or_default(),
+    };
+    recorder::transition_from(&app, &RecorderState::Idle, scheduled.clone())?;
+    println!("📅 Recording scheduled for {}: {}", start.to_rfc3339(), request.output_path);
+
+    let task_state = scheduled.clone();
+    tauri::async_runtime::spawn(async move {
+        run_schedule(app, request, start, task_state).await;
+    });
+    Ok(scheduled)
+}
+
+// Wait for the start time (minus the countdown), then start unless the schedule was cancelled
+async fn run_schedule(app: AppHandle, request: RecordingRequest, start: chrono::DateTime<chrono::Utc>, scheduled: RecorderState) {
+    let RecorderState::Scheduled { countdown, limits, .. } = scheduled.clone() else {
+        return;
+    };
+    // The countdown ends at the scheduled time rather than pushing the start back
+    let fire_at = start - chrono::Duration::seconds(countdown.unwrap_or(0) as i64);
+
+    loop {
+        if recorder::current_state().ok().as_ref() != Some(&scheduled) {
+            return; // cancelled
+        }
+        let remaining = (fire_at - chrono::Utc::now()).num_milliseconds();
+        if remaining <= 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(remaining.min(1000) as u64)).await;
+    }
+
+    println!("📅 Scheduled recording due: {}", request.output_path);
+    if let Err(e) = screen_capture::start_recording(&app, request, countdown, limits, scheduled).await {
+        println!("❌ Scheduled recording failed to start: {}", e);
+        let _ = app.emit("scheduled-recording-failed", e);
+    }
+}
@@ -1,18 +1,13 @@
-use tauri::{command, AppHandle};
-use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
 
-// Recording state management
-pub struct RecordingState {
-    pub is_recording: bool,
-    pub output_path: Option<String>,
-}
-
-lazy_static::lazy_static! {
-    static ref RECORDING_STATE: Arc<Mutex<RecordingState>> = Arc::new(Mutex::new(RecordingState {
-        is_recording: false,
-        output_path: None,
-    }));
-}
+use crate::commands::audio_devices::{self, AudioSource};
+use crate::commands::audio_meter;
+use crate::commands::input_events;
+use crate::commands::iso_recording;
+use crate::commands::recorder::{self, RecorderState, RecordingLimits, RecordingSettings};
+use crate::commands::video_devices::WebcamSelection;
 
 /// Get available screens/displays for recording
 #[command]
@@ -28,93 +23,251 @@ pub async fn get_available_screens() -> Result<Vec<ScreenInfo>, String> {
     }
 }
 
-/// Start native screen recording
+/// Start native screen recording, optionally after a countdown of `countdown` seconds
+/// and stopping itself once `limits` are reached. `settings` picks the frame rate,
+/// cursor and which input is logged for export. `audio_sources` picks the microphone
+/// in place of `include_audio`; see `native_audio_device` for what it accepts. The
+/// AVFoundation recorder can't pick a camera, so a `webcam` selection is rejected;
+/// start_iso_recording records the chosen one.
 #[command]
 pub async fn start_screen_recording(
-    _app: AppHandle,
+    app: AppHandle,
     screen_id: String,
     include_audio: bool,
+    audio_sources: Option<Vec<AudioSource>>,
     include_webcam: bool,
+    webcam: Option<WebcamSelection>,
     pip_shape: Option<i32>,
     pip_x: Option<f32>,
     pip_y: Option<f32>,
     pip_size: Option<f32>,
     output_path: String,
+    countdown: Option<u32>,
+    limits: Option<RecordingLimits>,
+    settings: Option<RecordingSettings>,
 ) -> Result<String, String> {
-    // Check state first, then release mutex before await
-    {
-        let state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
-        if state.is_recording {
-            return Err("Recording already in progress".to_string());
-        }
+    let request = RecordingRequest {
+        screen_id,
+        include_audio,
+        audio_sources: audio_sources.unwrap_or_default(),
+        include_webcam,
+        webcam,
+        pip_shape,
+        pip_x,
+        pip_y,
+        pip_size,
+        output_path,
+        settings: settings.unwrap_or_default(),
+    };
+    start_recording(&app, request, countdown, limits.unwrap_or_default(), RecorderState::Idle).await
+}
+
+/// Everything needed to start a capture; kept so a scheduled recording can start later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingRequest {
+    pub screen_id: String,
+    #[serde(default)]
+    pub include_audio: bool,
+    #[serde(default)]
+    pub audio_sources: Vec<AudioSource>, // overrides include_audio when given
+    #[serde(default)]
+    pub include_webcam: bool,
+    #[serde(default)]
+    pub webcam: Option<WebcamSelection>,
+    pub pip_shape: Option<i32>,
+    pub pip_x: Option<f32>,
+    pub pip_y: Option<f32>,
+    pub pip_size: Option<f32>,
+    pub output_path: String,
+    #[serde(default)]
+    pub settings: RecordingSettings,
+}
+
+/// Start a capture from `from` (idle, or the schedule that is now due)
+pub async fn start_recording(
+    app: &AppHandle,
+    request: RecordingRequest,
+    countdown: Option<u32>,
+    limits: RecordingLimits,
+    from: RecorderState,
+) -> Result<String, String> {
+    let RecordingRequest { screen_id, include_audio, audio_sources, include_webcam, webcam, pip_shape, pip_x, pip_y, pip_size, output_path, settings } = request;
+    settings.validate()?;
+    // AVFoundation draws clicks into the capture itself
+    input_events::check_settings(&settings, None, cfg!(target_os = "macos"))?;
+    if webcam.is_some() {
+        return Err("Native recording can't pick a camera; use an ISO recording to record a chosen webcam".to_string());
     }
+    audio_devices::validate_sources(&audio_sources)?;
+    let audio_device = native_audio_device(include_audio, &audio_sources)?;
+
+    let from = match countdown.filter(|seconds| *seconds > 0) {
+        Some(seconds) => recorder::run_countdown(app, &from, &output_path, seconds).await?,
+        None => from,
+    };
+    // Claim the recorder first so a second start is rejected while this one is in flight
+    recorder::transition_from(app, &from, RecorderState::Starting { output_path: output_path.clone() })?;
     
-    // Perform the recording start (releases mutex before await)
     #[cfg(target_os = "macos")]
-    {
-        macos::start_recording(
-            screen_id, 
-            include_audio,
-            include_webcam,
-            pip_shape.unwrap_or(0),
-            pip_x.unwrap_or(0.8),
-            pip_y.unwrap_or(0.8),
-            pip_size.unwrap_or(0.2),
-            output_path.clone()
-        ).await?;
-    }
+    let started = macos::start_recording(
+        screen_id, 
+        audio_device,
+        include_webcam,
+        pip_shape.unwrap_or(0),
+        pip_x.unwrap_or(0.8),
+        pip_y.unwrap_or(0.8),
+        pip_size.unwrap_or(0.2),
+        &settings,
+        output_path.clone()
+    ).await;
     
     #[cfg(not(target_os = "macos"))]
-    {
-        return Err("Screen capture not implemented for this platform yet".to_string());
+    let started: Result<(), String> = {
+        let _ = (screen_id, audio_device, include_webcam, pip_shape, pip_x, pip_y, pip_size);
+        Err("Screen capture not implemented for this platform yet".to_string())
+    };
+    
+    if let Err(e) = started {
+        recorder::transition(app, RecorderState::Idle)?;
+        return Err(e);
     }
     
-    // Update state after recording started
-    {
-        let mut state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
-        state.is_recording = true;
-        state.output_path = Some(output_path.clone());
+    recorder::transition(app, RecorderState::Recording {
+        output_path: output_path.clone(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        resumed_at: None,
+        elapsed_before: 0.0,
+    })?;
+    input_events::start_logging(&output_path, None, &settings, cfg!(target_os = "macos"));
+    if audio_device.is_some() {
+        let metered = if audio_sources.is_empty() {
+            vec![AudioSource { device_id: "default".to_string(), gain_db: 0.0 }]
+        } else {
+            audio_sources
+        };
+        audio_meter::start_recording_meter(app.clone(), &metered);
     }
     
-    // Note: Menu item enabling disabled for now due to type complexity
-    // Users can still stop recording via global shortcut (Cmd/Ctrl+Shift+S) or tray menu
-    // The stop button will work if clicked, but may appear disabled in some cases
+    if !limits.is_empty() {
+        tauri::async_runtime::spawn(watch_limits(app.clone(), output_path.clone(), limits));
+    }
     
     Ok(output_path)
 }
 
+// The AVFoundation recorder records one audio source as-is, picked by its index in
+// list_audio_devices (-1 for the default microphone). Several sources or a gain need
+// an ISO recording, so they're rejected here rather than quietly recorded without.
+fn native_audio_device(include_audio: bool, sources: &[AudioSource]) -> Result<Option<i32>, String> {
+    match sources {
+        [] => Ok(include_audio.then_some(-1)),
+        [source] if source.gain_db.abs() > f64::EPSILON => Err(format!(
+            "Native recording can't apply gain to {}; use an ISO recording for per-source gain",
+            source.device_id
+        )),
+        [source] if source.device_id == "default" => Ok(Some(-1)),
+        [source] => source
+            .device_id
+            .parse::<i32>()
+            .map(Some)
+            .map_err(|_| format!("Audio device not found: {}", source.device_id)),
+        _ => Err("Native recording takes one audio source; use an ISO recording to record several".to_string()),
+    }
+}
+
+// Poll the open recording and stop it once it runs past either limit
+async fn watch_limits(app: AppHandle, output_path: String, limits: RecordingLimits) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let Ok(state) = recorder::current_state() else {
+            return;
+        };
+        if !state.is_active() || state.output_path() != Some(output_path.as_str()) {
+            return; // stopped some other way
+        }
+        
+        let size_mb = std::fs::metadata(&output_path).map(|m| m.len() as f64 / 1_048_576.0).unwrap_or(0.0);
+        let reason = match (limits.max_duration_secs, limits.max_file_size_mb) {
+            (Some(max), _) if state.elapsed_seconds() >= max => "duration",
+            (_, Some(max)) if size_mb >= max => "file_size",
+            _ => continue,
+        };
+        
+        println!("⏱️ Recording limit reached ({}), stopping: {}", reason, output_path);
+        let _ = app.emit("recording-limit-reached", serde_json::json!({ "output_path": output_path, "reason": reason }));
+        if let Err(e) = stop_screen_recording(app.clone()).await {
+            println!("❌ Failed to stop recording at limit: {}", e);
+        }
+        return;
+    }
+}
+
 /// Stop native screen recording
 #[command]
-pub async fn stop_screen_recording(_app: AppHandle) -> Result<String, String> {
-    // Check state first, then release mutex before await
-    {
-        let state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
-        if !state.is_recording {
-            return Err("No recording in progress".to_string());
-        }
+pub async fn stop_screen_recording(app: AppHandle) -> Result<String, String> {
+    let output_path = match recorder::current_state()? {
+        RecorderState::Recording { output_path, .. } | RecorderState::Paused { output_path, .. } => output_path,
+        RecorderState::Idle => return Err("No recording in progress".to_string()),
+        other => return Err(format!("Recorder is {}, not recording", other.name())),
+    };
+    recorder::transition(&app, RecorderState::Stopping { output_path: output_path.clone() })?;
+    
+    let stopped = if iso_recording::is_running() {
+        tauri::async_runtime::spawn_blocking(iso_recording::stop_capture)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.map(|_| ()))
+    } else {
+        stop_native_capture().await
+    };
+    if let Err(e) = input_events::finish_logging() {
+        println!("⚠️ {}", e);
     }
+    audio_meter::stop_recording_meter();
     
-    // Perform the recording stop (releases mutex before await)
+    // The capture is over either way; a failed stop still returns the recorder to idle
+    recorder::transition(&app, RecorderState::Idle)?;
+    stopped?;
+    
+    // Note: Menu item disabling handled automatically when recording stops
+    
+    // However it was stopped (shortcut, tray, limit, control API), the frontend picks up the file from here
+    let _ = app.emit("recording-stopped", serde_json::json!({ "output_path": output_path }));
+    Ok(output_path)
+}
+
+async fn stop_native_capture() -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
-        macos::stop_recording().await?;
+        macos::stop_recording().await
     }
     
     #[cfg(not(target_os = "macos"))]
     {
-        return Err("Screen capture not implemented for this platform yet".to_string());
+        Err("Screen capture not implemented for this platform yet".to_string())
     }
-    
-    // Update state after recording stopped
-    let output_path = {
-        let mut state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
-        state.is_recording = false;
-        state.output_path.take().unwrap_or_default()
-    };
-    
-    // Note: Menu item disabling handled automatically when recording stops
-    
-    Ok(output_path)
+}
+
+/// Pause or resume the capture behind the open recording. ISO bundles can't pause
+/// (their tracks would drift apart); a capture the frontend runs pauses itself.
+pub fn set_capture_paused(paused: bool) -> Result<(), String> {
+    if iso_recording::is_running() {
+        return Err("ISO recordings can't be paused".to_string());
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if macos::check_is_recording() {
+            return macos::set_paused(paused);
+        }
+    }
+    let _ = paused;
+    Ok(())
+}
+
+/// Whether a capture run from Rust (native or ISO) is open, so it can be stopped
+/// without the frontend
+pub fn has_backend_capture() -> bool {
+    iso_recording::is_running() || (cfg!(target_os = "macos") && is_recording().unwrap_or(false))
 }
 
 /// Check if recording is in progress
@@ -122,13 +275,12 @@ pub async fn stop_screen_recording(_app: AppHandle) -> Result<String, String> {
 pub fn is_recording() -> Result<bool, String> {
     #[cfg(target_os = "macos")]
     {
-        Ok(macos::check_is_recording())
+        Ok(macos::check_is_recording() || iso_recording::is_running())
     }
     
     #[cfg(not(target_os = "macos"))]
     {
-        let state = RECORDING_STATE.lock().map_err(|e| e.to_string())?;
-        Ok(state.is_recording)
+        Ok(recorder::current_state()?.is_active())
     }
 }
 
@@ -170,22 +322,24 @@ mod macos {
     
     pub async fn start_recording(
         screen_id: String,
-        include_audio: bool,
+        audio_device: Option<i32>,
         include_webcam: bool,
         pip_shape: i32,
         pip_x: f32,
         pip_y: f32,
         pip_size: f32,
+        settings: &RecordingSettings,
         output_path: String,
     ) -> Result<(), String> {
         macos_capture::start_recording(
             screen_id, 
-            include_audio, 
+            audio_device,
             include_webcam,
             pip_shape,
             pip_x,
             pip_y,
             pip_size,
+            settings,
             output_path
         ).await
     }
@@ -194,6 +348,10 @@ mod macos {
         macos_capture::stop_recording().await
     }
     
+    pub fn set_paused(paused: bool) -> Result<(), String> {
+        macos_capture::set_paused(paused)
+    }
+    
     pub fn check_is_recording() -> bool {
         macos_capture::is_recording()
     }
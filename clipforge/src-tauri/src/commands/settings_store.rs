@@ -0,0 +1,23 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+// App settings that outlive a restart (shortcuts, transcription, output naming,
+// watch folders), each under its own key of the tauri-plugin-store settings file
+
+const SETTINGS_STORE: &str = "settings.json";
+
+/// The value stored under `key`, or None if it's missing or no longer parses
+pub fn load<T: DeserializeOwned>(app: &AppHandle, key: &str) -> Option<T> {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(key))
+        .and_then(|value| serde_json::from_value(value).ok())
+}
+
+pub fn save<T: Serialize>(app: &AppHandle, key: &str, value: &T) -> Result<(), String> {
+    let store = app.store(SETTINGS_STORE).map_err(|e| format!("Failed to open settings store: {}", e))?;
+    store.set(key, serde_json::to_value(value).map_err(|e| e.to_string())?);
+    store.save().map_err(|e| format!("Failed to save {}: {}", key, e))
+}
@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter, Listener};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::commands::recorder::{self, RecorderState};
+use crate::commands::settings_store;
+
+// Global shortcuts, registered from Rust so they work while the window is hidden
+// during a recording. They are only held while a recording is open (or paused), so
+// the combinations stay free for other applications the rest of the time. Bindings
+// are kept in the settings store and can be changed at runtime; a press is forwarded
+// to the frontend as a "global-shortcut" event, except that a native or ISO recording
+// is stopped here and the frontend hears about it from "recording-stopped" instead.
+
+lazy_static::lazy_static! {
+    // Registered shortcut id -> action, used by the plugin handler
+    static ref ACTIVE_SHORTCUTS: Arc<Mutex<HashMap<u32, (ShortcutAction, Shortcut)>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+static SHORTCUTS_ARMED: AtomicBool = AtomicBool::new(false);
+
+const SHORTCUTS_KEY: &str = "shortcuts";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ToggleRecording,
+    Pause, // pauses, or resumes a paused recording
+    AddMarker,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub action: ShortcutAction,
+    pub accelerator: Option<String>, // e.g. "CommandOrControl+Shift+S"; None leaves the action unbound
+    pub registered: bool,
+    pub error: Option<String>, // why registration failed, e.g. taken by another app
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ShortcutTriggered {
+    action: ShortcutAction,
+    accelerator: String,
+}
+
+const ALL_ACTIONS: [ShortcutAction; 3] = [
+    ShortcutAction::ToggleRecording,
+    ShortcutAction::Pause,
+    ShortcutAction::AddMarker,
+];
+
+impl ShortcutAction {
+    fn default_accelerator(&self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleRecording => "CommandOrControl+Shift+S",
+            ShortcutAction::Pause => "CommandOrControl+Shift+P",
+            ShortcutAction::AddMarker => "CommandOrControl+Shift+M",
+        }
+    }
+}
+
+/// Handler for the global shortcut plugin; set with `Builder::with_handler` in lib.rs
+pub fn handle_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(action) = ACTIVE_SHORTCUTS.lock().ok().and_then(|active| active.get(&shortcut.id()).map(|(action, _)| *action)) else {
+        return;
+    };
+
+    println!("⌨️ Global shortcut {} -> {:?}", shortcut, action);
+
+    // A native or ISO recording is stopped without the frontend, which may be suspended while hidden
+    if action == ShortcutAction::ToggleRecording && crate::commands::screen_capture::has_backend_capture() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::commands::screen_capture::stop_screen_recording(app).await {
+                println!("❌ Failed to stop recording from shortcut: {}", e);
+            }
+        });
+        return;
+    }
+
+    let _ = app.emit("global-shortcut", ShortcutTriggered { action, accelerator: shortcut.to_string() });
+
+    match action {
+        ShortcutAction::Pause => {
+            let toggled = match recorder::current_state() {
+                Ok(RecorderState::Paused { .. }) => recorder::resume_recording(app.clone()),
+                _ => recorder::pause_recording(app.clone()),
+            };
+            if let Err(e) = toggled {
+                println!("⚠️ Pause not toggled: {}", e);
+            }
+        }
+        ShortcutAction::AddMarker => {
+            if let Err(e) = crate::commands::markers::add_recording_marker(app.clone(), None) {
+                println!("⚠️ Marker not added: {}", e);
+            }
+        }
+        ShortcutAction::ToggleRecording => {}
+    }
+}
+
+/// Hold the stored bindings (or the defaults) while a recording is open and release
+/// them when it ends. Failures are reported per binding rather than stopping the app.
+pub fn follow_recorder(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen_any("recorder-state-changed", move |event| {
+        if let Ok(state) = serde_json::from_str::<RecorderState>(event.payload()) {
+            set_armed(&handle, state.is_active());
+        }
+    });
+}
+
+fn set_armed(app: &AppHandle, armed: bool) {
+    if SHORTCUTS_ARMED.swap(armed, Ordering::SeqCst) != armed {
+        apply_bindings(app, &load_bindings(app));
+    }
+}
+
+#[command]
+pub fn get_shortcuts(app: AppHandle) -> Result<Vec<ShortcutBinding>, String> {
+    let bindings = load_bindings(&app);
+    let active = ACTIVE_SHORTCUTS.lock().map_err(|e| e.to_string())?;
+    Ok(ALL_ACTIONS
+        .iter()
+        .map(|action| {
+            let accelerator = bindings.get(action).cloned().flatten();
+            let registered = active.values().any(|(a, _)| a == action);
+            ShortcutBinding {
+                action: *action,
+                error: match (&accelerator, registered) {
+                    (Some(_), false) if SHORTCUTS_ARMED.load(Ordering::SeqCst) => Some("Not registered".to_string()),
+                    _ => None,
+                },
+                accelerator,
+                registered,
+            }
+        })
+        .collect())
+}
+
+/// Change (or clear, with None) the binding for one action and save it.
+/// A combination already used by another action is rejected; one taken by
+/// another application leaves the previous binding in place (only noticed while
+/// recording, when the shortcuts are held).
+#[command]
+pub fn rebind_shortcut(app: AppHandle, action: ShortcutAction, accelerator: Option<String>) -> Result<Vec<ShortcutBinding>, String> {
+    let mut bindings = load_bindings(&app);
+
+    if let Some(accelerator) = &accelerator {
+        let shortcut = parse_accelerator(accelerator)?;
+        for (other, other_accelerator) in &bindings {
+            let Some(other_accelerator) = other_accelerator else {
+                continue;
+            };
+            if *other != action && parse_accelerator(other_accelerator).map(|s| s.id()) == Ok(shortcut.id()) {
+                return Err(format!("{} is already bound to {:?}", accelerator, other));
+            }
+        }
+        if app.global_shortcut().is_registered(shortcut) && !is_ours(&shortcut, action) {
+            return Err(format!("{} is already registered", accelerator));
+        }
+    }
+
+    let previous = bindings.insert(action, accelerator.clone()).flatten();
+    let results = apply_bindings(&app, &bindings);
+    let failed = results
+        .iter()
+        .find(|b| b.action == action && b.accelerator.is_some() && !b.registered)
+        .and_then(|b| b.error.clone());
+
+    if let Some(error) = failed {
+        // Taken by another application: put the old binding back
+        bindings.insert(action, previous);
+        apply_bindings(&app, &bindings);
+        return Err(format!("Couldn't register {}: {}", accelerator.unwrap_or_default(), error));
+    }
+
+    save_bindings(&app, &bindings)?;
+    Ok(results)
+}
+
+#[command]
+pub fn reset_shortcuts(app: AppHandle) -> Result<Vec<ShortcutBinding>, String> {
+    let bindings = default_bindings();
+    save_bindings(&app, &bindings)?;
+    Ok(apply_bindings(&app, &bindings))
+}
+
+fn is_ours(shortcut: &Shortcut, action: ShortcutAction) -> bool {
+    ACTIVE_SHORTCUTS
+        .lock()
+        .map(|active| active.get(&shortcut.id()).map(|(a, _)| *a) == Some(action))
+        .unwrap_or(false)
+}
+
+fn parse_accelerator(accelerator: &str) -> Result<Shortcut, String> {
+    accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", accelerator, e))
+}
+
+fn default_bindings() -> HashMap<ShortcutAction, Option<String>> {
+    ALL_ACTIONS
+        .iter()
+        .map(|action| (*action, Some(action.default_accelerator().to_string())))
+        .collect()
+}
+
+// Stored bindings over the defaults; actions missing from the store keep their default
+// and ones that no longer exist (like the old "toggle_webcam") are ignored
+fn load_bindings(app: &AppHandle) -> HashMap<ShortcutAction, Option<String>> {
+    let mut bindings = default_bindings();
+    if let Some(stored) = settings_store::load::<HashMap<String, Option<String>>>(app, SHORTCUTS_KEY) {
+        bindings.extend(stored.into_iter().filter_map(|(action, accelerator)| {
+            let action = serde_json::from_value::<ShortcutAction>(serde_json::Value::String(action)).ok()?;
+            Some((action, accelerator))
+        }));
+    }
+    bindings
+}
+
+fn save_bindings(app: &AppHandle, bindings: &HashMap<ShortcutAction, Option<String>>) -> Result<(), String> {
+    settings_store::save(app, SHORTCUTS_KEY, bindings)
+}
+
+// Replace whatever we registered before with `bindings`; outside a recording nothing
+// is registered and the bindings are only reported back
+fn apply_bindings(app: &AppHandle, bindings: &HashMap<ShortcutAction, Option<String>>) -> Vec<ShortcutBinding> {
+    let armed = SHORTCUTS_ARMED.load(Ordering::SeqCst);
+    let global_shortcut = app.global_shortcut();
+    let Ok(mut active) = ACTIVE_SHORTCUTS.lock() else {
+        return Vec::new();
+    };
+    for (_, shortcut) in active.values() {
+        let _ = global_shortcut.unregister(*shortcut);
+    }
+    active.clear();
+
+    ALL_ACTIONS
+        .iter()
+        .map(|action| {
+            let accelerator = bindings.get(action).cloned().flatten();
+            let outcome = match &accelerator {
+                None => Ok(false),
+                Some(accelerator) if !armed => parse_accelerator(accelerator).map(|_| false),
+                Some(accelerator) => parse_accelerator(accelerator).and_then(|shortcut| {
+                    if let Some((other, _)) = active.get(&shortcut.id()) {
+                        return Err(format!("Conflicts with {:?}", other));
+                    }
+                    global_shortcut
+                        .register(shortcut)
+                        .map_err(|e| format!("Already in use ({})", e))?;
+                    active.insert(shortcut.id(), (*action, shortcut));
+                    Ok(true)
+                }),
+            };
+
+            if let Err(e) = &outcome {
+                println!("⚠️ Shortcut for {:?} not registered: {}", action, e);
+            }
+            ShortcutBinding {
+                action: *action,
+                accelerator,
+                registered: outcome.as_ref().copied().unwrap_or(false),
+                error: outcome.err(),
+            }
+        })
+        .collect()
+}
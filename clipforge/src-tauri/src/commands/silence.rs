@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+use crate::commands::ffmpeg;
+use crate::commands::redaction;
+use crate::commands::video_processing::VideoClip;
+
+lazy_static::lazy_static! {
+    static ref SILENCE_START_RE: regex::Regex =
+        regex::Regex::new(r"silence_start: (-?\d+(?:\.\d+)?)").unwrap();
+    static ref SILENCE_END_RE: regex::Regex =
+        regex::Regex::new(r"silence_end: (-?\d+(?:\.\d+)?)").unwrap();
+}
+
+const DEFAULT_THRESHOLD_DB: f64 = -35.0;
+const DEFAULT_MIN_DURATION: f64 = 0.75;
+const DEFAULT_PADDING: f64 = 0.15;
+
+// Clips shorter than this after cutting are dropped instead of left as slivers
+const MIN_SEGMENT_DURATION: f64 = 0.05;
+
+/// A time range in seconds (source time for silence cuts, timeline time for captions)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SilenceAnalysis {
+    pub file_path: String,
+    pub duration: f64,
+    pub threshold_db: f64,
+    pub min_duration: f64,
+    pub silences: Vec<TimeRange>,
+    pub cuts: Vec<TimeRange>,
+}
+
+/// Analyze a file's audio for silence and propose cut ranges
+#[command]
+pub async fn detect_silence(
+    file_path: String,
+    threshold_db: Option<f64>,
+    min_duration: Option<f64>,
+    padding: Option<f64>,
+) -> Result<SilenceAnalysis, String> {
+    tauri::async_runtime::spawn_blocking(move || analyze_silence(file_path, threshold_db, min_duration, padding))
+        .await
+        .map_err(|e| format!("Silence detection task failed: {}", e))?
+}
+
+pub fn analyze_silence(
+    file_path: String,
+    threshold_db: Option<f64>,
+    min_duration: Option<f64>,
+    padding: Option<f64>,
+) -> Result<SilenceAnalysis, String> {
+    let threshold_db = threshold_db.unwrap_or(DEFAULT_THRESHOLD_DB);
+    let min_duration = min_duration.unwrap_or(DEFAULT_MIN_DURATION);
+    let padding = padding.unwrap_or(DEFAULT_PADDING).max(0.0);
+
+    if threshold_db > 0.0 {
+        return Err(format!("Silence threshold must be in dBFS (<= 0), got {}", threshold_db));
+    }
+    if min_duration <= 0.0 {
+        return Err("Minimum silence duration must be greater than zero".to_string());
+    }
+    if !std::path::Path::new(&file_path).exists() {
+        return Err(format!("File does not exist: {}", file_path));
+    }
+
+    println!("🔇 Detecting silence in {} ({} dB, >= {}s)", file_path, threshold_db, min_duration);
+
+    // silencedetect logs to stderr; the decoded audio is discarded
+    let output = ffmpeg::run_ffmpeg(&[
+        "-i".to_string(),
+        file_path.clone(),
+        "-vn".to_string(),
+        "-af".to_string(),
+        format!("silencedetect=noise={}dB:d={}", threshold_db, min_duration),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ])?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let duration = ffmpeg::parse_duration(&stderr).unwrap_or(0.0);
+    let silences = parse_silence_log(&stderr, duration);
+    let cuts = propose_cuts(&silences, padding, duration);
+
+    println!("✅ Found {} silent range(s), {} proposed cut(s)", silences.len(), cuts.len());
+
+    Ok(SilenceAnalysis {
+        file_path,
+        duration,
+        threshold_db,
+        min_duration,
+        silences,
+        cuts,
+    })
+}
+
+/// Remove the given cut ranges from a clip, returning the remaining pieces laid out back to back
+#[command]
+pub fn apply_silence_cuts(clip: VideoClip, cuts: Vec<TimeRange>) -> Result<Vec<VideoClip>, String> {
+    if clip.duration <= 0.0 {
+        return Err("Clip has no duration to trim".to_string());
+    }
+
+    let mut cuts = cuts;
+    cuts.retain(|c| c.end > c.start);
+    cuts.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let source_end = clip.source_offset + clip.duration;
+    let mut kept = Vec::new();
+    let mut cursor = clip.source_offset;
+
+    for cut in &cuts {
+        if cut.end <= cursor || cut.start >= source_end {
+            continue;
+        }
+        if cut.start > cursor {
+            kept.push(TimeRange { start: cursor, end: cut.start });
+        }
+        cursor = cursor.max(cut.end);
+    }
+    if cursor < source_end {
+        kept.push(TimeRange { start: cursor, end: source_end });
+    }
+
+    let mut timeline_position = clip.start_time;
+    let trimmed: Vec<VideoClip> = kept
+        .into_iter()
+        .filter(|range| range.end - range.start >= MIN_SEGMENT_DURATION)
+        .map(|range| {
+            let duration = range.end - range.start;
+            let piece = VideoClip {
+                path: clip.path.clone(),
+                start_time: timeline_position,
+                duration,
+                source_offset: range.start,
+                clip_type: clip.clip_type.clone(),
+                pip_config: clip.pip_config.clone(),
+                link_id: clip.link_id.clone(),
+                input_overlay: clip.input_overlay.clone(),
+                auto_zoom: clip.auto_zoom.clone(),
+                redactions: redaction::piece_redactions(&clip.redactions, range.start, range.end),
+                layer: clip.layer.clone(),
+            };
+            timeline_position += duration;
+            piece
+        })
+        .collect();
+
+    if trimmed.is_empty() {
+        return Err("Clip is entirely silent; nothing left after trimming".to_string());
+    }
+
+    Ok(trimmed)
+}
+
+// Pair up silence_start/silence_end lines. A trailing start with no end runs to the end of the file.
+fn parse_silence_log(stderr: &str, duration: f64) -> Vec<TimeRange> {
+    let mut silences = Vec::new();
+    let mut open_start: Option<f64> = None;
+
+    for line in stderr.lines() {
+        if let Some(caps) = SILENCE_START_RE.captures(line) {
+            open_start = caps[1].parse::<f64>().ok().map(|s| s.max(0.0));
+        } else if let Some(caps) = SILENCE_END_RE.captures(line) {
+            if let (Some(start), Ok(end)) = (open_start.take(), caps[1].parse::<f64>()) {
+                silences.push(TimeRange { start, end });
+            }
+        }
+    }
+
+    if let Some(start) = open_start {
+        if duration > start {
+            silences.push(TimeRange { start, end: duration });
+        }
+    }
+
+    silences
+}
+
+// Shrink each silence by the padding on both sides so cuts don't clip the surrounding speech.
+// Silence touching the start or end of the file has nothing to protect on that side.
+fn propose_cuts(silences: &[TimeRange], padding: f64, duration: f64) -> Vec<TimeRange> {
+    silences
+        .iter()
+        .map(|s| TimeRange {
+            start: if s.start <= 0.0 { 0.0 } else { s.start + padding },
+            end: if duration > 0.0 && s.end >= duration { s.end } else { s.end - padding },
+        })
+        .filter(|c| c.end - c.start > 0.0)
+        .collect()
+}
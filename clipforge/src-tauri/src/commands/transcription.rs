@@ -0,0 +1,429 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, Emitter};
+
+use crate::commands::captions::{CaptionCue, CaptionTrack};
+use crate::commands::atomic_write;
+use crate::commands::ffmpeg;
+use crate::commands::settings_store;
+
+lazy_static::lazy_static! {
+    static ref WHISPER_CONFIG: Arc<Mutex<Option<WhisperCppConfig>>> = Arc::new(Mutex::new(None));
+    static ref PROGRESS_RE: regex::Regex = regex::Regex::new(r"progress\s*=\s*(\d+)%").unwrap();
+}
+
+const WHISPER_CONFIG_KEY: &str = "transcription";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptWord {
+    pub start: f64, // seconds into the media file
+    pub end: f64,
+    pub text: String,
+    pub confidence: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    pub words: Vec<TranscriptWord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub media_path: String,
+    pub engine: String,
+    pub language: Option<String>,
+    pub created_at: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionProgress {
+    pub media_path: String,
+    pub stage: String, // "extracting_audio", "transcribing" or "done"
+    pub progress: f64, // 0.0 - 1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhisperCppConfig {
+    pub binary_path: String, // e.g. /opt/whisper.cpp/build/bin/whisper-cli
+    pub model_path: String,  // e.g. ggml-base.en.bin
+    pub threads: Option<u32>,
+}
+
+// A speech-to-text backend. Engines run fully offline on a 16 kHz mono WAV file.
+pub trait TranscriptionEngine: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn transcribe(
+        &self,
+        wav_path: &Path,
+        language: Option<&str>,
+        on_progress: &dyn Fn(f64),
+    ) -> Result<Vec<TranscriptSegment>, String>;
+}
+
+// Shells out to a local whisper.cpp build and reads its full JSON output,
+// which carries per-token timestamps that we merge into words
+pub struct WhisperCppEngine {
+    config: WhisperCppConfig,
+}
+
+impl WhisperCppEngine {
+    pub fn new(config: WhisperCppConfig) -> Result<Self, String> {
+        if !Path::new(&config.binary_path).exists() {
+            return Err(format!("whisper.cpp binary not found: {}", config.binary_path));
+        }
+        if !Path::new(&config.model_path).exists() {
+            return Err(format!("whisper.cpp model not found: {}", config.model_path));
+        }
+        Ok(WhisperCppEngine { config })
+    }
+}
+
+impl TranscriptionEngine for WhisperCppEngine {
+    fn name(&self) -> &str {
+        "whisper.cpp"
+    }
+
+    fn transcribe(
+        &self,
+        wav_path: &Path,
+        language: Option<&str>,
+        on_progress: &dyn Fn(f64),
+    ) -> Result<Vec<TranscriptSegment>, String> {
+        // whisper.cpp appends .json to the output prefix
+        let output_prefix = wav_path.with_extension("");
+        let json_path = PathBuf::from(format!("{}.json", output_prefix.display()));
+
+        let mut cmd = Command::new(&self.config.binary_path);
+        cmd.arg("-m")
+            .arg(&self.config.model_path)
+            .arg("-f")
+            .arg(wav_path)
+            .arg("-l")
+            .arg(language.unwrap_or("auto"))
+            .arg("--output-json-full")
+            .arg("--output-file")
+            .arg(&output_prefix)
+            .arg("--print-progress")
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        if let Some(threads) = self.config.threads {
+            cmd.arg("-t").arg(threads.to_string());
+        }
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to start whisper.cpp: {}", e))?;
+
+        // Progress is reported on stderr as "... progress = 42%"
+        let mut stderr_lines = Vec::new();
+        if let Some(stderr) = child.stderr.take() {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if let Some(caps) = PROGRESS_RE.captures(&line) {
+                    if let Ok(percent) = caps[1].parse::<f64>() {
+                        on_progress(percent / 100.0);
+                    }
+                }
+                stderr_lines.push(line);
+            }
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to wait for whisper.cpp: {}", e))?;
+        if !status.success() {
+            let tail = stderr_lines[stderr_lines.len().saturating_sub(3)..].join("\n");
+            return Err(format!("whisper.cpp failed: {}", tail));
+        }
+
+        let content = fs::read_to_string(&json_path)
+            .map_err(|e| format!("Failed to read whisper.cpp output: {}", e))?;
+        let _ = fs::remove_file(&json_path);
+
+        parse_whisper_json(&content)
+    }
+}
+
+/// Configure the local whisper.cpp binary and model used for transcription and save
+/// them for the next start
+#[command]
+pub fn configure_transcription(app: AppHandle, config: WhisperCppConfig) -> Result<(), String> {
+    WhisperCppEngine::new(config.clone())?;
+    settings_store::save(&app, WHISPER_CONFIG_KEY, &config)?;
+    let mut current = WHISPER_CONFIG.lock().map_err(|e| e.to_string())?;
+    *current = Some(config);
+    Ok(())
+}
+
+#[command]
+pub fn get_transcription_config() -> Result<Option<WhisperCppConfig>, String> {
+    Ok(WHISPER_CONFIG.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Load the saved whisper.cpp configuration at startup. It's checked again when a
+/// transcription starts, so a model moved since then is reported there.
+pub fn restore_config(app: &AppHandle) {
+    if let Some(config) = settings_store::load::<WhisperCppConfig>(app, WHISPER_CONFIG_KEY) {
+        if let Ok(mut current) = WHISPER_CONFIG.lock() {
+            *current = Some(config);
+        }
+    }
+}
+
+/// Transcribe a recording and store the transcript next to it
+#[command]
+pub async fn transcribe_media(
+    app: AppHandle,
+    media_path: String,
+    language: Option<String>,
+) -> Result<Transcript, String> {
+    let config = WHISPER_CONFIG
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone()
+        .ok_or("Transcription is not configured. Set the whisper.cpp binary and model first.")?;
+    let engine = WhisperCppEngine::new(config)?;
+
+    if !Path::new(&media_path).exists() {
+        return Err(format!("File does not exist: {}", media_path));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let emit_progress = |stage: &str, progress: f64| {
+            let _ = app.emit("transcription-progress", TranscriptionProgress {
+                media_path: media_path.clone(),
+                stage: stage.to_string(),
+                progress,
+            });
+        };
+
+        run_transcription(&engine, &media_path, language, &emit_progress)
+    })
+    .await
+    .map_err(|e| format!("Transcription task failed: {}", e))?
+}
+
+/// Load the stored transcript for a media file, if one exists
+#[command]
+pub fn get_transcript(media_path: String) -> Result<Option<Transcript>, String> {
+    let path = transcript_path(&media_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read transcript: {}", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Invalid transcript file {}: {}", path.display(), e))
+}
+
+/// Find words in a transcript matching the query (case-insensitive)
+#[command]
+pub fn search_transcript(transcript: Transcript, query: String) -> Result<Vec<TranscriptWord>, String> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(transcript
+        .segments
+        .iter()
+        .flat_map(|s| s.words.iter())
+        .filter(|w| w.text.to_lowercase().contains(&query))
+        .cloned()
+        .collect())
+}
+
+/// Build a caption track from a transcript, offset to where the media sits on the timeline
+#[command]
+pub fn transcript_to_captions(
+    transcript: Transcript,
+    timeline_offset: Option<f64>,
+    max_chars: Option<usize>,
+) -> Result<CaptionTrack, String> {
+    let offset = timeline_offset.unwrap_or(0.0);
+    let max_chars = max_chars.unwrap_or(42).max(1);
+    let mut cues = Vec::new();
+
+    for segment in &transcript.segments {
+        if segment.words.is_empty() {
+            if !segment.text.trim().is_empty() {
+                cues.push(CaptionCue {
+                    start: segment.start + offset,
+                    end: segment.end + offset,
+                    text: segment.text.trim().to_string(),
+                });
+            }
+            continue;
+        }
+
+        // Break long segments into caption-sized lines on word boundaries
+        let mut line: Vec<&TranscriptWord> = Vec::new();
+        for word in &segment.words {
+            let line_len: usize = line.iter().map(|w| w.text.len() + 1).sum();
+            if !line.is_empty() && line_len + word.text.len() > max_chars {
+                cues.push(words_to_cue(&line, offset));
+                line.clear();
+            }
+            line.push(word);
+        }
+        if !line.is_empty() {
+            cues.push(words_to_cue(&line, offset));
+        }
+    }
+
+    Ok(CaptionTrack {
+        id: format!("transcript_{}", chrono::Utc::now().timestamp_millis()),
+        name: "Transcript".to_string(),
+        language: transcript.language,
+        cues,
+    })
+}
+
+pub fn run_transcription(
+    engine: &dyn TranscriptionEngine,
+    media_path: &str,
+    language: Option<String>,
+    emit_progress: &dyn Fn(&str, f64),
+) -> Result<Transcript, String> {
+    println!("📝 Transcribing {} with {}", media_path, engine.name());
+    emit_progress("extracting_audio", 0.0);
+
+    // Every engine gets the same 16 kHz mono input regardless of the source container
+    let wav_path = std::env::temp_dir()
+        .join("vidveil")
+        .join(format!("transcribe_{}.wav", chrono::Utc::now().timestamp_millis()));
+    if let Some(parent) = wav_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    }
+
+    ffmpeg::run_ffmpeg(&[
+        "-y".to_string(),
+        "-i".to_string(),
+        media_path.to_string(),
+        "-vn".to_string(),
+        "-ac".to_string(),
+        "1".to_string(),
+        "-ar".to_string(),
+        "16000".to_string(),
+        "-c:a".to_string(),
+        "pcm_s16le".to_string(),
+        wav_path.to_string_lossy().to_string(),
+    ])?;
+
+    emit_progress("transcribing", 0.0);
+    let result = engine.transcribe(&wav_path, language.as_deref(), &|p| emit_progress("transcribing", p));
+    let _ = fs::remove_file(&wav_path);
+    let segments = result?;
+
+    let transcript = Transcript {
+        media_path: media_path.to_string(),
+        engine: engine.name().to_string(),
+        language,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        segments,
+    };
+
+    let content = serde_json::to_string_pretty(&transcript)
+        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
+    atomic_write::write_atomic(transcript_path(media_path), content.as_bytes())
+        .map_err(|e| format!("Failed to save transcript: {}", e))?;
+
+    emit_progress("done", 1.0);
+    println!("✅ Transcribed {} segment(s)", transcript.segments.len());
+    Ok(transcript)
+}
+
+// Transcripts live beside the media as "<file>.transcript.json"
+pub fn transcript_path(media_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.transcript.json", media_path))
+}
+
+fn words_to_cue(words: &[&TranscriptWord], offset: f64) -> CaptionCue {
+    CaptionCue {
+        start: words[0].start + offset,
+        end: words[words.len() - 1].end + offset,
+        text: words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "),
+    }
+}
+
+// whisper.cpp --output-json-full: transcription[].{offsets, text, tokens[].{text, offsets, p}}.
+// Tokens are sub-word pieces; a piece starting with a space begins a new word.
+fn parse_whisper_json(content: &str) -> Result<Vec<TranscriptSegment>, String> {
+    let json: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| format!("Invalid whisper.cpp JSON: {}", e))?;
+    let entries = json["transcription"]
+        .as_array()
+        .ok_or("whisper.cpp JSON has no transcription array")?;
+
+    let millis = |v: &serde_json::Value| v.as_f64().unwrap_or(0.0) / 1000.0;
+    let mut segments = Vec::new();
+
+    for entry in entries {
+        let mut words: Vec<TranscriptWord> = Vec::new();
+        let mut confidences: Vec<f64> = Vec::new();
+
+        for token in entry["tokens"].as_array().into_iter().flatten() {
+            let text = token["text"].as_str().unwrap_or("");
+            // Special tokens such as [_BEG_] and [_TT_150] carry no speech
+            if text.starts_with("[_") || text.trim().is_empty() {
+                continue;
+            }
+
+            let start = millis(&token["offsets"]["from"]);
+            let end = millis(&token["offsets"]["to"]);
+            let p = token["p"].as_f64();
+
+            match words.last_mut() {
+                Some(word) if !text.starts_with(' ') => {
+                    word.text.push_str(text);
+                    word.end = end;
+                    if let Some(p) = p {
+                        confidences.push(p);
+                    }
+                }
+                _ => {
+                    if let Some(word) = words.last_mut() {
+                        word.confidence = average(&confidences);
+                    }
+                    confidences = p.into_iter().collect();
+                    words.push(TranscriptWord {
+                        start,
+                        end,
+                        text: text.trim().to_string(),
+                        confidence: None,
+                    });
+                }
+            }
+        }
+        if let Some(word) = words.last_mut() {
+            word.confidence = average(&confidences);
+        }
+
+        segments.push(TranscriptSegment {
+            start: millis(&entry["offsets"]["from"]),
+            end: millis(&entry["offsets"]["to"]),
+            text: entry["text"].as_str().unwrap_or("").trim().to_string(),
+            words,
+        });
+    }
+
+    Ok(segments)
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
@@ -0,0 +1,263 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+
+use crate::commands::output_paths::{self, TemplateValues};
+use crate::commands::recorder::{self, RecorderState, RecordingLimits, RecordingSettings};
+use crate::commands::screen_capture::{self, RecordingRequest};
+
+// System tray. The menu is rebuilt from the recorder state whenever it changes,
+// and the tooltip shows the elapsed time while a recording is open.
+
+lazy_static::lazy_static! {
+    static ref RECENT_EXPORTS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref MENU_STALE: AtomicBool = AtomicBool::new(false);
+}
+
+const TRAY_ID: &str = "default";
+const MAX_RECENT_EXPORTS: usize = 5;
+const RECENT_EXPORT_PREFIX: &str = "recent_export:";
+
+pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
+    let state = recorder::current_state().unwrap_or(RecorderState::Idle);
+    let menu = build_menu(app, &state)?;
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        // Left click restores the window; the menu opens on right click
+        .show_menu_on_left_click(false)
+        .icon(app.default_window_icon().unwrap().clone())
+        .tooltip(tooltip(&state))
+        .on_menu_event(handle_menu_event)
+        .on_tray_icon_event(handle_tray_event)
+        .build(app)?;
+
+    let handle = app.clone();
+    app.listen_any("recorder-state-changed", move |_| refresh_tray(&handle));
+
+    // Tick the elapsed time and pick up new exports
+    let handle = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+        if MENU_STALE.swap(false, Ordering::SeqCst) {
+            refresh_tray(&handle);
+        } else if let Ok(state) = recorder::current_state() {
+            if matches!(state, RecorderState::Recording { .. }) {
+                if let Some(tray) = handle.tray_by_id(TRAY_ID) {
+                    let _ = tray.set_tooltip(Some(tooltip(&state)));
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Add a finished export to the tray's recent list
+pub fn remember_export(path: &str) {
+    if let Ok(mut recent) = RECENT_EXPORTS.lock() {
+        recent.retain(|p| p != path);
+        recent.insert(0, path.to_string());
+        recent.truncate(MAX_RECENT_EXPORTS);
+        MENU_STALE.store(true, Ordering::SeqCst);
+    }
+}
+
+fn refresh_tray(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = recorder::current_state().unwrap_or(RecorderState::Idle);
+    match build_menu(app, &state) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => println!("⚠️ Failed to rebuild tray menu: {}", e),
+    }
+    let _ = tray.set_tooltip(Some(tooltip(&state)));
+}
+
+fn tooltip(state: &RecorderState) -> String {
+    let elapsed = state.elapsed_seconds() as u64;
+    let clock = if elapsed >= 3600 {
+        format!("{}:{:02}:{:02}", elapsed / 3600, elapsed / 60 % 60, elapsed % 60)
+    } else {
+        format!("{:02}:{:02}", elapsed / 60, elapsed % 60)
+    };
+    match state {
+        RecorderState::Recording { .. } => format!("VidVeil - Recording {}", clock),
+        RecorderState::Paused { .. } => format!("VidVeil - Paused {}", clock),
+        RecorderState::Countdown { remaining, .. } => format!("VidVeil - Recording in {}…", remaining),
+        RecorderState::Scheduled { starts_at, .. } => {
+            let at = chrono::DateTime::parse_from_rfc3339(starts_at)
+                .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string())
+                .unwrap_or_else(|_| starts_at.clone());
+            format!("VidVeil - Recording scheduled for {}", at)
+        }
+        RecorderState::Starting { .. } => "VidVeil - Starting recording…".to_string(),
+        RecorderState::Stopping { .. } => "VidVeil - Saving recording…".to_string(),
+        RecorderState::Idle => "VidVeil - Screen Recorder".to_string(),
+    }
+}
+
+fn build_menu(app: &AppHandle, state: &RecorderState) -> tauri::Result<Menu<Wry>> {
+    let idle = matches!(state, RecorderState::Idle);
+    let recording = matches!(state, RecorderState::Recording { .. });
+    let paused = matches!(state, RecorderState::Paused { .. });
+    let pending = state.is_pending();
+
+    let show_i = MenuItem::with_id(app, "show", "Show VidVeil", true, None::<&str>)?;
+    let start_i = MenuItem::with_id(app, "start_recording", "Start Recording", idle, None::<&str>)?;
+    let pause_i = MenuItem::with_id(app, "pause_recording", "Pause Recording", recording, None::<&str>)?;
+    let resume_i = MenuItem::with_id(app, "resume_recording", "Resume Recording", paused, None::<&str>)?;
+    let stop_i = if pending {
+        MenuItem::with_id(app, "cancel_recording", "Cancel Recording", true, None::<&str>)?
+    } else {
+        MenuItem::with_id(app, "stop_recording", "Stop Recording", recording || paused, None::<&str>)?
+    };
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let recent = RECENT_EXPORTS.lock().map(|r| r.clone()).unwrap_or_default();
+    let recent_items = recent
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let name = Path::new(path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+            MenuItem::with_id(app, format!("{}{}", RECENT_EXPORT_PREFIX, i), name, true, None::<&str>)
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let recent_refs: Vec<&dyn IsMenuItem<Wry>> = recent_items.iter().map(|i| i as &dyn IsMenuItem<Wry>).collect();
+    let recent_menu = Submenu::with_items(app, "Recent Exports", !recent_items.is_empty(), &recent_refs)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &show_i,
+            &PredefinedMenuItem::separator(app)?,
+            &start_i,
+            &pause_i,
+            &resume_i,
+            &stop_i,
+            &PredefinedMenuItem::separator(app)?,
+            &recent_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &quit_i,
+        ],
+    )
+}
+
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let id = event.id.as_ref();
+    match id {
+        "show" => show_main_window(app),
+        "start_recording" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = start_from_tray(&app).await {
+                    println!("❌ Failed to start recording from tray: {}", e);
+                    show_main_window(&app);
+                }
+            });
+        }
+        "pause_recording" => {
+            if let Err(e) = recorder::pause_recording(app.clone()) {
+                println!("⚠️ Tray pause failed: {}", e);
+            }
+        }
+        "resume_recording" => {
+            if let Err(e) = recorder::resume_recording(app.clone()) {
+                println!("⚠️ Tray resume failed: {}", e);
+            }
+        }
+        "cancel_recording" => {
+            if let Err(e) = recorder::cancel_pending_recording(app.clone()) {
+                println!("⚠️ Tray cancel failed: {}", e);
+            }
+        }
+        "stop_recording" => {
+            // Native and ISO captures are stopped here and reported with "recording-stopped";
+            // a capture the frontend runs itself is asked to stop
+            if screen_capture::has_backend_capture() {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = screen_capture::stop_screen_recording(app).await {
+                        println!("❌ Failed to stop recording from tray: {}", e);
+                    }
+                });
+            } else if let Some(window) = app.get_webview_window("main") {
+                let _ = window.emit("tray-stop-recording", ());
+            }
+        }
+        "quit" => app.exit(0),
+        _ => {
+            let path = id
+                .strip_prefix(RECENT_EXPORT_PREFIX)
+                .and_then(|i| i.parse::<usize>().ok())
+                .and_then(|i| RECENT_EXPORTS.lock().ok().and_then(|r| r.get(i).cloned()));
+            if let Some(path) = path {
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = crate::commands::video_processing::reveal_file_in_finder(path).await {
+                        println!("⚠️ Failed to reveal export: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+// Record the primary screen with the default settings into the export directory,
+// the same defaults the control API uses when no output path is given
+async fn start_from_tray(app: &AppHandle) -> Result<String, String> {
+    let screens = screen_capture::get_available_screens().await?;
+    let screen = screens
+        .iter()
+        .find(|s| s.is_primary)
+        .or(screens.first())
+        .ok_or_else(|| "No screens available for recording".to_string())?;
+    let output_path = output_paths::resolve_export_path(
+        "",
+        &TemplateValues {
+            project: Some("recording".to_string()),
+            ext: "mp4".to_string(),
+            ..TemplateValues::default()
+        },
+    )?;
+
+    let request = RecordingRequest {
+        screen_id: screen.id.clone(),
+        include_audio: true,
+        audio_sources: Vec::new(),
+        include_webcam: false,
+        webcam: None,
+        pip_shape: None,
+        pip_x: None,
+        pip_y: None,
+        pip_size: None,
+        output_path,
+        settings: RecordingSettings::default(),
+    };
+    screen_capture::start_recording(app, request, None, RecordingLimits::default(), RecorderState::Idle).await
+}
+
+fn handle_tray_event(tray: &TrayIcon, event: TrayIconEvent) {
+    if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+    } = event
+    {
+        show_main_window(tray.app_handle());
+    }
+}
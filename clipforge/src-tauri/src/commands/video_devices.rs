@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use tauri::command;
+
+use crate::commands::ffmpeg;
+use crate::commands::ffmpeg_capture::{self, DeviceKind};
+
+// Cameras for the ffmpeg-backed recorders. On Linux these are V4L2 devices
+// (/dev/videoN, including v4l2loopback ones) with their formats from v4l2-ctl, or
+// ffmpeg when v4l2-ctl isn't installed. Setting VIDVEIL_FAKE_WEBCAM to a video file
+// adds it as a looping fake camera for testing without hardware.
+
+pub const FAKE_WEBCAM_ENV: &str = "VIDVEIL_FAKE_WEBCAM";
+
+// Sizes offered for devices that take any size in a range (v4l2loopback before a
+// producer attaches), besides the largest one
+const COMMON_SIZES: [(u32, u32); 4] = [(640, 480), (1280, 720), (1920, 1080), (3840, 2160)];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoFormat {
+    pub pixel_format: String, // ffmpeg name: yuyv422, mjpeg, h264, ...
+    pub width: u32,
+    pub height: u32,
+    pub frame_rates: Vec<f64>, // empty when the device doesn't say
+}
+
+// What probing a /dev/videoN node found
+enum NodeFormats {
+    Capture(Vec<VideoFormat>),
+    NotCapture, // e.g. the metadata node next to a UVC camera
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoDevice {
+    pub id: String, // what recordings pass back to select the camera
+    pub name: String,
+    pub formats: Vec<VideoFormat>, // empty when formats can't be listed (macOS, Windows)
+    pub fake: bool,
+}
+
+/// A camera picked for a recording; unset fields leave the choice to the device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebcamSelection {
+    pub device_id: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+    pub pixel_format: Option<String>,
+}
+
+#[command]
+pub fn list_video_devices() -> Result<Vec<VideoDevice>, String> {
+    let mut devices = if cfg!(target_os = "linux") {
+        list_v4l2_devices()?
+    } else if cfg!(target_os = "macos") || cfg!(target_os = "windows") {
+        ffmpeg_capture::list_ffmpeg_devices(DeviceKind::Video)?
+            .into_iter()
+            // AVFoundation lists screens as video devices too
+            .filter(|(_, name)| !name.starts_with("Capture screen"))
+            .map(|(id, name)| VideoDevice { id, name, formats: Vec::new(), fake: false })
+            .collect()
+    } else {
+        return Err("Camera listing isn't supported on this platform".to_string());
+    };
+
+    if let Some(fake) = fake_device() {
+        devices.push(fake);
+    }
+    Ok(devices)
+}
+
+/// Reject a size, rate or pixel format the camera doesn't list. Devices that don't
+/// report formats are left to fail (or adapt) when the capture opens them.
+pub fn validate_selection(selection: &WebcamSelection) -> Result<(), String> {
+    let Ok(devices) = list_video_devices() else {
+        return Ok(());
+    };
+    let device = devices
+        .iter()
+        .find(|d| d.id == selection.device_id)
+        .ok_or_else(|| format!("Camera not found: {}", selection.device_id))?;
+    if device.formats.is_empty() {
+        return Ok(());
+    }
+
+    let matches = device.formats.iter().any(|f| {
+        selection.pixel_format.as_ref().is_none_or(|p| *p == f.pixel_format)
+            && selection.width.is_none_or(|w| w == f.width)
+            && selection.height.is_none_or(|h| h == f.height)
+            && selection
+                .fps
+                .is_none_or(|fps| f.frame_rates.is_empty() || f.frame_rates.iter().any(|r| (r - fps).abs() < 0.01))
+    });
+    if !matches {
+        let available: Vec<String> = device
+            .formats
+            .iter()
+            .map(|f| format!("{} {}x{}", f.pixel_format, f.width, f.height))
+            .collect();
+        return Err(format!(
+            "{} doesn't support the requested format; available: {}",
+            device.name,
+            available.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// A regular file standing in for a camera (see FAKE_WEBCAM_ENV)
+pub fn is_fake_device(device_id: &str) -> bool {
+    Path::new(device_id).is_file()
+}
+
+fn fake_device() -> Option<VideoDevice> {
+    let path = std::env::var(FAKE_WEBCAM_ENV).ok().filter(|p| is_fake_device(p))?;
+    let formats = ffmpeg::video_dimensions(&path)
+        .map(|(width, height)| {
+            vec![VideoFormat {
+                pixel_format: "yuv420p".to_string(),
+                width,
+                height,
+                frame_rates: Vec::new(),
+            }]
+        })
+        .unwrap_or_default();
+    Some(VideoDevice {
+        name: format!("Fake camera ({})", Path::new(&path).file_name()?.to_string_lossy()),
+        id: path,
+        formats,
+        fake: true,
+    })
+}
+
+// /sys/class/video4linux/videoN/name for every node; metadata nodes are skipped. Capture
+// nodes whose formats can't be listed are kept, with no formats
+fn list_v4l2_devices() -> Result<Vec<VideoDevice>, String> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/video4linux") else {
+        return Ok(Vec::new()); // no V4L2 devices at all
+    };
+    let mut nodes: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|n| n.starts_with("video"))
+        .collect();
+    nodes.sort_by_key(|n| n.trim_start_matches("video").parse::<u32>().unwrap_or(u32::MAX));
+
+    let mut devices = Vec::new();
+    for node in nodes {
+        let id = format!("/dev/{}", node);
+        let name = std::fs::read_to_string(format!("/sys/class/video4linux/{}/name", node))
+            .map(|n| n.trim().to_string())
+            .unwrap_or_else(|_| id.clone());
+        let formats = match v4l2_ctl_formats(&id).or_else(|| ffmpeg_v4l2_formats(&id)) {
+            Some(NodeFormats::Capture(formats)) => formats,
+            Some(NodeFormats::NotCapture) => continue,
+            None => Vec::new(),
+        };
+        devices.push(VideoDevice { id, name, formats, fake: false });
+    }
+    Ok(devices)
+}
+
+// `v4l2-ctl --info --list-formats-ext`:
+//   Device Caps      : 0x04200001
+//           Video Capture
+//   ...
+//   [0]: 'YUYV' (YUYV 4:2:2)
+//       Size: Discrete 640x480
+//           Interval: Discrete 0.033s (30.000 fps)
+//       Size: Stepwise 16x16 - 1920x1080 with step 1/1
+//           Interval: Continuous 0.033s - 1.000s (1.000-30.000 fps)
+// None when v4l2-ctl isn't available or fails
+fn v4l2_ctl_formats(device: &str) -> Option<NodeFormats> {
+    let output = Command::new("v4l2-ctl")
+        .args(["--info", "--list-formats-ext", "-d", device])
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !is_capture_node(&stdout) {
+        return Some(NodeFormats::NotCapture);
+    }
+
+    let mut formats: Vec<VideoFormat> = Vec::new();
+    let mut pixel_format = None;
+    // Intervals apply to every size listed for the last Size line
+    let mut size_start = 0;
+    for line in stdout.lines() {
+        let line = line.trim();
+        if let Some(fourcc) = line.split('\'').nth(1).filter(|_| line.starts_with('[')) {
+            pixel_format = Some(ffmpeg_pixel_format(fourcc));
+        } else if let Some(size) = line.strip_prefix("Size: ") {
+            size_start = formats.len();
+            let sizes = match size.split_once(' ') {
+                Some(("Discrete", size)) => parse_size(size).into_iter().collect(),
+                Some(("Stepwise" | "Continuous", range)) => range_sizes(range),
+                _ => Vec::new(),
+            };
+            if let Some(pixel_format) = &pixel_format {
+                for (width, height) in sizes {
+                    formats.push(VideoFormat { pixel_format: pixel_format.clone(), width, height, frame_rates: Vec::new() });
+                }
+            }
+        } else if line.starts_with("Interval:") {
+            // A range reads "(1.000-30.000 fps)"; keep its fastest rate
+            let fps = line
+                .split('(')
+                .nth(1)
+                .and_then(|rest| rest.split_whitespace().next())
+                .and_then(|fps| fps.rsplit('-').next())
+                .and_then(|fps| fps.parse::<f64>().ok());
+            if let Some(fps) = fps {
+                for format in &mut formats[size_start..] {
+                    format.frame_rates.push(fps);
+                }
+            }
+        }
+    }
+    Some(NodeFormats::Capture(formats))
+}
+
+// Whether the "Device Caps" block (this node, not the whole card) lists Video Capture
+fn is_capture_node(info: &str) -> bool {
+    let mut lines = info.lines().skip_while(|l| !l.trim_start().starts_with("Device Caps"));
+    lines.next();
+    lines
+        .take_while(|l| !l.contains(':'))
+        .any(|l| matches!(l.trim(), "Video Capture" | "Video Capture Multiplanar"))
+}
+
+// "16x16 - 1920x1080 with step 1/1" (Stepwise) or "16x16 - 1920x1080" (Continuous):
+// the common sizes in range plus the largest
+fn range_sizes(range: &str) -> Vec<(u32, u32)> {
+    let (bounds, step) = range.split_once(" with step ").unwrap_or((range, "1/1"));
+    let Some((min, max)) = bounds.split_once(" - ").and_then(|(min, max)| Some((parse_size(min)?, parse_size(max)?))) else {
+        return Vec::new();
+    };
+    let (step_w, step_h) = step
+        .split_once('/')
+        .and_then(|(w, h)| Some((w.trim().parse::<u32>().ok()?.max(1), h.trim().parse::<u32>().ok()?.max(1))))
+        .unwrap_or((1, 1));
+    let fits = |(w, h): (u32, u32)| {
+        (min.0..=max.0).contains(&w) && (min.1..=max.1).contains(&h) && (w - min.0) % step_w == 0 && (h - min.1) % step_h == 0
+    };
+
+    let mut sizes: Vec<(u32, u32)> = COMMON_SIZES.into_iter().filter(|size| fits(*size)).collect();
+    if !sizes.contains(&max) {
+        sizes.push(max);
+    }
+    sizes
+}
+
+// `ffmpeg -f v4l2 -list_formats all`, which has sizes but no frame rates:
+// "[video4linux2,v4l2 @ 0x..] Raw       :     yuyv422 :           YUYV 4:2:2 : 640x480 1280x720"
+fn ffmpeg_v4l2_formats(device: &str) -> Option<NodeFormats> {
+    let output = Command::new(ffmpeg::ffmpeg_binary())
+        .args(["-hide_banner", "-f", "v4l2", "-list_formats", "all", "-i", device])
+        .output()
+        .ok()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("Not a video capture device") {
+        return Some(NodeFormats::NotCapture);
+    }
+
+    let mut formats = Vec::new();
+    for line in stderr.lines() {
+        let fields: Vec<&str> = line.split(" : ").map(str::trim).collect();
+        if fields.len() < 4 || !(fields[0].ends_with("Raw") || fields[0].ends_with("Compressed")) {
+            continue;
+        }
+        for (width, height) in fields[3].split_whitespace().filter_map(parse_size) {
+            formats.push(VideoFormat { pixel_format: fields[1].to_string(), width, height, frame_rates: Vec::new() });
+        }
+    }
+    Some(NodeFormats::Capture(formats))
+}
+
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (width, height) = size.trim().split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+// V4L2 fourcc -> the name ffmpeg's -input_format expects
+fn ffmpeg_pixel_format(fourcc: &str) -> String {
+    match fourcc {
+        "YUYV" => "yuyv422",
+        "UYVY" => "uyvy422",
+        "MJPG" => "mjpeg",
+        "H264" => "h264",
+        "NV12" => "nv12",
+        "YU12" => "yuv420p",
+        "RGB3" => "rgb24",
+        "BGR3" => "bgr24",
+        "GREY" => "gray",
+        other => return other.to_lowercase(),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stepwise_range_offers_common_sizes_and_the_largest() {
+        assert_eq!(range_sizes("16x16 - 1920x1080 with step 1/1"), vec![(640, 480), (1280, 720), (1920, 1080)]);
+        assert_eq!(range_sizes("32x32 - 1000x800 with step 16/16"), vec![(640, 480), (1000, 800)]);
+        assert_eq!(range_sizes("1x1 - 8192x8192"), vec![(640, 480), (1280, 720), (1920, 1080), (3840, 2160), (8192, 8192)]);
+        assert!(range_sizes("garbage").is_empty());
+    }
+
+    #[test]
+    fn capture_node_is_read_from_device_caps() {
+        let camera = "Driver Info:\n\tCapabilities     : 0x84a00001\n\t\tVideo Capture\n\t\tMetadata Capture\n\tDevice Caps      : 0x04200001\n\t\tVideo Capture\n\t\tStreaming\nPriority: 2\n";
+        let metadata = "Driver Info:\n\tCapabilities     : 0x84a00001\n\t\tVideo Capture\n\t\tMetadata Capture\n\tDevice Caps      : 0x04a00000\n\t\tMetadata Capture\n\t\tStreaming\nPriority: 2\n";
+        assert!(is_capture_node(camera));
+        assert!(!is_capture_node(metadata));
+    }
+}
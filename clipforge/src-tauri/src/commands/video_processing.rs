@@ -2,21 +2,22 @@ use serde::{Deserialize, Serialize};
 use tauri::command;
 use std::ffi::{CString, CStr};
 use std::os::raw::{c_char, c_float};
-use std::fs::File;
-use std::io::Write;
+
+use crate::commands::atomic_write;
+use crate::commands::export_settings::{EncodeSettings, ExportSettings};
+use crate::commands::auto_zoom::AutoZoom;
+use crate::commands::ffmpeg_export;
+use crate::commands::layers::LayerConfig;
+use crate::commands::redaction::Redaction;
+use crate::commands::input_events::InputOverlay;
+use crate::commands::output_paths::{self, TemplateValues};
 
 #[command]
-pub async fn save_blob_to_temp_file(data: Vec<u8>, file_path: String) -> Result<(), String> {
-    match File::create(&file_path) {
-        Ok(mut file) => {
-            if let Err(e) = file.write_all(&data) {
-                return Err(format!("Failed to write data to file: {}", e));
-            }
-            println!("✅ Saved blob data to temporary file: {}", file_path);
-            Ok(())
-        }
-        Err(e) => Err(format!("Failed to create file {}: {}", file_path, e))
-    }
+pub async fn save_blob_to_temp_file(data: Vec<u8>, file_path: String) -> Result<String, String> {
+    crate::commands::atomic_write::write_atomic(&file_path, &data)
+        .map_err(|e| format!("Failed to write data to file: {}", e))?;
+    println!("✅ Saved blob data to temporary file: {}", file_path);
+    Ok(file_path)
 }
 
 // External functions from Objective-C
@@ -48,16 +49,28 @@ struct ExportResult {
     error_message: [c_char; 512],
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoClip {
     pub path: String,
     pub start_time: f64,
     pub duration: f64,
-    pub clip_type: String, // "screen" or "webcam"
+    #[serde(default)]
+    pub source_offset: f64, // seconds into the source file where the clip begins
+    pub clip_type: String, // "screen", "webcam", "audio", or a layer: "title", "text", "image"
     pub pip_config: Option<PipConfig>,
+    #[serde(default)]
+    pub link_id: Option<String>, // clips recorded together (an ISO bundle) move as one
+    #[serde(default)]
+    pub input_overlay: Option<InputOverlay>, // screen clips: what to draw from the recording's input log
+    #[serde(default)]
+    pub auto_zoom: Option<AutoZoom>, // screen clips: zoom toward clicks at export
+    #[serde(default)]
+    pub redactions: Vec<Redaction>, // screen clips: regions hidden at export
+    #[serde(default)]
+    pub layer: Option<LayerConfig>, // title, text and image clips
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PipConfig {
     pub x: f64,
     pub y: f64,
@@ -66,14 +79,6 @@ pub struct PipConfig {
     pub shape: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ExportSettings {
-    pub resolution: String,
-    pub quality: String,
-    pub format: String,
-    pub preset: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportProgress {
     pub progress: f64,
@@ -113,6 +118,94 @@ pub async fn export_video_native(
     clips: Vec<VideoClip>,
     output_path: String,
     settings: ExportSettings,
+) -> Result<ExportProgress, String> {
+    let encode = settings.resolve()?;
+    let absolute_output_path = output_paths::resolve_export_path(&output_path, &template_values(&clips, &output_path, &settings, &encode))?;
+
+    // AVFoundation only handles plain H.264 MP4/MOV without compositor effects; everything
+    // else goes through ffmpeg. The native exporter is polled for progress; like ffmpeg it
+    // renders to a partial file that is moved into place once captions and chapters are in.
+    // Both exporters block for the whole render, so they run off the async runtime
+    if encode.native_compatible && cfg!(target_os = "macos") && !ffmpeg_export::needs_compositor(&clips) {
+        let partial_path = atomic_write::partial_path(&absolute_output_path);
+        let exported = tauri::async_runtime::spawn_blocking({
+            let (partial_path, absolute_output_path) = (partial_path.clone(), absolute_output_path.clone());
+            move || {
+                let progress = export_with_avfoundation(clips.clone(), &partial_path, &encode)?;
+                if let Some(caption_options) = &settings.captions {
+                    crate::commands::captions::apply_captions_in_place(&partial_path, caption_options, &encode)?;
+                }
+                if settings.chapters == Some(true) {
+                    crate::commands::markers::apply_chapters(&clips, &partial_path, &absolute_output_path, encode.container)?;
+                }
+                let output_path = output_paths::commit_export(&partial_path, &absolute_output_path)?;
+                Ok((progress, output_path))
+            }
+        })
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))
+        .and_then(|r| r);
+        let (mut progress, output_path) = match exported {
+            Ok(exported) => exported,
+            Err(e) => {
+                atomic_write::discard(&partial_path);
+                return Err(e);
+            }
+        };
+        crate::commands::tray::remember_export(&output_path);
+        progress.output_path = Some(output_path);
+        return Ok(progress);
+    }
+
+    let output_path = tauri::async_runtime::spawn_blocking(move || {
+        ffmpeg_export::render_to_file(&clips, &settings, &encode, &absolute_output_path, None)
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))??;
+    crate::commands::tray::remember_export(&output_path);
+
+    Ok(ExportProgress {
+        progress: 1.0,
+        current_step: "Export complete".to_string(),
+        total_steps: 1,
+        current_step_number: 1,
+        output_path: Some(output_path),
+    })
+}
+
+// Template values for a single export. A bare output name doubles as the project
+// name; otherwise the first screen clip's file name is used.
+fn template_values(
+    clips: &[VideoClip],
+    output_path: &str,
+    settings: &ExportSettings,
+    encode: &EncodeSettings,
+) -> TemplateValues {
+    let bare_name = !output_path.trim().is_empty() && !output_path.contains('{') && !output_path.starts_with('/');
+    let project = if bare_name {
+        std::path::Path::new(output_path.trim()).file_stem().map(|s| s.to_string_lossy().to_string())
+    } else {
+        clips
+            .iter()
+            .find(|c| c.clip_type == "screen")
+            .and_then(|c| std::path::Path::new(&c.path).file_stem())
+            .map(|s| s.to_string_lossy().to_string())
+    };
+
+    TemplateValues {
+        project,
+        resolution: Some(encode.frame.label()),
+        preset: settings.export_preset.clone(),
+        ext: encode.container.extension().to_string(),
+    }
+}
+
+// Export through the Objective-C AVFoundation pipeline
+#[allow(unused_variables)]
+pub(crate) fn export_with_avfoundation(
+    clips: Vec<VideoClip>,
+    absolute_output_path: &str,
+    encode: &EncodeSettings,
 ) -> Result<ExportProgress, String> {
     #[cfg(target_os = "macos")]
     {
@@ -125,7 +218,7 @@ pub async fn export_video_native(
         
         // Sort clips by timeline order
         let mut sorted_clips = clips;
-        sorted_clips.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
+        sorted_clips.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
         
         println!("📊 Clips sorted by timeline order");
 
@@ -168,168 +261,15 @@ pub async fn export_video_native(
         let screen_path = string_to_c_string(&screen_clip.path);
         let webcam_path = webcam_clip.map(|c| string_to_c_string(&c.path));
         
-        // Sanitize filename to remove invalid characters
-        fn sanitize_filename(filename: &str) -> String {
-            filename
-                .chars()
-                .map(|c| match c {
-                    '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-                    _ => c,
-                })
-                .collect::<String>()
-                .trim()
-                .to_string()
-        }
         
-        // Ensure output path is absolute and save to Downloads folder if relative
-        let absolute_output_path = if output_path.starts_with('/') {
-            // Validate absolute path exists
-            let mut path = std::path::PathBuf::from(&output_path);
-            if let Some(parent) = path.parent() {
-                if !parent.exists() {
-                    return Err(format!("Output directory does not exist: {}", parent.display()));
-                }
-            }
-            
-            // Generate unique filename if file already exists
-            let mut counter = 1;
-            while path.exists() {
-                // Extract base name and extension
-                let file_name = path.file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("export");
-                
-                let (base_name, ext) = if let Some(dot_pos) = file_name.rfind('.') {
-                    (&file_name[..dot_pos], &file_name[dot_pos..])
-                } else {
-                    (file_name, "")
-                };
-                
-                let unique_filename = format!("{}_({}){}", base_name, counter, ext);
-                
-                if let Some(parent) = path.parent() {
-                    path = parent.join(&unique_filename);
-                } else {
-                    path = std::path::PathBuf::from(&unique_filename);
-                }
-                
-                counter += 1;
-                
-                // Safety limit to prevent infinite loop
-                if counter > 1000 {
-                    return Err(format!(
-                        "Could not find available filename after 1000 attempts. Please delete existing files."
-                    ));
-                }
-            }
-            
-            if counter > 1 {
-                println!("📁 File already exists, using unique filename: {}", path.display());
-            }
-            
-            path.to_string_lossy().to_string()
-        } else {
-            // Use Downloads folder as default save location
-            let home_dir = std::env::var("HOME")
-                .map_err(|e| format!("Failed to get HOME directory: {}", e))?;
-            
-            let downloads_dir = std::path::PathBuf::from(&home_dir).join("Downloads");
-            
-            // Ensure Downloads directory exists
-            if !downloads_dir.exists() {
-                std::fs::create_dir_all(&downloads_dir)
-                    .map_err(|e| format!("Failed to create Downloads directory: {}", e))?;
-                println!("✅ Created Downloads directory: {}", downloads_dir.display());
-            }
-            
-            // Check if Downloads directory is writable
-            let metadata = std::fs::metadata(&downloads_dir)
-                .map_err(|e| format!("Failed to access Downloads directory: {}", e))?;
-            
-            if !metadata.permissions().readonly() {
-                // Try creating a test file to verify writability
-                let test_file = downloads_dir.join(".clipforge_test_write");
-                if std::fs::write(&test_file, b"test").is_err() {
-                    return Err(format!("Downloads directory is not writable: {}", downloads_dir.display()));
-                }
-                let _ = std::fs::remove_file(&test_file);
-            } else {
-                return Err(format!("Downloads directory is read-only: {}", downloads_dir.display()));
-            }
-            
-            // Sanitize the filename and ensure it has the correct extension
-            let sanitized_name = sanitize_filename(&output_path);
-            let filename = if sanitized_name.is_empty() {
-                "clipforge-export.mp4".to_string()
-            } else {
-                sanitized_name
-            };
-            
-            // Ensure filename has proper extension based on format
-            let extension = match settings.format.as_str() {
-                "mp4" | "mpeg4" => "mp4",
-                "mov" | "quicktime" => "mov",
-                "webm" => "webm",
-                "gif" => "gif",
-                "mp3" => "mp3",
-                _ => "mp4",
-            };
-            
-            let final_filename = if filename.ends_with(&format!(".{}", extension)) {
-                filename
-            } else {
-                // Remove any existing extension and add the correct one
-                if let Some(dot_pos) = filename.rfind('.') {
-                    format!("{}.{}", &filename[..dot_pos], extension)
-                } else {
-                    format!("{}.{}", filename, extension)
-                }
-            };
-            
-            // Generate unique filename if file already exists
-            let mut downloads_path = downloads_dir.join(&final_filename);
-            let mut counter = 1;
-            
-            // Keep trying with incremented suffix until we find a filename that doesn't exist
-            while downloads_path.exists() {
-                // Extract base name and extension
-                let base_name = if let Some(dot_pos) = final_filename.rfind('.') {
-                    &final_filename[..dot_pos]
-                } else {
-                    &final_filename
-                };
-                let ext = if let Some(dot_pos) = final_filename.rfind('.') {
-                    &final_filename[dot_pos..]
-                } else {
-                    ""
-                };
-                
-                let unique_filename = format!("{}_({}){}", base_name, counter, ext);
-                downloads_path = downloads_dir.join(&unique_filename);
-                counter += 1;
-                
-                // Safety limit to prevent infinite loop
-                if counter > 1000 {
-                    return Err(format!(
-                        "Could not find available filename after 1000 attempts. Please delete existing files in {}",
-                        downloads_dir.display()
-                    ));
-                }
-            }
-            
-            if counter > 1 {
-                println!("📁 File already exists, using unique filename: {}", downloads_path.display());
-            } else {
-                println!("📁 Output will be saved to: {}", downloads_path.display());
-            }
-            downloads_path.to_string_lossy().to_string()
+        let output_path_c = string_to_c_string(absolute_output_path);
+        let format_c = string_to_c_string(encode.container.extension());
+        let quality_c = string_to_c_string(encode.quality.as_str());
+        let resolution_c = match encode.frame.resolution {
+            crate::commands::reframe::Resolution::Named(named) => string_to_c_string(named.as_str()),
+            crate::commands::reframe::Resolution::Custom { .. } => string_to_c_string("source"),
         };
         
-        let output_path_c = string_to_c_string(&absolute_output_path);
-        let format_c = string_to_c_string(&settings.format);
-        let quality_c = string_to_c_string(&settings.quality);
-        let resolution_c = string_to_c_string(&settings.resolution);
-        
         // Debug: Log the absolute output path
         println!("📁 Absolute output path: {}", absolute_output_path);
         
@@ -373,7 +313,7 @@ pub async fn export_video_native(
                 current_step: c_str_to_string(&result.current_step),
                 total_steps: 1,
                 current_step_number: 1,
-                output_path: Some(absolute_output_path),
+                output_path: Some(absolute_output_path.to_string()),
             })
         } else {
             Err(c_str_to_string(&result.error_message))
@@ -471,6 +411,19 @@ pub async fn reveal_file_in_finder(file_path: String) -> Result<(), String> {
     
     #[cfg(not(target_os = "macos"))]
     {
-        Err("Reveal in Finder is only available on macOS".to_string())
+        use std::process::Command;
+        
+        // Explorer can select the file; elsewhere the file manager opens its folder
+        let spawned = if cfg!(target_os = "windows") {
+            Command::new("explorer").arg(format!("/select,{}", file_path)).spawn()
+        } else {
+            let folder = std::path::Path::new(&file_path)
+                .parent()
+                .ok_or_else(|| format!("No folder to reveal for {}", file_path))?;
+            Command::new("xdg-open").arg(folder).spawn()
+        };
+        spawned.map_err(|e| format!("Failed to reveal file: {}", e))?;
+        
+        Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,383 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{command, AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::export_jobs::{self, BatchExportTarget, ExportJob, JobHooks, JobStatus};
+use crate::commands::export_settings::ExportSettings;
+use crate::commands::ffmpeg;
+use crate::commands::ffmpeg_export;
+use crate::commands::media::MEDIA_EXTENSIONS;
+use crate::commands::output_paths::{self, TemplateValues};
+use crate::commands::settings_store;
+use crate::commands::silence;
+use crate::commands::video_processing::VideoClip;
+
+// Watch folders: directories polled for new recordings, each of which is queued as
+// an export job running a fixed pipeline (normalize audio, trim silence, export) once
+// it has finished being written. The folder list is kept in the settings store.
+
+lazy_static::lazy_static! {
+    static ref WATCH_FOLDERS: Arc<Mutex<Vec<WatchFolder>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref SEEN_FILES: Arc<Mutex<HashMap<PathBuf, FileState>>> = Arc::new(Mutex::new(HashMap::new()));
+    static ref WATCH_LOG: Arc<Mutex<Vec<WatchLogEntry>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref WATCHER_RUNNING: AtomicBool = AtomicBool::new(false);
+}
+
+const WATCH_FOLDERS_KEY: &str = "watch_folders";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// A file counts as fully written once its size and mtime hold still this many polls in a row
+const STABLE_POLLS: u32 = 3;
+const MAX_LOG_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SilenceTrim {
+    pub threshold_db: Option<f64>,
+    pub min_duration: Option<f64>,
+    pub padding: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchPipeline {
+    pub normalize_lufs: Option<f64>,       // e.g. -16; None leaves the audio alone
+    pub trim_silence: Option<SilenceTrim>, // None keeps the whole recording
+    pub export_settings: Option<ExportSettings>, // None uses the "web" preset
+    pub output_dir: Option<String>,        // None uses the default export directory
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchFolder {
+    pub id: String,
+    pub directory: String,
+    pub pipeline: WatchPipeline,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchLogEntry {
+    pub folder_id: String,
+    pub file_path: String,
+    pub output_path: Option<String>,
+    pub success: bool,
+    pub message: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FileState {
+    Settling { size: u64, modified: Option<SystemTime>, stable_polls: u32 },
+    Done, // processed, or already there when the folder was added
+}
+
+/// Start watching a directory. Files already in it are left alone; only new ones are processed.
+#[command]
+pub fn add_watch_folder(app: AppHandle, directory: String, pipeline: WatchPipeline) -> Result<WatchFolder, String> {
+    let path = PathBuf::from(&directory);
+    if !path.is_dir() {
+        return Err(format!("Directory does not exist: {}", directory));
+    }
+    if let Some(dir) = &pipeline.output_dir {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("Output directory does not exist: {}", dir));
+        }
+    }
+    pipeline.export_settings.clone().unwrap_or_default().resolve()?;
+
+    let mut folders = WATCH_FOLDERS.lock().map_err(|e| e.to_string())?;
+    if folders.iter().any(|f| Path::new(&f.directory) == path) {
+        return Err(format!("Already watching {}", directory));
+    }
+
+    let folder = WatchFolder {
+        id: format!("watch_{}", chrono::Utc::now().timestamp_millis()),
+        directory,
+        pipeline,
+    };
+    folders.push(folder.clone());
+    settings_store::save(&app, WATCH_FOLDERS_KEY, &*folders)?;
+    mark_existing_files(&path);
+    println!("👀 Watching {} for new recordings", folder.directory);
+
+    start_watcher(&app);
+    Ok(folder)
+}
+
+#[command]
+pub fn remove_watch_folder(app: AppHandle, folder_id: String) -> Result<bool, String> {
+    let mut folders = WATCH_FOLDERS.lock().map_err(|e| e.to_string())?;
+    let before = folders.len();
+    folders.retain(|f| f.id != folder_id);
+    if folders.len() == before {
+        return Ok(false);
+    }
+    settings_store::save(&app, WATCH_FOLDERS_KEY, &*folders)?;
+    Ok(true)
+}
+
+#[command]
+pub fn list_watch_folders() -> Result<Vec<WatchFolder>, String> {
+    Ok(WATCH_FOLDERS.lock().map_err(|e| e.to_string())?.clone())
+}
+
+#[command]
+pub fn get_watch_log() -> Result<Vec<WatchLogEntry>, String> {
+    Ok(WATCH_LOG.lock().map_err(|e| e.to_string())?.clone())
+}
+
+/// Resume watching the saved folders at startup. As when a folder is added, files
+/// already there (including ones that arrived while the app was closed) are left alone.
+pub fn restore_folders(app: &AppHandle) {
+    let Some(saved) = settings_store::load::<Vec<WatchFolder>>(app, WATCH_FOLDERS_KEY) else {
+        return;
+    };
+    if saved.is_empty() {
+        return;
+    }
+    for folder in &saved {
+        mark_existing_files(Path::new(&folder.directory));
+        println!("👀 Watching {} for new recordings", folder.directory);
+    }
+    if let Ok(mut folders) = WATCH_FOLDERS.lock() {
+        *folders = saved;
+    }
+    start_watcher(app);
+}
+
+fn start_watcher(app: &AppHandle) {
+    if !WATCHER_RUNNING.swap(true, Ordering::SeqCst) {
+        let app = app.clone();
+        std::thread::spawn(move || watch_loop(app));
+    }
+}
+
+fn mark_existing_files(directory: &Path) {
+    if let Ok(mut seen) = SEEN_FILES.lock() {
+        for file in media_files(directory) {
+            seen.insert(file, FileState::Done);
+        }
+    }
+}
+
+fn media_files(directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        // Skip hidden files, which include our own partial outputs
+        .filter(|path| !path.file_name().and_then(|n| n.to_str()).unwrap_or(".").starts_with('.'))
+        .filter(|path| {
+            path.extension()
+                .map(|ext| MEDIA_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+fn watch_loop(app: AppHandle) {
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        let folders = match WATCH_FOLDERS.lock() {
+            Ok(folders) => folders.clone(),
+            Err(_) => continue,
+        };
+        for folder in folders {
+            for file in settled_files(&folder) {
+                process_file(&app, &folder, &file);
+            }
+        }
+    }
+}
+
+// Update the size/mtime tracking for a folder and return files that just finished settling
+fn settled_files(folder: &WatchFolder) -> Vec<PathBuf> {
+    let Ok(mut seen) = SEEN_FILES.lock() else {
+        return Vec::new();
+    };
+
+    let mut ready = Vec::new();
+    for file in media_files(Path::new(&folder.directory)) {
+        let Ok(metadata) = std::fs::metadata(&file) else {
+            continue;
+        };
+        let (size, modified) = (metadata.len(), metadata.modified().ok());
+
+        let next = match seen.get(&file) {
+            Some(FileState::Done) => continue,
+            Some(FileState::Settling { size: last_size, modified: last_modified, stable_polls })
+                if *last_size == size && *last_modified == modified && size > 0 =>
+            {
+                FileState::Settling { size, modified, stable_polls: stable_polls + 1 }
+            }
+            _ => FileState::Settling { size, modified, stable_polls: 0 },
+        };
+
+        if matches!(next, FileState::Settling { stable_polls, .. } if stable_polls >= STABLE_POLLS) {
+            seen.insert(file.clone(), FileState::Done);
+            ready.push(file);
+        } else {
+            seen.insert(file, next);
+        }
+    }
+    ready
+}
+
+fn process_file(app: &AppHandle, folder: &WatchFolder, file: &Path) {
+    let file_path = file.to_string_lossy().to_string();
+    println!("📥 New recording in watch folder: {}", file_path);
+    if let Err(e) = queue_pipeline(app, folder, &file_path) {
+        record_result(app, &folder.id, &file_path, Err(e));
+    }
+}
+
+// Queue the recording as an export job. Normalizing and trimming run on the export
+// worker when the job starts, so the poll loop never waits on ffmpeg.
+fn queue_pipeline(app: &AppHandle, folder: &WatchFolder, file_path: &str) -> Result<(), String> {
+    let pipeline = folder.pipeline.clone();
+    let stem = Path::new(file_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "recording".to_string());
+
+    let settings = pipeline
+        .export_settings
+        .clone()
+        .unwrap_or_else(|| ExportSettings { export_preset: Some("web".to_string()), ..ExportSettings::default() });
+    let encode = settings.resolve()?;
+    let output_path = match &pipeline.output_dir {
+        Some(dir) => {
+            let values = TemplateValues {
+                project: Some(stem.clone()),
+                resolution: Some(encode.frame.label()),
+                preset: settings.export_preset.clone(),
+                ext: encode.container.extension().to_string(),
+            };
+            let name = output_paths::render_template(&output_paths::output_config()?.template, &values)?;
+            Some(Path::new(dir).join(name).to_string_lossy().to_string())
+        }
+        None => None,
+    };
+    let normalized = pipeline.normalize_lufs.map(|_| {
+        std::env::temp_dir()
+            .join("vidveil")
+            .join(format!("normalized_{}_{}.mkv", stem, chrono::Utc::now().timestamp_millis()))
+            .to_string_lossy()
+            .to_string()
+    });
+
+    let prepare = {
+        let (file_path, normalized) = (file_path.to_string(), normalized.clone());
+        move || prepare_clips(&pipeline, &file_path, normalized.as_deref())
+    };
+    let on_finished = {
+        let (app, folder_id, file_path) = (app.clone(), folder.id.clone(), file_path.to_string());
+        move |job: &ExportJob| {
+            if let Some(temp) = &normalized {
+                let _ = std::fs::remove_file(temp);
+            }
+            let result = match job.targets.first() {
+                Some(target) if target.status == JobStatus::Completed => Ok(target.output_path.clone()),
+                Some(target) => Err(target.error.clone().unwrap_or_else(|| "Export cancelled".to_string())),
+                None => Err("Export job has no target".to_string()),
+            };
+            record_result(&app, &folder_id, &file_path, result);
+        }
+    };
+
+    let job = export_jobs::queue_export(
+        app.clone(),
+        vec![source_clip(file_path, 0.0)],
+        stem,
+        vec![BatchExportTarget { settings, output_path, label: None }],
+        JobHooks {
+            prepare: Some(Box::new(prepare)),
+            on_finished: Some(Box::new(on_finished)),
+        },
+    )?;
+
+    // Outputs written back into a watched folder must not be picked up again
+    if let Ok(mut seen) = SEEN_FILES.lock() {
+        for target in &job.targets {
+            seen.insert(PathBuf::from(&target.output_path), FileState::Done);
+        }
+    }
+    Ok(())
+}
+
+// Log, notify and tell the frontend how a recording's pipeline went
+fn record_result(app: &AppHandle, folder_id: &str, file_path: &str, result: Result<String, String>) {
+    let entry = WatchLogEntry {
+        folder_id: folder_id.to_string(),
+        file_path: file_path.to_string(),
+        output_path: result.as_ref().ok().cloned(),
+        success: result.is_ok(),
+        message: match &result {
+            Ok(_) => "Processed".to_string(),
+            Err(e) => e.clone(),
+        },
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let file_name = Path::new(file_path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (title, body) = match &result {
+        Ok(output_path) => ("Recording processed", format!("{} → {}", file_name, output_path)),
+        Err(e) => ("Recording failed to process", format!("{}: {}", file_name, e)),
+    };
+    match &result {
+        Ok(_) => println!("✅ Watch folder pipeline finished: {}", body),
+        Err(_) => println!("❌ Watch folder pipeline failed: {}", body),
+    }
+    let _ = app.notification().builder().title(title).body(&body).show();
+    let _ = app.emit("watch-folder-event", entry.clone());
+
+    if let Ok(mut log) = WATCH_LOG.lock() {
+        log.push(entry);
+        let overflow = log.len().saturating_sub(MAX_LOG_ENTRIES);
+        log.drain(..overflow);
+    }
+}
+
+// Normalize (into `normalized`) and trim one recording, returning the clips to export
+fn prepare_clips(pipeline: &WatchPipeline, file_path: &str, normalized: Option<&str>) -> Result<Vec<VideoClip>, String> {
+    let mut source = file_path.to_string();
+    if let (Some(target_lufs), Some(temp)) = (pipeline.normalize_lufs, normalized) {
+        if let Some(parent) = Path::new(temp).parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+        }
+        ffmpeg_export::normalize_loudness(&source, temp, target_lufs)?;
+        source = temp.to_string();
+    }
+
+    let duration = ffmpeg::parse_duration(&ffmpeg::probe(&source)?)
+        .ok_or_else(|| format!("Could not read the duration of {}", source))?;
+    let clip = source_clip(&source, duration);
+    match &pipeline.trim_silence {
+        Some(trim) => {
+            let analysis = silence::analyze_silence(source.clone(), trim.threshold_db, trim.min_duration, trim.padding)?;
+            silence::apply_silence_cuts(clip, analysis.cuts)
+        }
+        None => Ok(vec![clip]),
+    }
+}
+
+fn source_clip(path: &str, duration: f64) -> VideoClip {
+    VideoClip {
+        path: path.to_string(),
+        start_time: 0.0,
+        duration,
+        source_offset: 0.0,
+        clip_type: "screen".to_string(),
+        pip_config: None,
+        link_id: None,
+        input_overlay: None,
+        auto_zoom: None,
+        redactions: Vec::new(),
+        layer: None,
+    }
+}
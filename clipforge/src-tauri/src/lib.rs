@@ -1,12 +1,5 @@
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 
-use tauri::{
-	Emitter,
-	Manager,
-	menu::{Menu, MenuItem},
-	tray::{MouseButton, TrayIconBuilder, TrayIconEvent}
-};
-
 pub mod commands;
 
 pub fn run() {
@@ -37,54 +30,66 @@ pub fn run() {
 			crate::commands::video_processing::cancel_export,
 			crate::commands::video_processing::get_video_info,
 			crate::commands::video_processing::save_blob_to_temp_file,
-			crate::commands::video_processing::reveal_file_in_finder
+			crate::commands::video_processing::reveal_file_in_finder,
+			crate::commands::silence::detect_silence,
+			crate::commands::silence::apply_silence_cuts,
+			crate::commands::captions::import_captions,
+			crate::commands::captions::export_captions,
+			crate::commands::captions::trim_caption_track,
+			crate::commands::captions::render_captions,
+			crate::commands::project::load_project,
+			crate::commands::project::save_project,
+			crate::commands::transcription::configure_transcription,
+			crate::commands::transcription::get_transcription_config,
+			crate::commands::transcription::transcribe_media,
+			crate::commands::transcription::get_transcript,
+			crate::commands::transcription::search_transcript,
+			crate::commands::transcription::transcript_to_captions,
+			crate::commands::export_jobs::start_batch_export,
+			crate::commands::export_jobs::get_export_job,
+			crate::commands::export_jobs::list_export_jobs,
+			crate::commands::export_jobs::cancel_export_job,
+			crate::commands::output_paths::configure_output,
+			crate::commands::output_paths::get_output_config,
+			crate::commands::watch_folders::add_watch_folder,
+			crate::commands::watch_folders::remove_watch_folder,
+			crate::commands::watch_folders::list_watch_folders,
+			crate::commands::watch_folders::get_watch_log,
+			crate::commands::recorder::get_recorder_state,
+			crate::commands::control_api::start_control_api,
+			crate::commands::control_api::stop_control_api,
+			crate::commands::control_api::get_control_api_status,
+			crate::commands::shortcuts::get_shortcuts,
+			crate::commands::shortcuts::rebind_shortcut,
+			crate::commands::shortcuts::reset_shortcuts,
+			crate::commands::recorder::pause_recording,
+			crate::commands::recorder::resume_recording,
+			crate::commands::recorder::report_recording,
+			crate::commands::recorder::cancel_pending_recording,
+			crate::commands::scheduled_recording::schedule_recording,
+			crate::commands::markers::add_recording_marker,
+			crate::commands::markers::get_recording_markers,
+			crate::commands::markers::save_recording_markers,
+			crate::commands::iso_recording::start_iso_recording,
+			crate::commands::iso_recording::stop_iso_recording,
+			crate::commands::iso_recording::import_recording_bundle,
+			crate::commands::audio_devices::list_audio_devices,
+			crate::commands::audio_meter::start_audio_monitor,
+			crate::commands::audio_meter::stop_audio_monitor,
+			crate::commands::video_devices::list_video_devices,
+			crate::commands::input_events::get_input_events,
+			crate::commands::auto_zoom::get_zoom_keyframes,
+			crate::commands::redaction::list_redactions,
+			crate::commands::redaction::save_redaction,
+			crate::commands::redaction::remove_redaction
 		])
 		.setup(|app| {
-			// Create tray icon
-			let show_i = MenuItem::with_id(app, "show", "Show VidVeil", true, None::<&str>)?;
-			let stop_i = MenuItem::with_id(app, "stop_recording", "Stop Recording", true, None::<&str>)?;
-			// Keep stop button enabled - it will emit event that frontend can handle appropriately
-			let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-			let menu = Menu::with_items(app, &[&show_i, &stop_i, &quit_i])?;
+			crate::commands::tray::create_tray(app.handle())?;
 
-			let _tray = TrayIconBuilder::with_id("default")
-				.menu(&menu)
-				.show_menu_on_left_click(true)
-				.icon(app.default_window_icon().unwrap().clone())
-				.tooltip("VidVeil - Screen Recorder")
-				.on_menu_event(move |app, event| {
-					match event.id.as_ref() {
-						"show" => {
-							if let Some(window) = app.get_webview_window("main") {
-								let _ = window.show();
-								let _ = window.unminimize();
-								let _ = window.set_focus();
-							}
-						}
-						"stop_recording" => {
-							// Emit event to frontend to stop recording
-							if let Some(window) = app.get_webview_window("main") {
-								let _ = window.emit("tray-stop-recording", ());
-							}
-						}
-						"quit" => {
-							app.exit(0);
-						}
-						_ => {}
-					}
-				})
-				.on_tray_icon_event(move |_tray, event| {
-					// Handle tray icon clicks - restore window on left click
-					if let TrayIconEvent::Click {
-						button: MouseButton::Left,
-						button_state: _,
-						..
-					} = event
-					{
-						// Note: App handle not available in tray event, so we'll handle this from frontend
-					}
-				})
-				.build(app)?;
+			crate::commands::shortcuts::follow_recorder(app.handle());
+			crate::commands::transcription::restore_config(app.handle());
+			crate::commands::output_paths::restore_config(app.handle());
+			crate::commands::watch_folders::restore_folders(app.handle());
 
 			Ok(())
 		})
@@ -93,7 +98,11 @@ pub fn run() {
 		.plugin(tauri_plugin_os::init())
 		.plugin(tauri_plugin_fs::init())
 		.plugin(tauri_plugin_store::Builder::new().build())
-		.plugin(tauri_plugin_global_shortcut::Builder::new().build())
+		.plugin(
+			tauri_plugin_global_shortcut::Builder::new()
+				.with_handler(crate::commands::shortcuts::handle_shortcut)
+				.build()
+		)
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
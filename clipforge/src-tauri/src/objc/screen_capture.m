@@ -96,11 +96,15 @@ bool request_camera_permission_sync() {
 CaptureResult start_screen_recording_objc(
     uint32_t display_id,
     bool include_audio,
+    int audio_device_index,
     bool include_webcam,
     int pip_shape __attribute__((unused)),
     float pip_x_percent __attribute__((unused)),
     float pip_y_percent __attribute__((unused)),
     float pip_size_percent __attribute__((unused)),
+    uint32_t fps,
+    bool capture_cursor,
+    bool capture_clicks,
     const char* output_path
 ) {
     CaptureResult result;
@@ -218,14 +222,17 @@ CaptureResult start_screen_recording_objc(
             NSLog(@"✅ Screen input created successfully");
             
             // Set screen input properties
-            screenInput.capturesCursor = YES;
-            screenInput.capturesMouseClicks = YES;
+            screenInput.capturesCursor = capture_cursor;
+            screenInput.capturesMouseClicks = capture_clicks;
             
             // CRITICAL: Set minimum frame rate for screen capture
             // Without this, AVFoundation may not capture any frames
-            screenInput.minFrameDuration = CMTimeMake(1, 30); // 30 fps minimum
+            if (fps == 0) {
+                fps = 30;
+            }
+            screenInput.minFrameDuration = CMTimeMake(1, (int32_t)fps);
             
-            NSLog(@"✅ Screen input configured: cursor=%d, clicks=%d, fps=30", YES, YES);
+            NSLog(@"✅ Screen input configured: cursor=%d, clicks=%d, fps=%u", capture_cursor, capture_clicks, fps);
             
             // Add screen input to session
             if ([g_session canAddInput:screenInput]) {
@@ -260,9 +267,21 @@ CaptureResult start_screen_recording_objc(
                 }
             }
             
-            // Add audio input if requested
+            // Add audio input if requested: the device at audio_device_index in the
+            // audio device list (the order ffmpeg lists them in), or the default one at -1
             if (include_audio) {
-                AVCaptureDevice* audioDevice = [AVCaptureDevice defaultDeviceWithMediaType:AVMediaTypeAudio];
+                AVCaptureDevice* audioDevice = nil;
+                if (audio_device_index >= 0) {
+                    NSArray<AVCaptureDevice*>* audioDevices = [AVCaptureDevice devicesWithMediaType:AVMediaTypeAudio];
+                    if ((NSUInteger)audio_device_index >= audioDevices.count) {
+                        snprintf(result.error_message, sizeof(result.error_message), "Audio device not found: %d", audio_device_index);
+                        g_session = nil;
+                        return result;
+                    }
+                    audioDevice = audioDevices[audio_device_index];
+                } else {
+                    audioDevice = [AVCaptureDevice defaultDeviceWithMediaType:AVMediaTypeAudio];
+                }
                 if (audioDevice != nil) {
                     NSError* audioError = nil;
                     AVCaptureDeviceInput* audioInput = [[AVCaptureDeviceInput alloc] initWithDevice:audioDevice error:&audioError];
@@ -540,6 +559,45 @@ CaptureResult stop_screen_recording_objc() {
     }
 }
 
+// Pause or resume the open recording; the movie file simply skips the paused stretch
+CaptureResult set_recording_paused_objc(bool paused) {
+    CaptureResult result;
+    result.success = false;
+    memset(result.error_message, 0, sizeof(result.error_message));
+    
+    @autoreleasepool {
+        @try {
+            if (g_output == nil || ![g_output isRecording]) {
+                strncpy(result.error_message, "No recording in progress", sizeof(result.error_message) - 1);
+                return result;
+            }
+            
+            if (paused) {
+                NSLog(@"⏸️ Pausing recording");
+                [g_output pauseRecording];
+                if (g_webcam_output != nil) {
+                    [g_webcam_output pauseRecording];
+                }
+            } else {
+                NSLog(@"▶️ Resuming recording");
+                [g_output resumeRecording];
+                if (g_webcam_output != nil) {
+                    [g_webcam_output resumeRecording];
+                }
+            }
+            
+            result.success = true;
+            return result;
+            
+        } @catch (NSException* exception) {
+            const char* reason = [[exception reason] UTF8String];
+            snprintf(result.error_message, sizeof(result.error_message), 
+                    "Objective-C exception: %s", reason);
+            return result;
+        }
+    }
+}
+
 // Check if currently recording
 bool is_recording_objc() {
     return (g_session != nil && g_output != nil && [g_session isRunning]);